@@ -0,0 +1,61 @@
+//! Compare `Sha256Hasher::hash_leaf_many` with hashing leaves one at a time.
+//!
+//! `Sha256Hasher` picks its batch backend from the CPU, so the AVX2
+//! multi-buffer backend is also measured directly through
+//! `MultiBufferSha256Hasher` where the CPU supports it.
+//!
+//! Run with `cargo bench --bench sha256_batch`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use merkle_trees::{Hasher, Sha256Hasher};
+use std::hint::black_box;
+
+const LEAF_SIZES: [usize; 3] = [32, 256, 1024];
+const BATCH: usize = 1024;
+
+fn leaves(size: usize) -> Vec<Vec<u8>> {
+    (0..BATCH).map(|i| vec![i as u8; size]).collect()
+}
+
+fn bench_hash_leaf_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sha256_hash_leaf_many");
+    for size in LEAF_SIZES {
+        let data = leaves(size);
+        let inputs: Vec<&[u8]> = data.iter().map(|leaf| leaf.as_slice()).collect();
+        group.throughput(Throughput::Bytes((BATCH * size) as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("scalar_loop", size),
+            &inputs,
+            |b, inputs| {
+                let hasher = Sha256Hasher::new();
+                b.iter(|| {
+                    inputs
+                        .iter()
+                        .map(|leaf| hasher.hash_leaf(black_box(leaf)))
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("batch", size), &inputs, |b, inputs| {
+            let hasher = Sha256Hasher::new();
+            b.iter(|| hasher.hash_leaf_many(black_box(inputs)))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            group.bench_with_input(
+                BenchmarkId::new("multi_buffer", size),
+                &inputs,
+                |b, inputs| {
+                    let hasher = merkle_trees::hasher::MultiBufferSha256Hasher;
+                    b.iter(|| hasher.hash_leaf_many(black_box(inputs)))
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash_leaf_many);
+criterion_main!(benches);
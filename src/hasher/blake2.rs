@@ -0,0 +1,137 @@
+use super::{HashId, Hasher};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Blake2s256, Digest};
+
+/// BLAKE2b hasher with a 256-bit output, using the `blake2` crate.
+///
+/// This is BLAKE2b-256 (the output length is part of the parameter block),
+/// not a truncated BLAKE2b-512. Requires the `blake2` feature.
+#[derive(Clone)]
+pub struct Blake2bHasher;
+
+impl Default for Blake2bHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blake2bHasher {
+    pub fn new() -> Self {
+        Blake2bHasher
+    }
+}
+
+impl Hasher for Blake2bHasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::BLAKE2B_256)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        Blake2b::<U32>::digest(data).into()
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Blake2b::<U32>::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// BLAKE2s hasher using the `blake2` crate.
+///
+/// Produces a 32-byte array (256 bits), the native BLAKE2s output length.
+/// Requires the `blake2` feature.
+#[derive(Clone)]
+pub struct Blake2sHasher;
+
+impl Default for Blake2sHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blake2sHasher {
+    pub fn new() -> Self {
+        Blake2sHasher
+    }
+}
+
+impl Hasher for Blake2sHasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::BLAKE2S_256)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        Blake2s256::digest(data).into()
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Blake2s256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blake2b_known_hash() {
+        let hasher = Blake2bHasher::new();
+        // "hello" BLAKE2b-256 hash
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0x32, 0x4d, 0xcf, 0x02, 0x7d, 0xd4, 0xa3, 0x0a, 0x93, 0x2c, 0x44, 0x1f, 0x36, 0x5a,
+            0x25, 0xe8, 0x6b, 0x17, 0x3d, 0xef, 0xa4, 0xb8, 0xe5, 0x89, 0x48, 0x25, 0x34, 0x71,
+            0xb8, 0x1b, 0x72, 0xcf,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_blake2s_known_hash() {
+        let hasher = Blake2sHasher::new();
+        // "hello" BLAKE2s-256 hash
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0x19, 0x21, 0x3b, 0xac, 0xc5, 0x8d, 0xee, 0x6d, 0xbd, 0xe3, 0xce, 0xb9, 0xa4, 0x7c,
+            0xbb, 0x33, 0x0b, 0x3d, 0x86, 0xf8, 0xcc, 0xa8, 0x99, 0x7e, 0xb0, 0x0b, 0xe4, 0x56,
+            0xf1, 0x40, 0xca, 0x25,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_blake2b_and_blake2s_differ() {
+        let hash_b = Blake2bHasher::new().hash_bytes(b"hello");
+        let hash_s = Blake2sHasher::new().hash_bytes(b"hello");
+        assert_ne!(hash_b, hash_s);
+    }
+
+    #[test]
+    fn test_different_inputs_different_hashes() {
+        let hasher = Blake2bHasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+        let hasher = Blake2sHasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: Blake2bHasher = Default::default();
+        assert_eq!(hasher.hash_bytes(b"test").len(), 32);
+        let hasher: Blake2sHasher = Default::default();
+        assert_eq!(hasher.hash_bytes(b"test").len(), 32);
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Blake2bHasher::new());
+        crate::hasher::tests::assert_hash_parts_matches(&Blake2sHasher::new());
+    }
+}
@@ -0,0 +1,96 @@
+use super::{HashId, Hasher};
+
+/// BLAKE3 hasher using the `blake3` crate.
+///
+/// Produces a 32-byte array (256 bits), the default BLAKE3 output length.
+/// Requires the `blake3` feature.
+#[derive(Clone)]
+pub struct Blake3Hasher;
+
+impl Default for Blake3Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blake3Hasher {
+    pub fn new() -> Self {
+        Blake3Hasher
+    }
+}
+
+impl Hasher for Blake3Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::BLAKE3)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        blake3::hash(data).into()
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blake3_hasher_length() {
+        let hasher = Blake3Hasher::new();
+        let hash = hasher.hash_bytes(b"hello");
+        assert_eq!(hash.len(), 32);
+    }
+
+    #[test]
+    fn test_different_inputs_different_hashes() {
+        let hasher = Blake3Hasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn test_known_hash() {
+        let hasher = Blake3Hasher::new();
+        // "hello" BLAKE3 hash
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0xea, 0x8f, 0x16, 0x3d, 0xb3, 0x86, 0x82, 0x92, 0x5e, 0x44, 0x91, 0xc5, 0xe5, 0x8d,
+            0x4b, 0xb3, 0x50, 0x6e, 0xf8, 0xc1, 0x4e, 0xb7, 0x8a, 0x86, 0xe9, 0x08, 0xc5, 0x62,
+            0x4a, 0x67, 0x20, 0x0f,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_known_hash_empty_input() {
+        let hasher = Blake3Hasher::new();
+        // Empty input vector from the BLAKE3 specification
+        let hash = hasher.hash_bytes(b"");
+        let expected: [u8; 32] = [
+            0xaf, 0x13, 0x49, 0xb9, 0xf5, 0xf9, 0xa1, 0xa6, 0xa0, 0x40, 0x4d, 0xea, 0x36, 0xdc,
+            0xc9, 0x49, 0x9b, 0xcb, 0x25, 0xc9, 0xad, 0xc1, 0x12, 0xb7, 0xcc, 0x9a, 0x93, 0xca,
+            0xe4, 0x1f, 0x32, 0x62,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: Blake3Hasher = Default::default();
+        assert_eq!(
+            hasher.hash_bytes(b"test"),
+            Blake3Hasher::new().hash_bytes(b"test")
+        );
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Blake3Hasher::new());
+    }
+}
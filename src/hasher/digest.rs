@@ -0,0 +1,155 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::Hasher;
+use sha2::Digest;
+
+/// Adapter implementing `Hasher` for any RustCrypto `Digest`.
+///
+/// Output sizes other than 32 bytes are mapped to the 32-byte `Hasher`
+/// output as follows:
+///
+/// - longer digests are truncated to their first 32 bytes
+///   (e.g. SHA-512 becomes the first half of its output);
+/// - shorter digests are padded on the right with zero bytes
+///   (e.g. SHA-224 or RIPEMD-160).
+///
+/// Note that truncating is not the same as a dedicated 256-bit variant:
+/// `DigestHasher<Sha512>` differs from SHA-512/256, which uses its own
+/// initial state.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::hasher::DigestHasher;
+/// use merkle_trees::{Hasher, MerkleTree, SimpleMerkleTree};
+///
+/// type Sha512Hasher = DigestHasher<sha2::Sha512>;
+///
+/// let mut tree = SimpleMerkleTree::new(Sha512Hasher::new());
+/// tree.add_leaf(b"hello").unwrap();
+/// assert!(tree.get_root().is_some());
+/// ```
+pub struct DigestHasher<D> {
+    _digest: PhantomData<fn() -> D>,
+}
+
+impl<D: Digest> DigestHasher<D> {
+    pub fn new() -> Self {
+        DigestHasher {
+            _digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Digest> Default for DigestHasher<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Manual impls so that `D` itself does not need to be `Clone` or `Debug`
+impl<D> Clone for DigestHasher<D> {
+    fn clone(&self) -> Self {
+        DigestHasher {
+            _digest: PhantomData,
+        }
+    }
+}
+
+impl<D> fmt::Debug for DigestHasher<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestHasher").finish()
+    }
+}
+
+impl<D: Digest> Hasher for DigestHasher<D> {
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        fit_output(&D::digest(data))
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut digest = D::new();
+        for part in parts {
+            digest.update(part);
+        }
+        fit_output(&digest.finalize())
+    }
+}
+
+/// Truncate or zero-pad a digest output to 32 bytes.
+fn fit_output(output: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    let len = output.len().min(32);
+    hash[..len].copy_from_slice(&output[..len]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+
+    #[test]
+    fn test_sha256_matches_sha256_hasher() {
+        let hasher = DigestHasher::<sha2::Sha256>::new();
+        assert_eq!(
+            hasher.hash_bytes(b"hello"),
+            Sha256Hasher::new().hash_bytes(b"hello")
+        );
+    }
+
+    #[test]
+    fn test_longer_output_is_truncated() {
+        let hasher = DigestHasher::<sha2::Sha512>::new();
+        // First 32 bytes of the "hello" SHA-512 hash
+        let expected: [u8; 32] = [
+            0x9b, 0x71, 0xd2, 0x24, 0xbd, 0x62, 0xf3, 0x78, 0x5d, 0x96, 0xd4, 0x6a, 0xd3, 0xea,
+            0x3d, 0x73, 0x31, 0x9b, 0xfb, 0xc2, 0x89, 0x0c, 0xaa, 0xda, 0xe2, 0xdf, 0xf7, 0x25,
+            0x19, 0x67, 0x3c, 0xa7,
+        ];
+        assert_eq!(hasher.hash_bytes(b"hello"), expected);
+    }
+
+    #[test]
+    fn test_shorter_output_is_zero_padded() {
+        let hasher = DigestHasher::<sha2::Sha224>::new();
+        // "hello" SHA-224 hash followed by 4 zero bytes
+        let expected: [u8; 32] = [
+            0xea, 0x09, 0xae, 0x9c, 0xc6, 0x76, 0x8c, 0x50, 0xfc, 0xee, 0x90, 0x3e, 0xd0, 0x54,
+            0x55, 0x6e, 0x5b, 0xfc, 0x83, 0x47, 0x90, 0x7f, 0x12, 0x59, 0x8a, 0xa2, 0x41, 0x93,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(hasher.hash_bytes(b"hello"), expected);
+    }
+
+    #[test]
+    fn test_truncated_sha512_differs_from_sha512_256() {
+        let truncated = DigestHasher::<sha2::Sha512>::new().hash_bytes(b"hello");
+        let native = DigestHasher::<sha2::Sha512_256>::new().hash_bytes(b"hello");
+        assert_ne!(truncated, native);
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&DigestHasher::<sha2::Sha256>::new());
+        crate::hasher::tests::assert_hash_parts_matches(&DigestHasher::<sha2::Sha512>::new());
+        crate::hasher::tests::assert_hash_parts_matches(&DigestHasher::<sha2::Sha224>::new());
+    }
+
+    #[cfg(feature = "ripemd")]
+    #[test]
+    fn test_ripemd160_matches_ripemd160_hasher() {
+        let hasher = DigestHasher::<ripemd::Ripemd160>::new();
+        assert_eq!(
+            hasher.hash_bytes(b"hello"),
+            crate::hasher::Ripemd160Hasher::new().hash_bytes(b"hello")
+        );
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: DigestHasher<sha2::Sha256> = Default::default();
+        assert_eq!(hasher.hash_bytes(b"test").len(), 32);
+    }
+}
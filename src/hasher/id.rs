@@ -0,0 +1,152 @@
+use crate::merkle::codec::{Reader, put_varint};
+
+/// Identifies the hash algorithm behind a root or proof.
+///
+/// The value is a multicodec code, so a root can be written as a
+/// multihash: `varint(code) || varint(32) || root`. Hashers without a
+/// registered code use the multicodec private-use range (`0x300000` and
+/// up). Roots are always 32 bytes, so digests shorter than that (such as
+/// RIPEMD-160) appear zero-padded.
+///
+/// With the `serde` feature, an identifier serializes as its numeric code.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::hasher::HashId;
+///
+/// let multihash = HashId::SHA2_256.multihash(&[7u8; 32]);
+/// assert_eq!(&multihash[..2], &[0x12, 0x20]);
+/// assert_eq!(HashId::parse_multihash(&multihash), Some((HashId::SHA2_256, [7u8; 32])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct HashId(u64);
+
+impl HashId {
+    /// SHA-256 (`sha2-256`).
+    pub const SHA2_256: HashId = HashId(0x12);
+    /// SHA3-256 (`sha3-256`).
+    pub const SHA3_256: HashId = HashId(0x16);
+    /// BLAKE3 with a 32-byte output (`blake3`).
+    pub const BLAKE3: HashId = HashId(0x1e);
+    /// SHA-512/256 (`sha2-512-256`).
+    pub const SHA2_512_256: HashId = HashId(0x1015);
+    /// RIPEMD-160 (`ripemd-160`), zero-padded to 32 bytes.
+    pub const RIPEMD_160: HashId = HashId(0x1053);
+    /// BLAKE2b with a 32-byte output (`blake2b-256`).
+    pub const BLAKE2B_256: HashId = HashId(0xb220);
+    /// BLAKE2s with a 32-byte output (`blake2s-256`).
+    pub const BLAKE2S_256: HashId = HashId(0xb260);
+    /// The crate's `SimpleHasher` (private use).
+    pub const SIMPLE: HashId = HashId(0x300000);
+    /// Poseidon over BN254 with the circomlib parameters (private use).
+    pub const POSEIDON_BN254: HashId = HashId(0x300001);
+
+    /// Create an identifier from a multicodec code.
+    pub const fn new(code: u64) -> Self {
+        HashId(code)
+    }
+
+    /// Returns the multicodec code.
+    pub const fn code(&self) -> u64 {
+        self.0
+    }
+
+    /// Encode a 32-byte digest as a multihash.
+    pub fn multihash(&self, digest: &[u8; 32]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(digest.len() + 4);
+        put_varint(&mut out, self.0);
+        put_varint(&mut out, digest.len() as u64);
+        out.extend_from_slice(digest);
+        out
+    }
+
+    /// Decode a multihash of a 32-byte digest.
+    ///
+    /// Returns `None` if the input is malformed, has trailing bytes, or the
+    /// digest is not 32 bytes long.
+    pub fn parse_multihash(bytes: &[u8]) -> Option<(HashId, [u8; 32])> {
+        let mut reader = Reader::new(bytes);
+        let code = reader.varint()?;
+        if reader.varint()? != 32 {
+            return None;
+        }
+        let digest = reader.hash()?;
+        reader.is_empty().then_some((HashId(code), digest))
+    }
+}
+
+impl std::fmt::Display for HashId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            HashId::SHA2_256 => "sha2-256",
+            HashId::SHA3_256 => "sha3-256",
+            HashId::BLAKE3 => "blake3",
+            HashId::SHA2_512_256 => "sha2-512-256",
+            HashId::RIPEMD_160 => "ripemd-160",
+            HashId::BLAKE2B_256 => "blake2b-256",
+            HashId::BLAKE2S_256 => "blake2s-256",
+            HashId::SIMPLE => "simple",
+            HashId::POSEIDON_BN254 => "poseidon-bn254",
+            HashId(code) => return write!(f, "0x{:x}", code),
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multihash_round_trip() {
+        for id in [HashId::SHA2_256, HashId::BLAKE2B_256, HashId::SIMPLE] {
+            let multihash = id.multihash(&[3u8; 32]);
+            assert_eq!(HashId::parse_multihash(&multihash), Some((id, [3u8; 32])));
+        }
+    }
+
+    #[test]
+    fn test_multihash_known_prefix() {
+        // blake2b-256 is the two-byte varint 0xa0 0xe4 0x02
+        let multihash = HashId::BLAKE2B_256.multihash(&[0u8; 32]);
+        assert_eq!(&multihash[..4], &[0xa0, 0xe4, 0x02, 0x20]);
+        assert_eq!(multihash.len(), 36);
+    }
+
+    #[test]
+    fn test_multihash_sha2_512_256_prefix() {
+        // sha2-512-256 is 0x1015 (0x1014 is sha2-512-224), varint 0x95 0x20
+        let multihash = HashId::SHA2_512_256.multihash(&[0u8; 32]);
+        assert_eq!(&multihash[..3], &[0x95, 0x20, 0x20]);
+        assert_eq!(multihash.len(), 35);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        let multihash = HashId::SHA2_256.multihash(&[1u8; 32]);
+        assert_eq!(HashId::parse_multihash(&multihash[..33]), None);
+
+        let mut trailing = multihash.clone();
+        trailing.push(0);
+        assert_eq!(HashId::parse_multihash(&trailing), None);
+
+        // A 20-byte digest is not a tree root
+        let mut short = vec![0x12, 20];
+        short.extend_from_slice(&[0u8; 20]);
+        assert_eq!(HashId::parse_multihash(&short), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(HashId::SHA2_256.to_string(), "sha2-256");
+        assert_eq!(HashId::new(0x300042).to_string(), "0x300042");
+        assert_eq!(HashId::new(0x12), HashId::SHA2_256);
+        assert_eq!(HashId::SIMPLE.code(), 0x300000);
+    }
+}
@@ -0,0 +1,279 @@
+#[cfg(feature = "blake2")]
+use blake2::digest::consts::U32;
+#[cfg(feature = "blake2")]
+use blake2::{Blake2bMac, Blake2sMac};
+#[cfg(feature = "hmac")]
+use hmac::Hmac;
+#[cfg(feature = "hmac")]
+use sha2::Sha256;
+#[cfg(any(feature = "hmac", feature = "blake2"))]
+use sha2::digest::{KeyInit, Mac};
+
+use super::Hasher;
+#[cfg(feature = "blake2")]
+use crate::merkle::MerkleTreeError;
+
+/// The keyed primitive behind a `KeyedHasher`, holding its keyed state.
+///
+/// States are boxed as they differ widely in size (BLAKE3 keeps a stack of
+/// chaining values).
+#[derive(Clone)]
+enum KeyedMode {
+    #[cfg(feature = "hmac")]
+    HmacSha256(Box<Hmac<Sha256>>),
+    #[cfg(feature = "blake2")]
+    Blake2b(Box<Blake2bMac<U32>>),
+    #[cfg(feature = "blake2")]
+    Blake2s(Box<Blake2sMac<U32>>),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// A hasher keyed with a secret, so that hashes (and therefore roots and
+/// proofs) can only be produced and checked by holders of the key.
+///
+/// Trees built with different keys share no hashes: a proof from one
+/// tenant's tree never verifies against another tenant's root. The keyed
+/// state is prepared once and cloned for each hash.
+///
+/// Available modes depend on cargo features:
+///
+/// - `hmac`: HMAC-SHA256 with a key of any length
+/// - `blake2`: keyed BLAKE2b-256 with a key of at most 64 bytes, or keyed
+///   BLAKE2s-256 with a key of at most 32 bytes
+/// - `blake3`: BLAKE3 keyed mode with a 32-byte key
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "hmac")] {
+/// use merkle_trees::hasher::KeyedHasher;
+/// use merkle_trees::{MerkleTree, SimpleMerkleTree};
+///
+/// let mut tree = SimpleMerkleTree::new(KeyedHasher::hmac_sha256(b"tenant-a secret"));
+/// tree.add_leaf(b"hello").unwrap();
+/// assert!(tree.get_root().is_some());
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct KeyedHasher {
+    mode: KeyedMode,
+}
+
+impl KeyedHasher {
+    /// Create an HMAC-SHA256 hasher. Keys of any length are accepted.
+    #[cfg(feature = "hmac")]
+    pub fn hmac_sha256(key: &[u8]) -> Self {
+        let mac = <Hmac<Sha256> as KeyInit>::new_from_slice(key)
+            .expect("HMAC accepts keys of any length");
+        KeyedHasher {
+            mode: KeyedMode::HmacSha256(Box::new(mac)),
+        }
+    }
+
+    /// Create a keyed BLAKE2b-256 hasher.
+    ///
+    /// Returns `MerkleTreeError::InvalidKeyLength` if the key is longer than
+    /// 64 bytes.
+    #[cfg(feature = "blake2")]
+    pub fn blake2b(key: &[u8]) -> Result<Self, MerkleTreeError> {
+        let mac = <Blake2bMac<U32> as KeyInit>::new_from_slice(key).map_err(|_| {
+            MerkleTreeError::InvalidKeyLength {
+                length: key.len(),
+                max: 64,
+            }
+        })?;
+        Ok(KeyedHasher {
+            mode: KeyedMode::Blake2b(Box::new(mac)),
+        })
+    }
+
+    /// Create a keyed BLAKE2s-256 hasher.
+    ///
+    /// Returns `MerkleTreeError::InvalidKeyLength` if the key is longer than
+    /// 32 bytes.
+    #[cfg(feature = "blake2")]
+    pub fn blake2s(key: &[u8]) -> Result<Self, MerkleTreeError> {
+        let mac = <Blake2sMac<U32> as KeyInit>::new_from_slice(key).map_err(|_| {
+            MerkleTreeError::InvalidKeyLength {
+                length: key.len(),
+                max: 32,
+            }
+        })?;
+        Ok(KeyedHasher {
+            mode: KeyedMode::Blake2s(Box::new(mac)),
+        })
+    }
+
+    /// Create a hasher using the BLAKE3 keyed mode.
+    #[cfg(feature = "blake3")]
+    pub fn blake3(key: &[u8; 32]) -> Self {
+        KeyedHasher {
+            mode: KeyedMode::Blake3(Box::new(blake3::Hasher::new_keyed(key))),
+        }
+    }
+}
+
+impl Hasher for KeyedHasher {
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        self.hash_parts(&[data])
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        match &self.mode {
+            #[cfg(feature = "hmac")]
+            KeyedMode::HmacSha256(mac) => {
+                let mut mac = mac.as_ref().clone();
+                for part in parts {
+                    mac.update(part);
+                }
+                mac.finalize().into_bytes().into()
+            }
+            #[cfg(feature = "blake2")]
+            KeyedMode::Blake2b(mac) => {
+                let mut mac = mac.as_ref().clone();
+                for part in parts {
+                    mac.update(part);
+                }
+                mac.finalize().into_bytes().into()
+            }
+            #[cfg(feature = "blake2")]
+            KeyedMode::Blake2s(mac) => {
+                let mut mac = mac.as_ref().clone();
+                for part in parts {
+                    mac.update(part);
+                }
+                mac.finalize().into_bytes().into()
+            }
+            #[cfg(feature = "blake3")]
+            KeyedMode::Blake3(hasher) => {
+                let mut hasher = hasher.as_ref().clone();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_hmac_sha256_known_hash() {
+        // RFC 4231, test case 2
+        let hasher = KeyedHasher::hmac_sha256(b"Jefe");
+        let expected: [u8; 32] = [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ];
+        assert_eq!(hasher.hash_bytes(b"what do ya want for nothing?"), expected);
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn test_blake2b_known_hash() {
+        let hasher = KeyedHasher::blake2b(b"tenant-key").unwrap();
+        // "hello" keyed BLAKE2b-256 hash
+        let expected: [u8; 32] = [
+            0x93, 0xa2, 0x95, 0x65, 0xf0, 0x38, 0xbd, 0x4c, 0x1b, 0x4a, 0x13, 0x22, 0xfe, 0xa7,
+            0x57, 0xff, 0x32, 0xe4, 0x54, 0xfa, 0x93, 0x6e, 0xb6, 0x5a, 0x3f, 0xe6, 0x91, 0xe9,
+            0x79, 0x5b, 0x5b, 0x1d,
+        ];
+        assert_eq!(hasher.hash_bytes(b"hello"), expected);
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn test_blake2b_rejects_long_key() {
+        assert_eq!(
+            KeyedHasher::blake2b(&[0u8; 65]).err(),
+            Some(MerkleTreeError::InvalidKeyLength {
+                length: 65,
+                max: 64
+            })
+        );
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn test_blake2s_known_hash() {
+        // Keyed BLAKE2s-256 of the empty message with key 00..1f, from the
+        // RFC 7693 reference implementation's keyed test vectors
+        let key: Vec<u8> = (0..32).collect();
+        let hasher = KeyedHasher::blake2s(&key).unwrap();
+        let expected: [u8; 32] = [
+            0x48, 0xa8, 0x99, 0x7d, 0xa4, 0x07, 0x87, 0x6b, 0x3d, 0x79, 0xc0, 0xd9, 0x23, 0x25,
+            0xad, 0x3b, 0x89, 0xcb, 0xb7, 0x54, 0xd8, 0x6a, 0xb7, 0x1a, 0xee, 0x04, 0x7a, 0xd3,
+            0x45, 0xfd, 0x2c, 0x49,
+        ];
+        assert_eq!(hasher.hash_bytes(b""), expected);
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn test_blake2s_rejects_long_key() {
+        assert_eq!(
+            KeyedHasher::blake2s(&[0u8; 33]).err(),
+            Some(MerkleTreeError::InvalidKeyLength {
+                length: 33,
+                max: 32
+            })
+        );
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_known_hash() {
+        // Empty input vector from the BLAKE3 specification
+        let hasher = KeyedHasher::blake3(b"whats the Elvish word for friend");
+        let expected: [u8; 32] = [
+            0x92, 0xb2, 0xb7, 0x56, 0x04, 0xed, 0x3c, 0x76, 0x1f, 0x9d, 0x6f, 0x62, 0x39, 0x2c,
+            0x8a, 0x92, 0x27, 0xad, 0x0e, 0xa3, 0xf0, 0x95, 0x73, 0xe7, 0x83, 0xf1, 0x49, 0x8a,
+            0x4e, 0xd6, 0x0d, 0x26,
+        ];
+        assert_eq!(hasher.hash_bytes(b""), expected);
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_different_keys_different_hashes() {
+        let a = KeyedHasher::hmac_sha256(b"tenant-a");
+        let b = KeyedHasher::hmac_sha256(b"tenant-b");
+        assert_ne!(a.hash_bytes(b"hello"), b.hash_bytes(b"hello"));
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        #[cfg(feature = "hmac")]
+        crate::hasher::tests::assert_hash_parts_matches(&KeyedHasher::hmac_sha256(b"key"));
+        #[cfg(feature = "blake2")]
+        crate::hasher::tests::assert_hash_parts_matches(&KeyedHasher::blake2b(b"key").unwrap());
+        #[cfg(feature = "blake2")]
+        crate::hasher::tests::assert_hash_parts_matches(&KeyedHasher::blake2s(b"key").unwrap());
+        #[cfg(feature = "blake3")]
+        crate::hasher::tests::assert_hash_parts_matches(&KeyedHasher::blake3(&[7u8; 32]));
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_proof_does_not_verify_under_other_key() {
+        use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+        let mut tree_a = SimpleMerkleTree::new(KeyedHasher::hmac_sha256(b"tenant-a"));
+        let mut tree_b = SimpleMerkleTree::new(KeyedHasher::hmac_sha256(b"tenant-b"));
+        for data in [b"x", b"y", b"z"] {
+            tree_a.add_leaf(data).unwrap();
+            tree_b.add_leaf(data).unwrap();
+        }
+
+        let proof = tree_a.prove(1).unwrap();
+        assert!(tree_a.verify(&proof, b"y", &tree_a.get_root_bytes().unwrap()));
+        assert!(!tree_b.verify(&proof, b"y", &tree_a.get_root_bytes().unwrap()));
+        assert!(!tree_b.verify(&proof, b"y", &tree_b.get_root_bytes().unwrap()));
+    }
+}
@@ -1,8 +1,53 @@
+#[cfg(feature = "blake2")]
+mod blake2;
+#[cfg(feature = "blake3")]
+mod blake3;
+mod digest;
+mod id;
+#[cfg(any(feature = "hmac", feature = "blake2", feature = "blake3"))]
+mod keyed;
+mod personalized;
+#[cfg(feature = "poseidon")]
+mod poseidon;
+mod registry;
+#[cfg(feature = "ripemd")]
+mod ripemd160;
+mod scheme;
 mod sha256;
+#[cfg(target_arch = "x86_64")]
+mod sha256_x8;
+#[cfg(feature = "sha3")]
+mod sha3;
+#[cfg(feature = "sha512-256")]
+mod sha512_256;
 mod simple;
 
+use std::sync::Arc;
+
 // Re-export implementations
+#[cfg(feature = "blake2")]
+pub use self::blake2::{Blake2bHasher, Blake2sHasher};
+#[cfg(feature = "blake3")]
+pub use self::blake3::Blake3Hasher;
+pub use self::digest::DigestHasher;
+#[cfg(feature = "sha3")]
+pub use self::sha3::Sha3_256Hasher;
+pub use id::HashId;
+#[cfg(any(feature = "hmac", feature = "blake2", feature = "blake3"))]
+pub use keyed::KeyedHasher;
+pub use personalized::PersonalizedHasher;
+#[cfg(feature = "poseidon")]
+pub use poseidon::PoseidonHasher;
+pub use registry::HasherRegistry;
+#[cfg(feature = "ripemd")]
+pub use ripemd160::Ripemd160Hasher;
+pub use scheme::{HashScheme, SchemedHasher};
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+pub use sha256::MultiBufferSha256Hasher;
 pub use sha256::Sha256Hasher;
+#[cfg(feature = "sha512-256")]
+pub use sha512_256::Sha512_256Hasher;
 pub use simple::SimpleHasher;
 
 /// A trait for hash algorithms (SHA256, Blake3, etc.).
@@ -12,4 +57,243 @@ pub use simple::SimpleHasher;
 pub trait Hasher {
     /// Hash raw bytes and return the result as a 32-byte array.
     fn hash_bytes(&self, data: &[u8]) -> [u8; 32];
+
+    /// Identify the algorithm, so roots and proofs can record it.
+    ///
+    /// Defaults to `None` (unidentified). Hashers whose output is not the
+    /// plain algorithm, such as keyed or personalized wrappers, keep the
+    /// default.
+    fn hash_id(&self) -> Option<HashId> {
+        None
+    }
+
+    /// Hash the concatenation of several byte slices.
+    ///
+    /// Equivalent to `hash_bytes` on the concatenated input. Tree nodes use
+    /// it to feed domain prefixes and child hashes without building a
+    /// temporary buffer. The default implementation concatenates; hashers
+    /// with an incremental API should override it to stream the parts.
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        self.hash_bytes(&parts.concat())
+    }
+
+    /// Hash a leaf of a Merkle tree.
+    ///
+    /// Defaults to `HashScheme::STANDARD`, i.e. `H(0x00 || data)`. Hashers
+    /// with their own tree construction (such as two-to-one compression
+    /// functions) override this together with `hash_internal`; use
+    /// `SchemedHasher` to pick another `HashScheme`.
+    fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+        HashScheme::STANDARD.hash_leaf(self, data)
+    }
+
+    /// Hash an internal node of a Merkle tree from its children's hashes.
+    ///
+    /// Defaults to `HashScheme::STANDARD`, i.e. `H(0x01 || left || right)`.
+    fn hash_internal(&self, left: &[u8], right: &[u8]) -> [u8; 32] {
+        HashScheme::STANDARD.hash_internal(self, left, right)
+    }
+
+    /// Hash several independent inputs, returning one hash per input.
+    ///
+    /// The default hashes them one by one. Hashers able to process several
+    /// messages at once (such as multi-buffer SIMD implementations)
+    /// override it.
+    fn hash_many(&self, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+        inputs.iter().map(|input| self.hash_bytes(input)).collect()
+    }
+
+    /// Hash a batch of leaves, one hash per input.
+    ///
+    /// Trees call this when they add many leaves at once, such as when a
+    /// tree is loaded. The default calls `hash_leaf` for each input;
+    /// overrides must return the same hashes.
+    fn hash_leaf_many(&self, leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+        leaves.iter().map(|data| self.hash_leaf(data)).collect()
+    }
+
+    /// Hash a batch of internal nodes, one per `(left, right)` pair.
+    ///
+    /// Trees call this once per level when rebuilding. The default calls
+    /// `hash_internal` for each pair; overrides must return the same hashes.
+    fn hash_internal_many(&self, pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+        pairs
+            .iter()
+            .map(|(left, right)| self.hash_internal(left, right))
+            .collect()
+    }
+}
+
+/// A hasher chosen at runtime, shareable between trees and threads.
+///
+/// `Hasher` is object safe, so any hasher can be stored behind a pointer;
+/// see `HasherRegistry` for looking one up by name.
+pub type DynHasher = Arc<dyn Hasher + Send + Sync>;
+
+/// Forward every method, so overridden tree hooks survive the indirection.
+macro_rules! forward_hasher {
+    ($($pointer:ty),*) => {$(
+        impl<H: Hasher + ?Sized> Hasher for $pointer {
+            fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+                (**self).hash_bytes(data)
+            }
+
+            fn hash_id(&self) -> Option<HashId> {
+                (**self).hash_id()
+            }
+
+            fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+                (**self).hash_parts(parts)
+            }
+
+            fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+                (**self).hash_leaf(data)
+            }
+
+            fn hash_internal(&self, left: &[u8], right: &[u8]) -> [u8; 32] {
+                (**self).hash_internal(left, right)
+            }
+
+            fn hash_many(&self, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+                (**self).hash_many(inputs)
+            }
+
+            fn hash_leaf_many(&self, leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+                (**self).hash_leaf_many(leaves)
+            }
+
+            fn hash_internal_many(&self, pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+                (**self).hash_internal_many(pairs)
+            }
+        }
+    )*};
+}
+
+forward_hasher!(&H, Box<H>, Arc<H>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hasher relying on the default `hash_parts`.
+    struct ConcatHasher;
+
+    impl Hasher for ConcatHasher {
+        fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+            Sha256Hasher::new().hash_bytes(data)
+        }
+    }
+
+    /// Check that `hash_parts` matches `hash_bytes` on the concatenation.
+    pub(crate) fn assert_hash_parts_matches<H: Hasher>(hasher: &H) {
+        let long = vec![0xabu8; 10_000];
+        let cases: [&[&[u8]]; 4] = [
+            &[],
+            &[b""],
+            &[&[0x00], b"hello"],
+            &[&[0x01], &[7u8; 32], &[], &long],
+        ];
+        for parts in cases {
+            assert_eq!(hasher.hash_parts(parts), hasher.hash_bytes(&parts.concat()));
+        }
+    }
+
+    #[test]
+    fn test_default_hash_parts_concatenates() {
+        assert_hash_parts_matches(&ConcatHasher);
+    }
+
+    #[test]
+    fn test_default_tree_hashes_use_domain_prefixes() {
+        let hasher = Sha256Hasher::new();
+        assert_eq!(hasher.hash_leaf(b"a"), hasher.hash_bytes(b"\x00a"));
+
+        let mut internal = vec![0x01];
+        internal.extend_from_slice(&[1u8; 32]);
+        internal.extend_from_slice(&[2u8; 32]);
+        assert_eq!(
+            hasher.hash_internal(&[1u8; 32], &[2u8; 32]),
+            hasher.hash_bytes(&internal)
+        );
+    }
+
+    /// Check that the batch methods match their one-at-a-time versions.
+    pub(crate) fn assert_batches_match<H: Hasher>(hasher: &H) {
+        let data: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; i as usize * 7]).collect();
+        let inputs: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        let expected: Vec<[u8; 32]> = inputs.iter().map(|i| hasher.hash_bytes(i)).collect();
+        assert_eq!(hasher.hash_many(&inputs), expected);
+        assert!(hasher.hash_many(&[]).is_empty());
+
+        let expected: Vec<[u8; 32]> = inputs.iter().map(|i| hasher.hash_leaf(i)).collect();
+        assert_eq!(hasher.hash_leaf_many(&inputs), expected);
+        assert!(hasher.hash_leaf_many(&[]).is_empty());
+
+        let hashes: Vec<[u8; 32]> = (0..19u8).map(|i| [i; 32]).collect();
+        let pairs: Vec<(&[u8], &[u8])> = hashes
+            .windows(2)
+            .map(|pair| (&pair[0][..], &pair[1][..]))
+            .collect();
+        let expected: Vec<[u8; 32]> = pairs
+            .iter()
+            .map(|(left, right)| hasher.hash_internal(left, right))
+            .collect();
+        assert_eq!(hasher.hash_internal_many(&pairs), expected);
+    }
+
+    #[test]
+    fn test_default_batches() {
+        assert_batches_match(&ConcatHasher);
+        assert_batches_match(&SimpleHasher::new());
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        assert_hash_parts_matches(&Sha256Hasher::new());
+        assert_hash_parts_matches(&SimpleHasher::new());
+    }
+
+    // =========================================================================
+    // Dynamic Dispatch Tests
+    // =========================================================================
+
+    /// A hasher overriding the tree hooks, to check they are forwarded.
+    struct HookHasher;
+
+    impl Hasher for HookHasher {
+        fn hash_bytes(&self, _data: &[u8]) -> [u8; 32] {
+            [0u8; 32]
+        }
+
+        fn hash_leaf(&self, _data: &[u8]) -> [u8; 32] {
+            [1u8; 32]
+        }
+
+        fn hash_internal(&self, _left: &[u8], _right: &[u8]) -> [u8; 32] {
+            [2u8; 32]
+        }
+    }
+
+    #[test]
+    fn test_dyn_hasher_matches_concrete() {
+        let hasher: DynHasher = Arc::new(Sha256Hasher::new());
+        let concrete = Sha256Hasher::new();
+        assert_eq!(hasher.hash_bytes(b"a"), concrete.hash_bytes(b"a"));
+        assert_eq!(hasher.hash_leaf(b"a"), concrete.hash_leaf(b"a"));
+        assert_hash_parts_matches(&hasher);
+    }
+
+    #[test]
+    fn test_pointers_forward_tree_hooks() {
+        let boxed: Box<dyn Hasher> = Box::new(HookHasher);
+        assert_eq!(boxed.hash_leaf(b"a"), [1u8; 32]);
+        assert_eq!(boxed.hash_internal(b"l", b"r"), [2u8; 32]);
+
+        fn leaf<H: Hasher>(hasher: H) -> [u8; 32] {
+            hasher.hash_leaf(b"a")
+        }
+        let shared: DynHasher = Arc::new(HookHasher);
+        assert_eq!(leaf(&shared), [1u8; 32]);
+        assert_eq!(shared.hash_internal(b"l", b"r"), [2u8; 32]);
+    }
 }
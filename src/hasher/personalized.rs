@@ -0,0 +1,248 @@
+use super::Hasher;
+
+/// A hasher wrapper that mixes a domain tag into every hash.
+///
+/// Plain hashes are computed by the inner hasher over
+/// `len(tag) || tag || data`, where `len(tag)` is the tag length as a
+/// big-endian `u64`. Tree nodes go through the inner hasher's own leaf and
+/// internal hooks, so a field-native hasher such as Poseidon keeps its
+/// tree construction:
+///
+/// - a leaf is `inner.hash_leaf(H(len(tag) || tag || data))`
+/// - an internal node is `inner.hash_internal(T || left, right)`, where
+///   `T = H(len(tag) || tag)` is computed once
+///
+/// The tag thus separates every hash of one tree from those of trees with
+/// another tag, without needing a secret.
+///
+/// Use it to give each tenant (or each tree) its own domain:
+///
+/// ```
+/// use merkle_trees::hasher::PersonalizedHasher;
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant-a");
+/// let mut tree = SimpleMerkleTree::new(hasher);
+/// tree.add_leaf(b"hello").unwrap();
+/// assert!(tree.get_root().is_some());
+/// ```
+#[derive(Clone)]
+pub struct PersonalizedHasher<H: Hasher> {
+    inner: H,
+    /// The length-prefixed tag, fed before every input.
+    prefix: Vec<u8>,
+    /// The hash of `prefix`, folded into every internal node.
+    tag_hash: [u8; 32],
+}
+
+/// Part counts up to this size are prefixed on the stack in `hash_parts`.
+const INLINE_PARTS: usize = 8;
+
+impl<H: Hasher> PersonalizedHasher<H> {
+    /// Wrap a hasher with the given domain tag.
+    pub fn new(inner: H, tag: &[u8]) -> Self {
+        let mut prefix = Vec::with_capacity(8 + tag.len());
+        prefix.extend_from_slice(&(tag.len() as u64).to_be_bytes());
+        prefix.extend_from_slice(tag);
+        let tag_hash = inner.hash_bytes(&prefix);
+        PersonalizedHasher {
+            inner,
+            prefix,
+            tag_hash,
+        }
+    }
+
+    /// Returns the domain tag.
+    pub fn tag(&self) -> &[u8] {
+        &self.prefix[8..]
+    }
+
+    /// Returns the wrapped hasher.
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    /// Prepend the tag hash to the left child of an internal node.
+    fn fold_tag(&self, left: &[u8]) -> [u8; 64] {
+        let mut input = [0u8; 64];
+        input[..32].copy_from_slice(&self.tag_hash);
+        input[32..].copy_from_slice(left);
+        input
+    }
+}
+
+impl<H: Hasher> Hasher for PersonalizedHasher<H> {
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        self.inner.hash_parts(&[&self.prefix, data])
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        if parts.len() < INLINE_PARTS {
+            let mut all: [&[u8]; INLINE_PARTS] = [&[]; INLINE_PARTS];
+            all[0] = &self.prefix;
+            all[1..=parts.len()].copy_from_slice(parts);
+            return self.inner.hash_parts(&all[..=parts.len()]);
+        }
+        let mut all = Vec::with_capacity(parts.len() + 1);
+        all.push(self.prefix.as_slice());
+        all.extend_from_slice(parts);
+        self.inner.hash_parts(&all)
+    }
+
+    fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+        self.inner.hash_leaf(&self.hash_bytes(data))
+    }
+
+    fn hash_internal(&self, left: &[u8], right: &[u8]) -> [u8; 32] {
+        self.inner.hash_internal(&self.fold_tag(left), right)
+    }
+
+    fn hash_internal_many(&self, pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+        let lefts: Vec<[u8; 64]> = pairs.iter().map(|(left, _)| self.fold_tag(left)).collect();
+        let folded: Vec<(&[u8], &[u8])> = lefts
+            .iter()
+            .zip(pairs)
+            .map(|(left, (_, right))| (left.as_slice(), *right))
+            .collect();
+        self.inner.hash_internal_many(&folded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+    #[test]
+    fn test_known_hash() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tag");
+        let mut input = vec![0, 0, 0, 0, 0, 0, 0, 3];
+        input.extend_from_slice(b"taghello");
+        assert_eq!(
+            hasher.hash_bytes(b"hello"),
+            Sha256Hasher::new().hash_bytes(&input)
+        );
+    }
+
+    #[test]
+    fn test_different_tags_different_hashes() {
+        let a = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant-a");
+        let b = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant-b");
+        assert_ne!(a.hash_bytes(b"hello"), b.hash_bytes(b"hello"));
+    }
+
+    #[test]
+    fn test_tag_boundary_is_unambiguous() {
+        // Without the length prefix, ("ab", "c") and ("a", "bc") would collide
+        let a = PersonalizedHasher::new(Sha256Hasher::new(), b"ab");
+        let b = PersonalizedHasher::new(Sha256Hasher::new(), b"a");
+        assert_ne!(a.hash_bytes(b"c"), b.hash_bytes(b"bc"));
+    }
+
+    #[test]
+    fn test_empty_tag_differs_from_plain_hasher() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"");
+        assert_ne!(
+            hasher.hash_bytes(b"hello"),
+            Sha256Hasher::new().hash_bytes(b"hello")
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant");
+        assert_eq!(hasher.tag(), b"tenant");
+        assert_eq!(
+            hasher.inner().hash_bytes(b"x"),
+            Sha256Hasher::new().hash_bytes(b"x")
+        );
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant");
+        crate::hasher::tests::assert_hash_parts_matches(&hasher);
+    }
+
+    #[test]
+    fn test_hash_parts_beyond_inline_parts() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant");
+        let parts: Vec<&[u8]> = (0..20).map(|_| b"xy".as_slice()).collect();
+        assert_eq!(
+            hasher.hash_parts(&parts),
+            hasher.hash_bytes(&parts.concat())
+        );
+    }
+
+    #[test]
+    fn test_tree_hooks_fold_in_the_tag() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tag");
+        let inner = Sha256Hasher::new();
+        assert_eq!(
+            hasher.hash_leaf(b"a"),
+            inner.hash_leaf(&hasher.hash_bytes(b"a"))
+        );
+
+        let tag_hash = inner.hash_bytes(&[0, 0, 0, 0, 0, 0, 0, 3, b't', b'a', b'g']);
+        let (left, right) = ([1u8; 32], [2u8; 32]);
+        assert_eq!(
+            hasher.hash_internal(&left, &right),
+            inner.hash_parts(&[&[0x01], &tag_hash, &left, &right])
+        );
+    }
+
+    #[test]
+    fn test_batches_match() {
+        let hasher = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant");
+        crate::hasher::tests::assert_batches_match(&hasher);
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon_keeps_its_tree_construction() {
+        use crate::hasher::PoseidonHasher;
+        use crate::merkle::simple_tree::verify_proof;
+
+        let poseidon = PoseidonHasher::new();
+        let hasher = PersonalizedHasher::new(poseidon.clone(), b"tenant");
+        let mut tree = SimpleMerkleTree::new(hasher.clone());
+        let mut plain = SimpleMerkleTree::new(poseidon.clone());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+            plain.add_leaf(data).unwrap();
+        }
+
+        // Leaves and nodes are Poseidon compressions, not a byte-level
+        // prefix scheme over Poseidon's sponge
+        let leaf = poseidon.hash_leaf(&hasher.hash_bytes(b"a"));
+        assert_eq!(hasher.hash_leaf(b"a"), leaf);
+        assert_ne!(hasher.hash_leaf(b"a"), hasher.hash_parts(&[&[0x00], b"a"]));
+
+        let root = tree.get_root_bytes().unwrap();
+        assert_ne!(Some(root), plain.get_root_bytes());
+        for (i, data) in [b"a", b"b", b"c"].iter().enumerate() {
+            let proof = tree.prove(i as u64).unwrap();
+            assert_eq!(verify_proof(*data, &proof, &root, &hasher), Ok(true));
+        }
+        let other = PersonalizedHasher::new(poseidon, b"other");
+        let proof = tree.prove(0).unwrap();
+        assert_eq!(verify_proof(b"a", &proof, &root, &other), Ok(false));
+    }
+
+    #[test]
+    fn test_proof_does_not_verify_under_other_tag() {
+        let mut tree_a = SimpleMerkleTree::new(PersonalizedHasher::new(Sha256Hasher::new(), b"a"));
+        let mut tree_b = SimpleMerkleTree::new(PersonalizedHasher::new(Sha256Hasher::new(), b"b"));
+        for data in [b"x", b"y", b"z"] {
+            tree_a.add_leaf(data).unwrap();
+            tree_b.add_leaf(data).unwrap();
+        }
+
+        let proof = tree_a.prove(2).unwrap();
+        let root_a = tree_a.get_root_bytes().unwrap();
+        assert!(tree_a.verify(&proof, b"z", &root_a));
+        assert!(!tree_b.verify(&proof, b"z", &root_a));
+        assert!(!tree_b.verify(&proof, b"z", &tree_b.get_root_bytes().unwrap()));
+    }
+}
@@ -0,0 +1,236 @@
+use super::{HashId, Hasher};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher as _};
+
+/// Largest number of inputs the circomlib parameters support in one call.
+const MAX_INPUTS: usize = 12;
+
+/// Bytes packed into each field element, so every chunk is below the modulus.
+const CHUNK_SIZE: usize = 31;
+
+/// Poseidon hasher over the BN254 scalar field, using the circomlib
+/// parameters (`x^5` S-box, 8 full rounds, width = inputs + 1).
+///
+/// Outputs are field elements encoded as 32 big-endian bytes, so they can be
+/// checked inside circom/snarkjs circuits. Requires the `poseidon` feature.
+///
+/// Trees use Poseidon as a two-to-one compression function instead of byte
+/// prefixes: an internal node is `Poseidon(left, right)` and a leaf is
+/// `Poseidon(hash_bytes(data))`. The different arities keep the two kinds of
+/// node apart. Child hashes that are not canonical field elements are reduced
+/// modulo the field order.
+///
+/// Arbitrary bytes are hashed by absorbing the length followed by 31-byte
+/// big-endian chunks, up to 12 elements per permutation and chaining the
+/// previous output as the first input of the next.
+#[derive(Clone)]
+pub struct PoseidonHasher;
+
+impl Default for PoseidonHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoseidonHasher {
+    pub fn new() -> Self {
+        PoseidonHasher
+    }
+}
+
+/// Hash 1 to 12 field elements with the circomlib parameters.
+fn poseidon(inputs: &[Fr]) -> Fr {
+    Poseidon::<Fr>::new_circom(inputs.len())
+        .and_then(|mut poseidon| poseidon.hash(inputs))
+        .expect("input count is within the circomlib parameters")
+}
+
+fn to_field(bytes: &[u8]) -> Fr {
+    Fr::from_be_bytes_mod_order(bytes)
+}
+
+fn to_bytes(element: Fr) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let bytes = element.into_bigint().to_bytes_be();
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+impl Hasher for PoseidonHasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::POSEIDON_BN254)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        let mut elements = vec![Fr::from(data.len() as u64)];
+        elements.extend(data.chunks(CHUNK_SIZE).map(to_field));
+
+        let (first, mut rest) = elements.split_at(elements.len().min(MAX_INPUTS));
+        let mut state = poseidon(first);
+        while !rest.is_empty() {
+            let (next, remaining) = rest.split_at(rest.len().min(MAX_INPUTS - 1));
+            let mut inputs = Vec::with_capacity(next.len() + 1);
+            inputs.push(state);
+            inputs.extend_from_slice(next);
+            state = poseidon(&inputs);
+            rest = remaining;
+        }
+        to_bytes(state)
+    }
+
+    fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+        to_bytes(poseidon(&[to_field(&self.hash_bytes(data))]))
+    }
+
+    fn hash_internal(&self, left: &[u8], right: &[u8]) -> [u8; 32] {
+        to_bytes(poseidon(&[to_field(left), to_field(right)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::simple_tree::verify_proof;
+    use crate::merkle::{MerkleTree, MerkleTreeError, SimpleMerkleTree};
+
+    const LEAVES: [&[u8]; 5] = [b"a", b"b", b"c", b"d", b"e"];
+
+    fn field_bytes(value: u64) -> [u8; 32] {
+        to_bytes(Fr::from(value))
+    }
+
+    // =========================================================================
+    // Known-Answer Tests (circomlib)
+    // =========================================================================
+
+    #[test]
+    fn test_circomlib_two_inputs() {
+        // circomlibjs poseidon([1, 2])
+        let hash = PoseidonHasher::new().hash_internal(&field_bytes(1), &field_bytes(2));
+        let expected: [u8; 32] = [
+            0x11, 0x5c, 0xc0, 0xf5, 0xe7, 0xd6, 0x90, 0x41, 0x3d, 0xf6, 0x4c, 0x6b, 0x96, 0x62,
+            0xe9, 0xcf, 0x2a, 0x36, 0x17, 0xf2, 0x74, 0x32, 0x45, 0x51, 0x9e, 0x19, 0x60, 0x7a,
+            0x44, 0x17, 0x18, 0x9a,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_circomlib_one_input() {
+        // circomlibjs poseidon([1])
+        let hash = to_bytes(poseidon(&[Fr::from(1u64)]));
+        let expected: [u8; 32] = [
+            0x29, 0x17, 0x61, 0x00, 0xea, 0xa9, 0x62, 0xbd, 0xc1, 0xfe, 0x6c, 0x65, 0x4d, 0x6a,
+            0x3c, 0x13, 0x0e, 0x96, 0xa4, 0xd1, 0x16, 0x8b, 0x33, 0x84, 0x8b, 0x89, 0x7d, 0xc5,
+            0x02, 0x82, 0x01, 0x33,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_leaf_is_one_input_poseidon_of_hash_bytes() {
+        let hasher = PoseidonHasher::new();
+        let inner = hasher.hash_bytes(b"hello");
+        assert_eq!(
+            hasher.hash_leaf(b"hello"),
+            to_bytes(poseidon(&[to_field(&inner)]))
+        );
+    }
+
+    // =========================================================================
+    // Byte Hashing Tests
+    // =========================================================================
+
+    #[test]
+    fn test_outputs_are_canonical_field_elements() {
+        let hasher = PoseidonHasher::new();
+        for data in [&b""[..], b"hello", &[0xffu8; 500]] {
+            let hash = hasher.hash_bytes(data);
+            assert_eq!(to_bytes(to_field(&hash)), hash);
+        }
+    }
+
+    #[test]
+    fn test_length_is_absorbed() {
+        let hasher = PoseidonHasher::new();
+        assert_ne!(hasher.hash_bytes(b""), hasher.hash_bytes(&[0]));
+        assert_ne!(hasher.hash_bytes(&[0]), hasher.hash_bytes(&[0, 0]));
+    }
+
+    #[test]
+    fn test_long_inputs_chain_permutations() {
+        let hasher = PoseidonHasher::new();
+        // Spans several permutations; a change in the last chunk must matter
+        let mut data = vec![7u8; CHUNK_SIZE * 30];
+        let before = hasher.hash_bytes(&data);
+        *data.last_mut().unwrap() = 8;
+        assert_ne!(hasher.hash_bytes(&data), before);
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: PoseidonHasher = Default::default();
+        assert_eq!(
+            hasher.hash_bytes(b"test"),
+            PoseidonHasher::new().hash_bytes(b"test")
+        );
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&PoseidonHasher::new());
+    }
+
+    // =========================================================================
+    // Tree Tests
+    // =========================================================================
+
+    #[test]
+    fn test_tree_proofs_verify() {
+        let mut tree = SimpleMerkleTree::new(PoseidonHasher::new());
+        for data in LEAVES {
+            tree.add_leaf(data).unwrap();
+        }
+        let root = tree.get_root_bytes().unwrap();
+        for (i, data) in LEAVES.iter().enumerate() {
+            let proof = tree.prove(i as u64).unwrap();
+            assert_eq!(
+                verify_proof(data, &proof, &root, &PoseidonHasher::new()),
+                Ok(true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tree_root_is_two_to_one_compression() {
+        let hasher = PoseidonHasher::new();
+        let mut tree = SimpleMerkleTree::new(hasher.clone());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+
+        let expected = hasher.hash_internal(&hasher.hash_leaf(b"a"), &hasher.hash_leaf(b"b"));
+        assert_eq!(tree.get_root_bytes(), Some(expected));
+    }
+
+    #[test]
+    fn test_proof_does_not_verify_under_sha256() {
+        let mut tree = SimpleMerkleTree::new(PoseidonHasher::new());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove(0).unwrap();
+        assert_eq!(
+            verify_proof(b"a", &proof, &root, &PoseidonHasher::new()),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_proof(b"a", &proof, &root, &Sha256Hasher::new()),
+            Err(MerkleTreeError::HasherMismatch {
+                proof: HashId::POSEIDON_BN254,
+                verifier: HashId::SHA2_256,
+            })
+        );
+    }
+}
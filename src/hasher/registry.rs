@@ -0,0 +1,170 @@
+use super::{DynHasher, Hasher, Sha256Hasher, SimpleHasher};
+use crate::merkle::MerkleTreeError;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A table of hashers by name, for choosing the algorithm at runtime.
+///
+/// `HasherRegistry::new()` knows the built-in hashers: `"sha256"` and
+/// `"simple"`, plus `"blake2b"`, `"blake2s"`, `"blake3"`, `"sha3-256"`,
+/// `"sha512-256"`, `"ripemd160"` and `"poseidon"` when their cargo
+/// features are enabled. Names are case-insensitive.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{HasherRegistry, MerkleTree, SimpleMerkleTree};
+///
+/// let registry = HasherRegistry::new();
+/// let mut tree = SimpleMerkleTree::new(registry.get("sha256").unwrap());
+/// tree.add_leaf(b"hello").unwrap();
+/// assert!(registry.get("md5").is_err());
+/// ```
+#[derive(Clone)]
+pub struct HasherRegistry {
+    hashers: BTreeMap<String, DynHasher>,
+}
+
+impl Default for HasherRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HasherRegistry {
+    /// Create a registry holding the built-in hashers.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("sha256", Sha256Hasher::new());
+        registry.register("simple", SimpleHasher::new());
+        #[cfg(feature = "blake2")]
+        {
+            registry.register("blake2b", super::Blake2bHasher::new());
+            registry.register("blake2s", super::Blake2sHasher::new());
+        }
+        #[cfg(feature = "blake3")]
+        registry.register("blake3", super::Blake3Hasher::new());
+        #[cfg(feature = "sha3")]
+        registry.register("sha3-256", super::Sha3_256Hasher::new());
+        #[cfg(feature = "sha512-256")]
+        registry.register("sha512-256", super::Sha512_256Hasher::new());
+        #[cfg(feature = "ripemd")]
+        registry.register("ripemd160", super::Ripemd160Hasher::new());
+        #[cfg(feature = "poseidon")]
+        registry.register("poseidon", super::PoseidonHasher::new());
+        registry
+    }
+
+    /// Create a registry with no hashers.
+    pub fn empty() -> Self {
+        HasherRegistry {
+            hashers: BTreeMap::new(),
+        }
+    }
+
+    /// Register a hasher under `name`, replacing any previous entry.
+    pub fn register<H: Hasher + Send + Sync + 'static>(&mut self, name: &str, hasher: H) {
+        self.hashers
+            .insert(name.to_ascii_lowercase(), Arc::new(hasher));
+    }
+
+    /// Look up a hasher by name.
+    ///
+    /// Returns `MerkleTreeError::UnknownHasher` if no hasher has that name.
+    pub fn get(&self, name: &str) -> Result<DynHasher, MerkleTreeError> {
+        self.hashers
+            .get(&name.to_ascii_lowercase())
+            .cloned()
+            .ok_or_else(|| MerkleTreeError::UnknownHasher {
+                name: name.to_string(),
+            })
+    }
+
+    /// Returns the registered names, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.hashers.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::PersonalizedHasher;
+
+    #[test]
+    fn test_builtin_hashers() {
+        let registry = HasherRegistry::new();
+        let names: Vec<&str> = registry.names().collect();
+        assert!(names.contains(&"sha256"));
+        assert!(names.contains(&"simple"));
+
+        let sha256 = registry.get("sha256").unwrap();
+        assert_eq!(
+            sha256.hash_bytes(b"hello"),
+            Sha256Hasher::new().hash_bytes(b"hello")
+        );
+        let simple = registry.get("simple").unwrap();
+        assert_eq!(
+            simple.hash_bytes(b"hello"),
+            SimpleHasher::new().hash_bytes(b"hello")
+        );
+    }
+
+    #[test]
+    fn test_builtin_hash_ids_are_distinct() {
+        let registry = HasherRegistry::new();
+        let mut ids: Vec<u64> = registry
+            .names()
+            .map(|name| registry.get(name).unwrap().hash_id().unwrap().code())
+            .collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+    }
+
+    #[test]
+    fn test_names_are_case_insensitive() {
+        let registry = HasherRegistry::new();
+        assert!(registry.get("SHA256").is_ok());
+    }
+
+    #[test]
+    fn test_unknown_name() {
+        let registry = HasherRegistry::empty();
+        assert_eq!(registry.names().count(), 0);
+        assert_eq!(
+            registry.get("sha256").err(),
+            Some(MerkleTreeError::UnknownHasher {
+                name: "sha256".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_register_custom_hasher() {
+        let mut registry = HasherRegistry::new();
+        let custom = PersonalizedHasher::new(Sha256Hasher::new(), b"tenant");
+        registry.register("tenant", custom.clone());
+        assert_eq!(
+            registry.get("tenant").unwrap().hash_bytes(b"a"),
+            custom.hash_bytes(b"a")
+        );
+
+        // Registering again replaces the entry
+        registry.register("tenant", SimpleHasher::new());
+        assert_eq!(
+            registry.get("tenant").unwrap().hash_bytes(b"a"),
+            SimpleHasher::new().hash_bytes(b"a")
+        );
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon_keeps_its_tree_hooks() {
+        let registry = HasherRegistry::new();
+        let poseidon = registry.get("poseidon").unwrap();
+        let concrete = crate::hasher::PoseidonHasher::new();
+        assert_eq!(poseidon.hash_leaf(b"a"), concrete.hash_leaf(b"a"));
+    }
+}
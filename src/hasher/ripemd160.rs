@@ -0,0 +1,90 @@
+use super::{HashId, Hasher};
+use ripemd::{Digest, Ripemd160};
+
+/// RIPEMD-160 hasher using the `ripemd` crate from RustCrypto.
+///
+/// RIPEMD-160 produces 20 bytes; the result is padded on the right with 12
+/// zero bytes to fit the 32-byte `Hasher` output. Provided for
+/// compatibility with existing systems only, as 160 bits gives a lower
+/// collision resistance than the other hashers. Requires the `ripemd`
+/// feature.
+#[derive(Clone)]
+pub struct Ripemd160Hasher;
+
+impl Default for Ripemd160Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ripemd160Hasher {
+    pub fn new() -> Self {
+        Ripemd160Hasher
+    }
+}
+
+impl Hasher for Ripemd160Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::RIPEMD_160)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash[..20].copy_from_slice(&Ripemd160::digest(data));
+        hash
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Ripemd160::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        let mut hash = [0u8; 32];
+        hash[..20].copy_from_slice(&hasher.finalize());
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hash() {
+        let hasher = Ripemd160Hasher::new();
+        // "hello" RIPEMD-160 hash, zero-padded to 32 bytes
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0x10, 0x8f, 0x07, 0xb8, 0x38, 0x24, 0x12, 0x61, 0x2c, 0x04, 0x8d, 0x07, 0xd1, 0x3f,
+            0x81, 0x41, 0x18, 0x44, 0x5a, 0xcd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_different_inputs_different_hashes() {
+        let hasher = Ripemd160Hasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: Ripemd160Hasher = Default::default();
+        assert_eq!(
+            hasher.hash_bytes(b"test"),
+            Ripemd160Hasher::new().hash_bytes(b"test")
+        );
+    }
+
+    #[test]
+    fn test_output_is_zero_padded() {
+        let hash = Ripemd160Hasher::new().hash_bytes(b"anything");
+        assert_eq!(&hash[20..], &[0u8; 12]);
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Ripemd160Hasher::new());
+    }
+}
@@ -0,0 +1,296 @@
+use super::{HashId, Hasher};
+use std::borrow::Cow;
+
+/// How a tree turns leaf data and child hashes into node hashes.
+///
+/// A scheme is a leaf prefix, an internal node prefix, and whether leaf
+/// data is hashed twice. Leaves hash to `H(leaf_prefix || data)`, or to
+/// `H(leaf_prefix || H(data))` with double-hashing, and internal nodes to
+/// `H(node_prefix || left || right)`.
+///
+/// `HashScheme::STANDARD` (`0x00` / `0x01`, single hash) is what every
+/// hasher uses by default. Other schemes exist for compatibility with
+/// trees built elsewhere; apply one with `SchemedHasher`.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::hasher::{HashScheme, SchemedHasher};
+/// use merkle_trees::{Hasher, Sha256Hasher};
+///
+/// // Leaves are H(H(data)) and nodes H(left || right)
+/// let scheme = HashScheme::UNPREFIXED.with_double_hashed_leaves();
+/// let hasher = SchemedHasher::new(Sha256Hasher::new(), scheme);
+///
+/// let sha256 = Sha256Hasher::new();
+/// assert_eq!(
+///     hasher.hash_leaf(b"a"),
+///     sha256.hash_bytes(&sha256.hash_bytes(b"a"))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashScheme {
+    leaf_prefix: Cow<'static, [u8]>,
+    node_prefix: Cow<'static, [u8]>,
+    double_hash_leaves: bool,
+}
+
+impl HashScheme {
+    /// `0x00` before leaves, `0x01` before internal nodes (RFC 6962).
+    pub const STANDARD: HashScheme = HashScheme {
+        leaf_prefix: Cow::Borrowed(&[0x00]),
+        node_prefix: Cow::Borrowed(&[0x01]),
+        double_hash_leaves: false,
+    };
+
+    /// No prefixes at all.
+    ///
+    /// Without prefixes an internal node is indistinguishable from a
+    /// 64-byte leaf, so only use this to match an existing format, and
+    /// preferably together with `with_double_hashed_leaves`.
+    pub const UNPREFIXED: HashScheme = HashScheme {
+        leaf_prefix: Cow::Borrowed(&[]),
+        node_prefix: Cow::Borrowed(&[]),
+        double_hash_leaves: false,
+    };
+
+    /// Create a scheme with custom leaf and internal node prefixes.
+    pub fn new(leaf_prefix: &[u8], node_prefix: &[u8]) -> Self {
+        HashScheme {
+            leaf_prefix: Cow::Owned(leaf_prefix.to_vec()),
+            node_prefix: Cow::Owned(node_prefix.to_vec()),
+            double_hash_leaves: false,
+        }
+    }
+
+    /// Hash leaf data once more before applying the leaf prefix.
+    pub fn with_double_hashed_leaves(mut self) -> Self {
+        self.double_hash_leaves = true;
+        self
+    }
+
+    /// Returns the bytes fed before leaf data.
+    pub fn leaf_prefix(&self) -> &[u8] {
+        &self.leaf_prefix
+    }
+
+    /// Returns the bytes fed before the children of an internal node.
+    pub fn node_prefix(&self) -> &[u8] {
+        &self.node_prefix
+    }
+
+    /// Returns true if leaf data is hashed twice.
+    pub fn double_hashes_leaves(&self) -> bool {
+        self.double_hash_leaves
+    }
+
+    /// Hash a leaf with `hasher` according to this scheme.
+    pub fn hash_leaf<H: Hasher + ?Sized>(&self, hasher: &H, data: &[u8]) -> [u8; 32] {
+        if self.double_hash_leaves {
+            let inner = hasher.hash_bytes(data);
+            hasher.hash_parts(&[&self.leaf_prefix, &inner])
+        } else {
+            hasher.hash_parts(&[&self.leaf_prefix, data])
+        }
+    }
+
+    /// Hash an internal node with `hasher` according to this scheme.
+    pub fn hash_internal<H: Hasher + ?Sized>(
+        &self,
+        hasher: &H,
+        left: &[u8],
+        right: &[u8],
+    ) -> [u8; 32] {
+        hasher.hash_parts(&[&self.node_prefix, left, right])
+    }
+}
+
+impl Default for HashScheme {
+    fn default() -> Self {
+        HashScheme::STANDARD
+    }
+}
+
+/// A hasher wrapper that builds trees with a given `HashScheme`.
+///
+/// Raw hashing is passed through to the inner hasher unchanged; only
+/// `hash_leaf` and `hash_internal` follow the scheme. Since trees, nodes
+/// and `verify_proof` all hash through those two methods, a tree and its
+/// proofs stay consistent as long as they share the same wrapper.
+#[derive(Clone)]
+pub struct SchemedHasher<H: Hasher> {
+    inner: H,
+    scheme: HashScheme,
+}
+
+impl<H: Hasher> SchemedHasher<H> {
+    /// Wrap a hasher with the given scheme.
+    pub fn new(inner: H, scheme: HashScheme) -> Self {
+        SchemedHasher { inner, scheme }
+    }
+
+    /// Returns the scheme.
+    pub fn scheme(&self) -> &HashScheme {
+        &self.scheme
+    }
+
+    /// Returns the wrapped hasher.
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+}
+
+impl<H: Hasher> Hasher for SchemedHasher<H> {
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        self.inner.hash_bytes(data)
+    }
+
+    /// Always `None`: even under `HashScheme::STANDARD` the trees differ
+    /// from the inner hasher's whenever it has its own `hash_leaf` and
+    /// `hash_internal` (as `PoseidonHasher` does), so the inner identifier
+    /// would make proofs claim an algorithm that cannot verify them.
+    fn hash_id(&self) -> Option<HashId> {
+        None
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        self.inner.hash_parts(parts)
+    }
+
+    fn hash_many(&self, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+        self.inner.hash_many(inputs)
+    }
+
+    fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+        self.scheme.hash_leaf(&self.inner, data)
+    }
+
+    fn hash_internal(&self, left: &[u8], right: &[u8]) -> [u8; 32] {
+        self.scheme.hash_internal(&self.inner, left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::simple_tree::verify_proof;
+    use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+    fn concat(parts: &[&[u8]]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    // =========================================================================
+    // Scheme Tests
+    // =========================================================================
+
+    #[test]
+    fn test_standard_matches_default_hooks() {
+        let sha = Sha256Hasher::new();
+        let scheme = HashScheme::default();
+        assert_eq!(scheme, HashScheme::STANDARD);
+        assert_eq!(scheme.hash_leaf(&sha, b"a"), sha.hash_leaf(b"a"));
+        assert_eq!(
+            scheme.hash_internal(&sha, &[1u8; 32], &[2u8; 32]),
+            sha.hash_internal(&[1u8; 32], &[2u8; 32])
+        );
+    }
+
+    #[test]
+    fn test_unprefixed() {
+        let sha = Sha256Hasher::new();
+        let scheme = HashScheme::UNPREFIXED;
+        assert_eq!(scheme.hash_leaf(&sha, b"a"), sha.hash_bytes(b"a"));
+        assert_eq!(
+            scheme.hash_internal(&sha, &[1u8; 32], &[2u8; 32]),
+            sha.hash_bytes(&concat(&[&[1u8; 32], &[2u8; 32]]))
+        );
+    }
+
+    #[test]
+    fn test_custom_prefixes_and_double_hashing() {
+        let sha = Sha256Hasher::new();
+        let scheme = HashScheme::new(b"leaf:", b"node:").with_double_hashed_leaves();
+        assert_eq!(scheme.leaf_prefix(), b"leaf:");
+        assert_eq!(scheme.node_prefix(), b"node:");
+        assert!(scheme.double_hashes_leaves());
+
+        let inner = sha.hash_bytes(b"a");
+        assert_eq!(
+            scheme.hash_leaf(&sha, b"a"),
+            sha.hash_bytes(&concat(&[b"leaf:", &inner]))
+        );
+        assert_eq!(
+            scheme.hash_internal(&sha, b"l", b"r"),
+            sha.hash_bytes(b"node:lr")
+        );
+    }
+
+    // =========================================================================
+    // SchemedHasher Tests
+    // =========================================================================
+
+    #[test]
+    fn test_raw_hashing_passes_through() {
+        let hasher = SchemedHasher::new(Sha256Hasher::new(), HashScheme::UNPREFIXED);
+        assert_eq!(
+            hasher.hash_bytes(b"a"),
+            Sha256Hasher::new().hash_bytes(b"a")
+        );
+        crate::hasher::tests::assert_hash_parts_matches(&hasher);
+        crate::hasher::tests::assert_batches_match(&hasher);
+    }
+
+    #[test]
+    fn test_hash_id_is_unidentified() {
+        let standard = SchemedHasher::new(Sha256Hasher::new(), HashScheme::STANDARD);
+        assert_eq!(standard.hash_id(), None);
+        let custom = SchemedHasher::new(Sha256Hasher::new(), HashScheme::UNPREFIXED);
+        assert_eq!(custom.hash_id(), None);
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_standard_scheme_over_poseidon_is_not_poseidon() {
+        use crate::hasher::PoseidonHasher;
+
+        // Poseidon compresses two-to-one, so byte-prefixed nodes differ
+        let hasher = SchemedHasher::new(PoseidonHasher::new(), HashScheme::STANDARD);
+        let mut schemed = SimpleMerkleTree::new(hasher);
+        let mut plain = SimpleMerkleTree::new(PoseidonHasher::new());
+        for data in [b"a", b"b", b"c"] {
+            schemed.add_leaf(data).unwrap();
+            plain.add_leaf(data).unwrap();
+        }
+        assert_ne!(schemed.get_root_bytes(), plain.get_root_bytes());
+
+        // The proof must not claim to be a Poseidon proof
+        let proof = schemed.prove(1).unwrap();
+        assert_eq!(proof.hash_id(), None);
+        assert_eq!(schemed.get_root_multihash(), None);
+        assert_eq!(
+            plain.prove(1).unwrap().hash_id(),
+            Some(HashId::POSEIDON_BN254)
+        );
+    }
+
+    #[test]
+    fn test_tree_uses_scheme() {
+        let scheme = HashScheme::UNPREFIXED.with_double_hashed_leaves();
+        let hasher = SchemedHasher::new(Sha256Hasher::new(), scheme);
+        let mut tree = SimpleMerkleTree::new(hasher.clone());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+
+        let sha = Sha256Hasher::new();
+        let leaf = |data: &[u8]| sha.hash_bytes(&sha.hash_bytes(data));
+        let expected = sha.hash_bytes(&concat(&[&leaf(b"a"), &leaf(b"b")]));
+        assert_eq!(tree.get_root_bytes(), Some(expected));
+
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove(1).unwrap();
+        assert_eq!(verify_proof(b"b", &proof, &root, &hasher), Ok(true));
+        assert_eq!(verify_proof(b"b", &proof, &root, &sha), Ok(false));
+    }
+}
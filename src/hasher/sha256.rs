@@ -1,9 +1,14 @@
-use super::Hasher;
+use super::{HashId, HashScheme, Hasher};
 use sha2::{Digest, Sha256};
 
 /// SHA-256 hasher using the `sha2` crate from RustCrypto.
 ///
 /// Produces a 32-byte array (256 bits).
+///
+/// Single messages use SHA-NI when the CPU has it (detected at runtime by
+/// `sha2`). On x86-64 CPUs without SHA-NI but with AVX2, `hash_many`,
+/// `hash_leaf_many` and `hash_internal_many` hash eight messages at a time
+/// instead.
 #[derive(Clone)]
 pub struct Sha256Hasher;
 
@@ -20,10 +25,145 @@ impl Sha256Hasher {
 }
 
 impl Hasher for Sha256Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::SHA2_256)
+    }
+
     fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
         let result = Sha256::digest(data);
         result.into()
     }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hash_many(&self, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+        #[cfg(target_arch = "x86_64")]
+        if use_multi_buffer(inputs.len()) {
+            return super::sha256_x8::hash_many(inputs);
+        }
+        inputs.iter().map(|input| self.hash_bytes(input)).collect()
+    }
+
+    fn hash_leaf_many(&self, leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+        #[cfg(target_arch = "x86_64")]
+        if use_multi_buffer(leaves.len()) {
+            return hash_leaf_many_x8(leaves);
+        }
+        leaves.iter().map(|data| self.hash_leaf(data)).collect()
+    }
+
+    fn hash_internal_many(&self, pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+        #[cfg(target_arch = "x86_64")]
+        if use_multi_buffer(pairs.len()) {
+            return hash_internal_many_x8(pairs);
+        }
+        pairs
+            .iter()
+            .map(|(left, right)| self.hash_internal(left, right))
+            .collect()
+    }
+}
+
+/// Whether a batch of `count` messages should take the AVX2 path.
+///
+/// SHA-NI hashes one message faster than AVX2 hashes eight, so the
+/// multi-buffer path is only used on CPUs without it.
+#[cfg(target_arch = "x86_64")]
+fn use_multi_buffer(count: usize) -> bool {
+    count > 1 && !is_x86_feature_detected!("sha") && super::sha256_x8::is_supported()
+}
+
+/// SHA-256 hasher that always sends batches to the AVX2 backend.
+///
+/// `Sha256Hasher` skips that backend on CPUs with SHA-NI, so benchmarks and
+/// tests use this type to measure and check it there too. Single messages
+/// are hashed as `Sha256Hasher` hashes them.
+///
+/// # Panics
+///
+/// The batch methods panic if the CPU does not support AVX2.
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[derive(Clone, Default)]
+pub struct MultiBufferSha256Hasher;
+
+#[cfg(target_arch = "x86_64")]
+impl Hasher for MultiBufferSha256Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::SHA2_256)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        Sha256Hasher.hash_bytes(data)
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        Sha256Hasher.hash_parts(parts)
+    }
+
+    fn hash_many(&self, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+        super::sha256_x8::hash_many(inputs)
+    }
+
+    fn hash_leaf_many(&self, leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+        hash_leaf_many_x8(leaves)
+    }
+
+    fn hash_internal_many(&self, pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+        hash_internal_many_x8(pairs)
+    }
+}
+
+/// Hash leaves under the standard scheme with the AVX2 backend.
+#[cfg(target_arch = "x86_64")]
+fn hash_leaf_many_x8(leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+    let prefix = HashScheme::STANDARD.leaf_prefix();
+    let messages: Vec<[&[u8]; 2]> = leaves.iter().map(|data| [prefix, data]).collect();
+    hash_concatenated_x8(&messages)
+}
+
+/// Hash child pairs under the standard scheme with the AVX2 backend.
+#[cfg(target_arch = "x86_64")]
+fn hash_internal_many_x8(pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+    let prefix = HashScheme::STANDARD.node_prefix();
+    let messages: Vec<[&[u8]; 3]> = pairs
+        .iter()
+        .map(|(left, right)| [prefix, left, right])
+        .collect();
+    hash_concatenated_x8(&messages)
+}
+
+/// Hash each message, given as the parts to concatenate, with the AVX2
+/// backend. Every message is written into one buffer, so a batch costs a
+/// single allocation however many messages it holds.
+#[cfg(target_arch = "x86_64")]
+fn hash_concatenated_x8<const N: usize>(messages: &[[&[u8]; N]]) -> Vec<[u8; 32]> {
+    let total = messages.iter().flatten().map(|part| part.len()).sum();
+    let mut buffer = Vec::with_capacity(total);
+    let mut ends = Vec::with_capacity(messages.len());
+    for parts in messages {
+        for part in parts {
+            buffer.extend_from_slice(part);
+        }
+        ends.push(buffer.len());
+    }
+
+    let mut start = 0;
+    let inputs: Vec<&[u8]> = ends
+        .iter()
+        .map(|&end| {
+            let input = &buffer[start..end];
+            start = end;
+            input
+        })
+        .collect();
+    super::sha256_x8::hash_many(&inputs)
 }
 
 #[cfg(test)]
@@ -68,8 +208,66 @@ mod tests {
 
     #[test]
     fn test_default_trait() {
-        let hasher = Sha256Hasher::default();
+        let hasher: Sha256Hasher = Default::default();
         let hash = hasher.hash_bytes(b"test");
         assert_eq!(hash.len(), 32);
     }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Sha256Hasher::new());
+    }
+
+    #[test]
+    fn test_batches_match() {
+        crate::hasher::tests::assert_batches_match(&Sha256Hasher::new());
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_concatenated_x8_matches_scalar() {
+        let hasher = Sha256Hasher::new();
+        let data: Vec<Vec<u8>> = (0..19u8).map(|i| vec![i; i as usize * 5]).collect();
+        let leaves: Vec<[&[u8]; 2]> = data.iter().map(|d| [&[0x00][..], d]).collect();
+        let pairs: Vec<[&[u8]; 3]> = data
+            .windows(2)
+            .map(|w| [&[0x01][..], &w[0][..], &w[1][..]])
+            .collect();
+
+        if !is_x86_feature_detected!("avx2") {
+            // The backend refuses to run rather than return wrong hashes
+            assert!(std::panic::catch_unwind(|| hash_concatenated_x8(&leaves)).is_err());
+            return;
+        }
+        let expected: Vec<[u8; 32]> = data.iter().map(|d| hasher.hash_leaf(d)).collect();
+        assert_eq!(hash_concatenated_x8(&leaves), expected);
+        let expected: Vec<[u8; 32]> = data
+            .windows(2)
+            .map(|w| hasher.hash_internal(&w[0], &w[1]))
+            .collect();
+        assert_eq!(hash_concatenated_x8(&pairs), expected);
+        assert!(hash_concatenated_x8::<2>(&[]).is_empty());
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_multi_buffer_tree_matches_scalar() {
+        use crate::merkle::tests::{leaf, tree_of};
+        use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+        let leaves: Vec<Vec<u8>> = (0..37).map(leaf).collect();
+
+        // Leaves are batched by apply_leaves and every level by get_root
+        let build = || {
+            let mut tree = SimpleMerkleTree::new(MultiBufferSha256Hasher);
+            tree.apply_leaves(leaves.iter().cloned().enumerate().collect(), 0)
+                .unwrap();
+            tree.get_root()
+        };
+        if !is_x86_feature_detected!("avx2") {
+            assert!(std::panic::catch_unwind(build).is_err());
+            return;
+        }
+        assert_eq!(build(), tree_of(37).get_root());
+    }
 }
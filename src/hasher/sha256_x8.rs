@@ -0,0 +1,222 @@
+//! Eight-lane SHA-256 using AVX2.
+//!
+//! Each 256-bit register holds the same state word for eight independent
+//! messages, so one pass of the compression function advances all eight.
+//! Messages of different lengths are padded separately; a lane's digest is
+//! taken after its last block and later blocks in that lane are ignored.
+
+use std::arch::x86_64::*;
+
+/// Number of messages hashed per pass.
+pub(crate) const LANES: usize = 8;
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Rotate each 32-bit lane right by a constant.
+macro_rules! rotr {
+    ($x:expr, $n:literal) => {
+        _mm256_or_si256(
+            _mm256_srli_epi32::<$n>($x),
+            _mm256_slli_epi32::<{ 32 - $n }>($x),
+        )
+    };
+}
+
+/// Returns true if this CPU can run `hash_many`.
+pub(crate) fn is_supported() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+/// Hash each input with SHA-256, eight at a time.
+///
+/// # Panics
+///
+/// Panics if the CPU does not support AVX2; check `is_supported` first.
+pub(crate) fn hash_many(inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+    assert!(is_supported(), "AVX2 is not available");
+    let mut out = Vec::with_capacity(inputs.len());
+    for group in inputs.chunks(LANES) {
+        // SAFETY: AVX2 support was checked above.
+        unsafe { hash_group(group, &mut out) };
+    }
+    out
+}
+
+/// Apply SHA-256 padding to a message.
+fn pad(message: &[u8]) -> Vec<u8> {
+    let len = (message.len() + 9).div_ceil(64) * 64;
+    let mut padded = Vec::with_capacity(len);
+    padded.extend_from_slice(message);
+    padded.push(0x80);
+    padded.resize(len - 8, 0);
+    padded.extend_from_slice(&(message.len() as u64).wrapping_mul(8).to_be_bytes());
+    padded
+}
+
+#[target_feature(enable = "avx2")]
+fn hash_group(group: &[&[u8]], out: &mut Vec<[u8; 32]>) {
+    let padded: Vec<Vec<u8>> = group.iter().map(|message| pad(message)).collect();
+    let block_counts: Vec<usize> = padded.iter().map(|p| p.len() / 64).collect();
+    let max_blocks = block_counts.iter().copied().max().unwrap_or(0);
+
+    let mut state = IV.map(|word| _mm256_set1_epi32(word as i32));
+    let mut digests = vec![[0u8; 32]; group.len()];
+
+    for block in 0..max_blocks {
+        // Message words by index, then by lane; finished lanes stay zero
+        let mut words = [[0u32; LANES]; 16];
+        for (lane, message) in padded.iter().enumerate() {
+            if block >= block_counts[lane] {
+                continue;
+            }
+            let bytes = &message[block * 64..(block + 1) * 64];
+            for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+                word[lane] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            }
+        }
+        compress(&mut state, &words);
+
+        let lanes = state.map(|vector| store(vector));
+        for (lane, digest) in digests.iter_mut().enumerate() {
+            if block + 1 == block_counts[lane] {
+                for (bytes, word) in digest.chunks_exact_mut(4).zip(&lanes) {
+                    bytes.copy_from_slice(&word[lane].to_be_bytes());
+                }
+            }
+        }
+    }
+    out.extend(digests);
+}
+
+#[target_feature(enable = "avx2")]
+fn load(words: &[u32; LANES]) -> __m256i {
+    // SAFETY: `words` is 32 readable bytes; the load is unaligned.
+    unsafe { _mm256_loadu_si256(words.as_ptr().cast()) }
+}
+
+#[target_feature(enable = "avx2")]
+fn store(vector: __m256i) -> [u32; LANES] {
+    let mut words = [0u32; LANES];
+    // SAFETY: `words` is 32 writable bytes; the store is unaligned.
+    unsafe { _mm256_storeu_si256(words.as_mut_ptr().cast(), vector) };
+    words
+}
+
+#[target_feature(enable = "avx2")]
+fn xor3(a: __m256i, b: __m256i, c: __m256i) -> __m256i {
+    _mm256_xor_si256(_mm256_xor_si256(a, b), c)
+}
+
+#[target_feature(enable = "avx2")]
+fn compress(state: &mut [__m256i; 8], block: &[[u32; LANES]; 16]) {
+    let mut w = [_mm256_setzero_si256(); 64];
+    for (slot, words) in w.iter_mut().zip(block) {
+        *slot = load(words);
+    }
+    for t in 16..64 {
+        let s0 = xor3(
+            rotr!(w[t - 15], 7),
+            rotr!(w[t - 15], 18),
+            _mm256_srli_epi32::<3>(w[t - 15]),
+        );
+        let s1 = xor3(
+            rotr!(w[t - 2], 17),
+            rotr!(w[t - 2], 19),
+            _mm256_srli_epi32::<10>(w[t - 2]),
+        );
+        w[t] = _mm256_add_epi32(
+            _mm256_add_epi32(w[t - 16], s0),
+            _mm256_add_epi32(w[t - 7], s1),
+        );
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (&k, &word) in K.iter().zip(&w) {
+        let s1 = xor3(rotr!(e, 6), rotr!(e, 11), rotr!(e, 25));
+        let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+        let t1 = _mm256_add_epi32(
+            _mm256_add_epi32(h, s1),
+            _mm256_add_epi32(ch, _mm256_add_epi32(_mm256_set1_epi32(k as i32), word)),
+        );
+        let s0 = xor3(rotr!(a, 2), rotr!(a, 13), rotr!(a, 22));
+        let maj = xor3(
+            _mm256_and_si256(a, b),
+            _mm256_and_si256(a, c),
+            _mm256_and_si256(b, c),
+        );
+        let t2 = _mm256_add_epi32(s0, maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = _mm256_add_epi32(d, t1);
+        d = c;
+        c = b;
+        b = a;
+        a = _mm256_add_epi32(t1, t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = _mm256_add_epi32(*word, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_pad_lengths() {
+        for len in 0..200 {
+            let padded = pad(&vec![0u8; len]);
+            assert_eq!(padded.len() % 64, 0);
+            assert!(padded.len() >= len + 9);
+            assert!(padded.len() < len + 9 + 64);
+        }
+    }
+
+    #[test]
+    fn test_matches_sha2() {
+        // Mixed lengths around the block boundaries, and partial groups
+        let messages: Vec<Vec<u8>> = (0..150u32)
+            .map(|len| (0..len).map(|i| (i * 31 + len) as u8).collect())
+            .collect();
+        if !is_x86_feature_detected!("avx2") {
+            // Without AVX2 the only thing to check is that it refuses to run
+            let inputs: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+            assert!(std::panic::catch_unwind(|| hash_many(&inputs)).is_err());
+            return;
+        }
+        for count in [1, 7, 8, 9, 150] {
+            let inputs: Vec<&[u8]> = messages[..count].iter().map(Vec::as_slice).collect();
+            let expected: Vec<[u8; 32]> = inputs
+                .iter()
+                .map(|input| Sha256::digest(input).into())
+                .collect();
+            assert_eq!(hash_many(&inputs), expected);
+        }
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        if is_x86_feature_detected!("avx2") {
+            assert!(hash_many(&[]).is_empty());
+        } else {
+            assert!(std::panic::catch_unwind(|| hash_many(&[])).is_err());
+        }
+    }
+}
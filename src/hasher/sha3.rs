@@ -0,0 +1,76 @@
+use super::{HashId, Hasher};
+use sha3::{Digest, Sha3_256};
+
+/// SHA3-256 hasher using the `sha3` crate from RustCrypto.
+///
+/// Produces a 32-byte array (256 bits). Requires the `sha3` feature.
+#[derive(Clone)]
+pub struct Sha3_256Hasher;
+
+impl Default for Sha3_256Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha3_256Hasher {
+    pub fn new() -> Self {
+        Sha3_256Hasher
+    }
+}
+
+impl Hasher for Sha3_256Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::SHA3_256)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        Sha3_256::digest(data).into()
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hash() {
+        let hasher = Sha3_256Hasher::new();
+        // "hello" SHA3-256 hash
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0x33, 0x38, 0xbe, 0x69, 0x4f, 0x50, 0xc5, 0xf3, 0x38, 0x81, 0x49, 0x86, 0xcd, 0xf0,
+            0x68, 0x64, 0x53, 0xa8, 0x88, 0xb8, 0x4f, 0x42, 0x4d, 0x79, 0x2a, 0xf4, 0xb9, 0x20,
+            0x23, 0x98, 0xf3, 0x92,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_different_inputs_different_hashes() {
+        let hasher = Sha3_256Hasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: Sha3_256Hasher = Default::default();
+        assert_eq!(
+            hasher.hash_bytes(b"test"),
+            Sha3_256Hasher::new().hash_bytes(b"test")
+        );
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Sha3_256Hasher::new());
+    }
+}
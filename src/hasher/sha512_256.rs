@@ -0,0 +1,87 @@
+use super::{HashId, Hasher};
+use sha2::{Digest, Sha512_256};
+
+/// SHA-512/256 hasher using the `sha2` crate from RustCrypto.
+///
+/// SHA-512 with a distinct initial state, truncated to 256 bits. It is
+/// usually faster than SHA-256 on 64-bit hosts. Requires the `sha512-256`
+/// feature, which adds no dependency.
+#[derive(Clone)]
+pub struct Sha512_256Hasher;
+
+impl Default for Sha512_256Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha512_256Hasher {
+    pub fn new() -> Self {
+        Sha512_256Hasher
+    }
+}
+
+impl Hasher for Sha512_256Hasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::SHA2_512_256)
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+        Sha512_256::digest(data).into()
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha512_256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hash() {
+        let hasher = Sha512_256Hasher::new();
+        // "hello" SHA-512/256 hash
+        let hash = hasher.hash_bytes(b"hello");
+        let expected: [u8; 32] = [
+            0xe3, 0x0d, 0x87, 0xcf, 0xa2, 0xa7, 0x5d, 0xb5, 0x45, 0xea, 0xc4, 0xd6, 0x1b, 0xaf,
+            0x97, 0x03, 0x66, 0xa8, 0x35, 0x7c, 0x7f, 0x72, 0xfa, 0x95, 0xb5, 0x2d, 0x0a, 0xcc,
+            0xb6, 0x98, 0xf1, 0x3a,
+        ];
+        assert_eq!(hash, expected);
+    }
+
+    #[test]
+    fn test_different_inputs_different_hashes() {
+        let hasher = Sha512_256Hasher::new();
+        assert_ne!(hasher.hash_bytes(b"hello"), hasher.hash_bytes(b"world"));
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let hasher: Sha512_256Hasher = Default::default();
+        assert_eq!(
+            hasher.hash_bytes(b"test"),
+            Sha512_256Hasher::new().hash_bytes(b"test")
+        );
+    }
+
+    #[test]
+    fn test_differs_from_sha256() {
+        let hash = Sha512_256Hasher::new().hash_bytes(b"hello");
+        assert_ne!(
+            hash,
+            crate::hasher::Sha256Hasher::new().hash_bytes(b"hello")
+        );
+    }
+
+    #[test]
+    fn test_hash_parts_matches_hash_bytes() {
+        crate::hasher::tests::assert_hash_parts_matches(&Sha512_256Hasher::new());
+    }
+}
@@ -1,4 +1,4 @@
-use super::Hasher;
+use super::{HashId, Hasher};
 
 /// A simple placeholder hasher for testing (NOT cryptographically secure!)
 ///
@@ -20,6 +20,10 @@ impl SimpleHasher {
 }
 
 impl Hasher for SimpleHasher {
+    fn hash_id(&self) -> Option<HashId> {
+        Some(HashId::SIMPLE)
+    }
+
     fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
         // Simple sum-based "hash" - just for demonstration
         let sum: u32 = data.iter().map(|&b| b as u32).sum();
@@ -28,6 +32,17 @@ impl Hasher for SimpleHasher {
         hash[0..4].copy_from_slice(&sum.to_be_bytes());
         hash
     }
+
+    fn hash_parts(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let sum: u32 = parts
+            .iter()
+            .flat_map(|part| part.iter())
+            .map(|&b| b as u32)
+            .sum();
+        let mut hash = [0u8; 32];
+        hash[0..4].copy_from_slice(&sum.to_be_bytes());
+        hash
+    }
 }
 
 #[cfg(test)]
@@ -59,7 +74,7 @@ mod tests {
 
     #[test]
     fn test_default_trait() {
-        let hasher = SimpleHasher::default();
+        let hasher: SimpleHasher = Default::default();
         let hash = hasher.hash_bytes(b"test");
         assert_eq!(hash.len(), 32);
     }
@@ -1,11 +1,39 @@
 pub mod hasher;
 pub mod merkle;
+#[cfg(feature = "serde")]
+pub mod serde_hex;
 
 // Re-export main types at crate root for convenience
-pub use hasher::{Hasher, Sha256Hasher, SimpleHasher};
-pub use merkle::simple_tree::verify_proof;
+#[cfg(feature = "blake3")]
+pub use hasher::Blake3Hasher;
+#[cfg(any(feature = "hmac", feature = "blake2", feature = "blake3"))]
+pub use hasher::KeyedHasher;
+#[cfg(feature = "poseidon")]
+pub use hasher::PoseidonHasher;
+#[cfg(feature = "ripemd")]
+pub use hasher::Ripemd160Hasher;
+#[cfg(feature = "sha3")]
+pub use hasher::Sha3_256Hasher;
+#[cfg(feature = "sha512-256")]
+pub use hasher::Sha512_256Hasher;
+#[cfg(feature = "blake2")]
+pub use hasher::{Blake2bHasher, Blake2sHasher};
+pub use hasher::{
+    DigestHasher, DynHasher, HashScheme, Hasher, HasherRegistry, PersonalizedHasher, SchemedHasher,
+    Sha256Hasher, SimpleHasher,
+};
+#[cfg(all(feature = "file-store", unix))]
+pub use merkle::FileStore;
+#[cfg(feature = "mmap")]
+pub use merkle::MappedMerkleTree;
+pub use merkle::jellyfish::{JellyfishMerkleTree, MemoryTreeStore};
+pub use merkle::simple_tree::{verify_proof, verify_proof_from_hash};
+pub use merkle::sorted_tree::verify_absence_proof;
 pub use merkle::{
-    Hash, InternalNode, LeafNode, MerkleTree, MerkleTreeError, Node, Proof, SimpleMerkleTree,
+    AbsenceProof, Digest32, DynMerkleTree, Hash, HashOnlyNodeStore, InternalNode, LeafNode,
+    MemoryNodeStore, MerkleTree, MerkleTreeError, NeighborProof, Node, NodeStore, ParseHashError,
+    PersistentMerkleTree, Proof, PrunableNodeStore, SimpleMerkleTree, Snapshot, SortedMerkleTree,
+    StoredMerkleTree, TreeDiff, TreeSnapshot,
 };
 
 /// Convert bytes to a hexadecimal string.
@@ -0,0 +1,114 @@
+use crate::merkle::proof::Proof;
+
+/// A membership proof for one of the neighbours of a missing key.
+///
+/// Carries the neighbour's key alongside its proof so that a verifier
+/// can check both the ordering and the inclusion of the neighbour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeighborProof {
+    /// The key stored in the neighbouring leaf.
+    key: Vec<u8>,
+    /// Membership proof for the neighbouring leaf.
+    proof: Proof,
+}
+
+impl NeighborProof {
+    /// Create a new neighbour proof from a key and its membership proof.
+    pub fn new(key: Vec<u8>, proof: Proof) -> Self {
+        NeighborProof { key, proof }
+    }
+
+    /// Returns the key stored in the neighbouring leaf.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the membership proof for the neighbouring leaf.
+    pub fn proof(&self) -> &Proof {
+        &self.proof
+    }
+}
+
+/// A proof that a key is absent from a sorted Merkle tree.
+///
+/// Absence is shown by proving membership of the two leaves that would
+/// surround the key, and checking that they are adjacent (their indices
+/// differ by one). When the key sorts before every leaf only the right
+/// neighbour (index 0) is present; when it sorts after every leaf only the
+/// left neighbour is present, and its proof must follow the rightmost path
+/// of the tree.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::merkle::absence_proof::{AbsenceProof, NeighborProof};
+/// use merkle_trees::merkle::proof::Proof;
+///
+/// let left = NeighborProof::new(b"a".to_vec(), Proof::new(0, vec![[1u8; 32]]));
+/// let right = NeighborProof::new(b"c".to_vec(), Proof::new(1, vec![[2u8; 32]]));
+/// let proof = AbsenceProof::new(Some(left), Some(right));
+///
+/// assert_eq!(proof.left().unwrap().key(), b"a");
+/// assert_eq!(proof.right().unwrap().proof().index(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsenceProof {
+    /// The greatest key smaller than the missing key, if any.
+    left: Option<NeighborProof>,
+    /// The smallest key greater than the missing key, if any.
+    right: Option<NeighborProof>,
+}
+
+impl AbsenceProof {
+    /// Create a new absence proof from its left and right neighbours.
+    pub fn new(left: Option<NeighborProof>, right: Option<NeighborProof>) -> Self {
+        AbsenceProof { left, right }
+    }
+
+    /// Returns the left neighbour, or `None` if the key sorts before every leaf.
+    pub fn left(&self) -> Option<&NeighborProof> {
+        self.left.as_ref()
+    }
+
+    /// Returns the right neighbour, or `None` if the key sorts after every leaf.
+    pub fn right(&self) -> Option<&NeighborProof> {
+        self.right.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbor_proof_accessors() {
+        let neighbor = NeighborProof::new(b"key".to_vec(), Proof::new(3, vec![[7u8; 32]]));
+        assert_eq!(neighbor.key(), b"key");
+        assert_eq!(neighbor.proof().index(), 3);
+    }
+
+    #[test]
+    fn test_absence_proof_one_sided() {
+        let right = NeighborProof::new(b"b".to_vec(), Proof::new(0, vec![]));
+        let proof = AbsenceProof::new(None, Some(right));
+        assert!(proof.left().is_none());
+        assert_eq!(proof.right().unwrap().key(), b"b");
+    }
+
+    #[test]
+    fn test_absence_proof_equality() {
+        let make = || {
+            AbsenceProof::new(
+                Some(NeighborProof::new(
+                    b"a".to_vec(),
+                    Proof::new(0, vec![[1u8; 32]]),
+                )),
+                Some(NeighborProof::new(
+                    b"c".to_vec(),
+                    Proof::new(1, vec![[2u8; 32]]),
+                )),
+            )
+        };
+        assert_eq!(make(), make());
+    }
+}
@@ -0,0 +1,174 @@
+//! Low-level helpers for the crate's binary encodings.
+//!
+//! Integers are LEB128 varints, byte strings are prefixed with their
+//! varint length, and hashes are written as 32 raw bytes. Readers return
+//! `None` on truncated or malformed input so callers can map it to their
+//! own error type, or use `read_varint` to get a `MerkleTreeError`.
+
+use crate::merkle::MerkleTreeError;
+
+/// Append an unsigned LEB128 varint.
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Append a length-prefixed byte string.
+pub(crate) fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// A cursor over an encoded buffer.
+pub(crate) struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Reader { input }
+    }
+
+    /// Returns the bytes not consumed yet.
+    pub(crate) fn rest(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// Returns true if every byte has been consumed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        let (&byte, rest) = self.input.split_first()?;
+        self.input = rest;
+        Some(byte)
+    }
+
+    /// Read an unsigned LEB128 varint, rejecting overlong encodings.
+    pub(crate) fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 {
+                return None;
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                // A trailing zero byte means a shorter encoding exists
+                if byte == 0 && shift > 0 {
+                    return None;
+                }
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Read a varint that must fit in a `usize`.
+    pub(crate) fn len(&mut self) -> Option<usize> {
+        usize::try_from(self.varint()?).ok()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.input.len() {
+            return None;
+        }
+        let (head, rest) = self.input.split_at(len);
+        self.input = rest;
+        Some(head)
+    }
+
+    /// Read a length-prefixed byte string.
+    pub(crate) fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    pub(crate) fn hash(&mut self) -> Option<[u8; 32]> {
+        self.take(32)?.try_into().ok()
+    }
+
+    /// Read a count of items that each take at least `min_size` bytes.
+    ///
+    /// Rejects counts the remaining input cannot hold, so a corrupt count
+    /// cannot trigger a huge allocation.
+    pub(crate) fn count(&mut self, min_size: usize) -> Option<usize> {
+        let count = self.len()?;
+        (count.checked_mul(min_size.max(1))? <= self.input.len()).then_some(count)
+    }
+}
+
+/// Read a varint, telling truncated input apart from a malformed encoding.
+pub(crate) fn read_varint(reader: &mut Reader) -> Result<u64, MerkleTreeError> {
+    let rest = reader.rest();
+    reader.varint().ok_or_else(|| {
+        // A varint is at most 10 bytes and ends at a byte below 0x80
+        if rest.len() < 10 && rest.iter().all(|byte| byte & 0x80 != 0) {
+            MerkleTreeError::TruncatedInput
+        } else {
+            MerkleTreeError::InvalidEncoding
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            put_varint(&mut out, value);
+            let mut reader = Reader::new(&out);
+            assert_eq!(reader.varint(), Some(value));
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_varint_known_encoding() {
+        let mut out = Vec::new();
+        put_varint(&mut out, 300);
+        assert_eq!(out, vec![0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_varint_rejects_overlong_and_truncated() {
+        assert_eq!(Reader::new(&[0x80, 0x00]).varint(), None);
+        assert_eq!(Reader::new(&[0x80]).varint(), None);
+        assert_eq!(Reader::new(&[0xff; 10]).varint(), None);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut out = Vec::new();
+        put_bytes(&mut out, b"hello");
+        let mut reader = Reader::new(&out);
+        assert_eq!(reader.bytes(), Some(b"hello".as_slice()));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_bytes_rejects_short_input() {
+        assert_eq!(Reader::new(&[5, b'a', b'b']).bytes(), None);
+    }
+
+    #[test]
+    fn test_count_rejects_impossible_lengths() {
+        let mut out = Vec::new();
+        put_varint(&mut out, 1_000_000);
+        out.extend_from_slice(&[0u8; 64]);
+        assert_eq!(Reader::new(&out).count(32), None);
+
+        let mut out = Vec::new();
+        put_varint(&mut out, 2);
+        out.extend_from_slice(&[0u8; 64]);
+        assert_eq!(Reader::new(&out).count(32), Some(2));
+    }
+}
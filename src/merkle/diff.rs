@@ -0,0 +1,359 @@
+use crate::merkle::hash::Hash;
+use crate::merkle::node::Node;
+use crate::merkle::snapshot::tree_height;
+
+/// The leaf indices at which two trees differ.
+///
+/// Indices are positional and describe the change from the first tree to
+/// the second: `added` leaves exist only in the second tree, `removed`
+/// leaves exist only in the first, and `changed` leaves exist in both with
+/// different data. Each list is in ascending order.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let mut a = SimpleMerkleTree::new(Sha256Hasher::new());
+/// let mut b = SimpleMerkleTree::new(Sha256Hasher::new());
+/// for data in [b"a", b"b", b"c"] {
+///     a.add_leaf(data).unwrap();
+/// }
+/// for data in [b"a", b"x", b"c", b"d"] {
+///     b.add_leaf(data).unwrap();
+/// }
+///
+/// let diff = a.diff(&b);
+/// assert_eq!(diff.changed(), &[1]);
+/// assert_eq!(diff.added(), &[3]);
+/// assert!(diff.removed().is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    /// Indices present only in the second tree.
+    added: Vec<u64>,
+    /// Indices present only in the first tree.
+    removed: Vec<u64>,
+    /// Indices present in both trees with different leaves.
+    changed: Vec<u64>,
+}
+
+impl TreeDiff {
+    /// Returns the indices present only in the second tree.
+    pub fn added(&self) -> &[u64] {
+        &self.added
+    }
+
+    /// Returns the indices present only in the first tree.
+    pub fn removed(&self) -> &[u64] {
+        &self.removed
+    }
+
+    /// Returns the indices present in both trees with different leaves.
+    pub fn changed(&self) -> &[u64] {
+        &self.changed
+    }
+
+    /// Returns true if the trees hold the same leaves.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two trees given their roots and leaf counts.
+///
+/// Both trees are descended together, level by level, and a pair of
+/// subtrees is skipped when their hashes match. Matching hashes alone are
+/// not enough: because the last node of an odd level is duplicated,
+/// `[a, b, c]` and `[a, b, c, c]` share a root. Subtrees are therefore only
+/// skipped when they also cover the same number of leaves.
+///
+/// # Complexity
+///
+/// - Time: O(d log n) hash comparisons for d differing leaves
+/// - Space: O(log n) recursion depth
+pub(crate) fn diff_trees(a: Option<&Node>, a_size: u64, b: Option<&Node>, b_size: u64) -> TreeDiff {
+    let mut diff = TreeDiff::default();
+    let height = tree_height(a_size).max(tree_height(b_size));
+    walk(
+        a.map(|node| Subtree::root(node, a_size)),
+        b.map(|node| Subtree::root(node, b_size)),
+        0,
+        height,
+        &mut diff,
+    );
+    diff
+}
+
+/// Count the real leaves under node `index` at `level` in a tree of `size`.
+///
+/// Returns 0 if the node does not exist.
+pub(crate) fn covered_leaves(size: u64, level: u32, index: u64) -> u64 {
+    let start = index.checked_shl(level).unwrap_or(u64::MAX);
+    let width = 1u64.checked_shl(level).unwrap_or(u64::MAX);
+    size.saturating_sub(start).min(width)
+}
+
+/// Returns true if two nodes at the same position hold the same leaves.
+///
+/// Each node is given by its hash and the number of real leaves it covers;
+/// see `diff_trees` for why the hash alone is not enough.
+pub(crate) fn same_subtree(a_hash: &[u8], a_count: u64, b_hash: &[u8], b_count: u64) -> bool {
+    a_count == b_count && a_hash == b_hash
+}
+
+/// The children of the given nodes at `level` (above 0) that cover real
+/// leaves in a tree of `size`, in order.
+///
+/// These are the nodes to compare next once `parents` are known to differ.
+/// A right child covering no real leaves is a duplicate of its sibling and
+/// is skipped.
+pub(crate) fn child_indices(
+    size: u64,
+    level: u32,
+    parents: &[u64],
+) -> impl Iterator<Item = u64> + '_ {
+    parents
+        .iter()
+        .flat_map(|&index| [2 * index, 2 * index + 1])
+        .filter(move |&child| covered_leaves(size, level - 1, child) > 0)
+}
+
+/// A subtree of one of the trees being compared.
+#[derive(Clone, Copy)]
+struct Subtree<'a> {
+    node: &'a Node,
+    /// The level of `node` in its own tree (0 for leaves).
+    height: u32,
+    /// The number of real (non-duplicated) leaves under `node`.
+    count: u64,
+}
+
+impl<'a> Subtree<'a> {
+    fn root(node: &'a Node, size: u64) -> Self {
+        Subtree {
+            node,
+            height: tree_height(size),
+            count: size,
+        }
+    }
+
+    /// Split into the subtrees covering the left and right halves of a
+    /// range at the given level.
+    fn split(self, level: u32) -> (Option<Self>, Option<Self>) {
+        match self.node {
+            Node::Internal(internal) if self.height == level => {
+                let half = 1u64 << (level - 1);
+                let left = Subtree {
+                    node: internal.left(),
+                    height: level - 1,
+                    count: self.count.min(half),
+                };
+                // A right child covering no real leaves is a duplicate
+                let right = (self.count > half).then(|| Subtree {
+                    node: internal.right(),
+                    height: level - 1,
+                    count: self.count - half,
+                });
+                (Some(left), right)
+            }
+            // The root of the shorter tree sits in the left-most range of
+            // the taller one
+            _ => (Some(self), None),
+        }
+    }
+}
+
+fn walk(a: Option<Subtree>, b: Option<Subtree>, start: u64, level: u32, diff: &mut TreeDiff) {
+    let (a, b) = match (a, b) {
+        (None, None) => return,
+        (Some(a), None) => {
+            diff.removed.extend(start..start + a.count);
+            return;
+        }
+        (None, Some(b)) => {
+            diff.added.extend(start..start + b.count);
+            return;
+        }
+        (Some(a), Some(b)) => (a, b),
+    };
+
+    if a.height == b.height && same_subtree(a.node.hash(), a.count, b.node.hash(), b.count) {
+        return;
+    }
+    if level == 0 {
+        diff.changed.push(start);
+        return;
+    }
+
+    let (a_left, a_right) = a.split(level);
+    let (b_left, b_right) = b.split(level);
+    walk(a_left, b_left, start, level - 1, diff);
+    walk(
+        a_right,
+        b_right,
+        start + (1u64 << (level - 1)),
+        level - 1,
+        diff,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+    fn tree(leaves: &[&[u8]]) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for leaf in leaves {
+            tree.add_leaf(leaf).unwrap();
+        }
+        tree
+    }
+
+    fn numbered(count: u32) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..count {
+            tree.add_leaf(format!("leaf{}", i).as_bytes()).unwrap();
+        }
+        tree
+    }
+
+    // =========================================================================
+    // Helper Tests
+    // =========================================================================
+
+    #[test]
+    fn test_covered_leaves() {
+        // Five leaves: level 1 holds [0,1] [2,3] [4]
+        assert_eq!(covered_leaves(5, 1, 0), 2);
+        assert_eq!(covered_leaves(5, 1, 2), 1);
+        assert_eq!(covered_leaves(5, 1, 3), 0);
+        assert_eq!(covered_leaves(5, 3, 0), 5);
+        assert_eq!(covered_leaves(5, 64, 1), 0);
+    }
+
+    #[test]
+    fn test_child_indices_skip_duplicates() {
+        // Five leaves: level 1 holds [0,1] [2,3] [4], so node 2 has one child
+        let children: Vec<u64> = child_indices(5, 1, &[0, 2]).collect();
+        assert_eq!(children, vec![0, 1, 4]);
+        let children: Vec<u64> = child_indices(5, 3, &[0]).collect();
+        assert_eq!(children, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_same_subtree_needs_equal_counts() {
+        let hash = [1u8; 32];
+        assert!(same_subtree(&hash, 3, &hash, 3));
+        assert!(!same_subtree(&hash, 3, &hash, 4));
+        assert!(!same_subtree(&hash, 3, &[2u8; 32], 3));
+    }
+
+    // =========================================================================
+    // Identical Tree Tests
+    // =========================================================================
+
+    #[test]
+    fn test_identical_trees() {
+        for size in [0, 1, 2, 3, 7, 8, 13] {
+            let diff = numbered(size).diff(&numbered(size));
+            assert!(diff.is_empty(), "size {}", size);
+        }
+    }
+
+    // =========================================================================
+    // Changed Leaf Tests
+    // =========================================================================
+
+    #[test]
+    fn test_single_change() {
+        let a = tree(&[b"a", b"b", b"c", b"d"]);
+        let b = tree(&[b"a", b"b", b"x", b"d"]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.changed(), &[2]);
+        assert!(diff.added().is_empty());
+        assert!(diff.removed().is_empty());
+    }
+
+    #[test]
+    fn test_several_changes_in_large_tree() {
+        let a = numbered(200);
+        let mut b = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..200 {
+            let data = if [0, 127, 128, 199].contains(&i) {
+                format!("changed{}", i)
+            } else {
+                format!("leaf{}", i)
+            };
+            b.add_leaf(data.as_bytes()).unwrap();
+        }
+        assert_eq!(a.diff(&b).changed(), &[0, 127, 128, 199]);
+    }
+
+    #[test]
+    fn test_change_in_duplicated_last_leaf() {
+        let a = tree(&[b"a", b"b", b"c"]);
+        let b = tree(&[b"a", b"b", b"x"]);
+        assert_eq!(a.diff(&b).changed(), &[2]);
+    }
+
+    // =========================================================================
+    // Added and Removed Leaf Tests
+    // =========================================================================
+
+    #[test]
+    fn test_appended_leaves() {
+        let diff = numbered(5).diff(&numbered(9));
+        assert_eq!(diff.added(), &[5, 6, 7, 8]);
+        assert!(diff.removed().is_empty());
+        assert!(diff.changed().is_empty());
+    }
+
+    #[test]
+    fn test_removed_leaves() {
+        let diff = numbered(9).diff(&numbered(5));
+        assert_eq!(diff.removed(), &[5, 6, 7, 8]);
+        assert!(diff.added().is_empty());
+        assert!(diff.changed().is_empty());
+    }
+
+    #[test]
+    fn test_duplicated_leaf_is_not_hidden() {
+        // Both trees have the same root because the odd leaf is duplicated
+        let a = tree(&[b"a", b"b", b"c"]);
+        let b = tree(&[b"a", b"b", b"c", b"c"]);
+        assert_eq!(a.get_root(), b.get_root());
+
+        let diff = a.diff(&b);
+        assert_eq!(diff.added(), &[3]);
+        assert!(diff.changed().is_empty());
+    }
+
+    #[test]
+    fn test_empty_trees() {
+        let empty = numbered(0);
+        assert_eq!(empty.diff(&numbered(3)).added(), &[0, 1, 2]);
+        assert_eq!(numbered(3).diff(&empty).removed(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_single_leaf_against_larger_tree() {
+        let a = tree(&[b"a"]);
+        let b = tree(&[b"x", b"b", b"c"]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.changed(), &[0]);
+        assert_eq!(diff.added(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_changes_and_growth_together() {
+        let a = tree(&[b"a", b"b", b"c"]);
+        let b = tree(&[b"a", b"x", b"c", b"d", b"e", b"f"]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.changed(), &[1]);
+        assert_eq!(diff.added(), &[3, 4, 5]);
+        assert!(diff.removed().is_empty());
+    }
+}
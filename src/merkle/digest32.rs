@@ -0,0 +1,440 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::bytes_to_hex;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A 32-byte hash, such as a tree root or a proof sibling.
+///
+/// `Digest32` converts between the raw bytes that `verify` and
+/// `verify_proof` take and the text forms that `get_root` and external
+/// systems use. It parses and formats lowercase hex (`Display`, `FromStr`,
+/// `LowerHex`) and standard base64. Hex input may be upper or lower case
+/// and may start with `0x`.
+///
+/// A `Digest32` dereferences to `[u8; 32]`, so `&digest` can be passed
+/// wherever a root `&[u8; 32]` is expected.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{Digest32, MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+/// tree.add_leaf(b"a").unwrap();
+/// tree.add_leaf(b"b").unwrap();
+///
+/// // A root received as text from elsewhere
+/// let root: Digest32 = tree.get_root().unwrap().parse().unwrap();
+/// let proof = tree.prove(1).unwrap();
+/// assert!(tree.verify(&proof, b"b", &root));
+/// assert_eq!(Some(root), tree.get_root_digest());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Digest32([u8; 32]);
+
+impl Digest32 {
+    /// Wrap raw hash bytes.
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Digest32(bytes)
+    }
+
+    /// Returns the raw hash bytes.
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Parse 64 hex digits, optionally prefixed with `0x`.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseHashError> {
+        let (offset, digits) = match hex.strip_prefix("0x").or(hex.strip_prefix("0X")) {
+            Some(digits) => (2, digits),
+            None => (0, hex),
+        };
+        if digits.len() != 64 {
+            return Err(ParseHashError::InvalidLength {
+                expected: 64,
+                found: digits.len(),
+            });
+        }
+        let mut bytes = [0u8; 32];
+        decode_hex(digits, offset, &mut bytes)?;
+        Ok(Digest32(bytes))
+    }
+
+    /// Format as 64 lowercase hex digits, without a prefix.
+    pub fn to_hex(&self) -> String {
+        bytes_to_hex(&self.0)
+    }
+
+    /// Parse standard base64 (RFC 4648), with or without the `=` padding.
+    pub fn from_base64(base64: &str) -> Result<Self, ParseHashError> {
+        let digits = base64.strip_suffix('=').unwrap_or(base64);
+        if digits.len() != 43 {
+            return Err(ParseHashError::InvalidLength {
+                expected: 44,
+                found: base64.len(),
+            });
+        }
+
+        // 43 digits carry 258 bits; the last two must be zero
+        let mut bits: u32 = 0;
+        let mut bit_count = 0;
+        let mut bytes = Vec::with_capacity(32);
+        for (index, character) in digits.char_indices() {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|&c| char::from(c) == character)
+                .ok_or(ParseHashError::InvalidCharacter { character, index })?;
+            bits = (bits << 6) | value as u32;
+            bit_count += 6;
+            if bit_count >= 8 {
+                bit_count -= 8;
+                bytes.push((bits >> bit_count) as u8);
+                bits &= (1 << bit_count) - 1;
+            }
+        }
+        if bits != 0 {
+            return Err(ParseHashError::NonCanonical);
+        }
+        let bytes: [u8; 32] = bytes.try_into().expect("length was checked");
+        Ok(Digest32(bytes))
+    }
+
+    /// Format as standard base64 with padding (44 characters).
+    pub fn to_base64(&self) -> String {
+        let mut out = String::with_capacity(44);
+        for chunk in self.0.chunks(3) {
+            let group = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &byte)| acc | (byte as u32) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                let value = (group >> (18 - 6 * i)) & 0x3f;
+                out.push(char::from(BASE64_ALPHABET[value as usize]));
+            }
+        }
+        while !out.len().is_multiple_of(4) {
+            out.push('=');
+        }
+        out
+    }
+}
+
+/// Decode `2 * out.len()` hex digits into `out`.
+///
+/// `offset` is added to the index reported for an invalid character, so
+/// that it points into the caller's original input. The length of
+/// `digits` must already have been checked.
+pub(crate) fn decode_hex(
+    digits: &str,
+    offset: usize,
+    out: &mut [u8],
+) -> Result<(), ParseHashError> {
+    debug_assert_eq!(digits.len(), 2 * out.len());
+    let mut chars = digits.char_indices();
+    for byte in out.iter_mut() {
+        for _ in 0..2 {
+            let (index, character) = chars.next().expect("length was checked");
+            let value = character
+                .to_digit(16)
+                .ok_or(ParseHashError::InvalidCharacter {
+                    character,
+                    index: offset + index,
+                })?;
+            *byte = (*byte << 4) | value as u8;
+        }
+    }
+    Ok(())
+}
+
+impl From<[u8; 32]> for Digest32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Digest32(bytes)
+    }
+}
+
+impl From<Digest32> for [u8; 32] {
+    fn from(digest: Digest32) -> Self {
+        digest.0
+    }
+}
+
+impl PartialEq<[u8; 32]> for Digest32 {
+    fn eq(&self, other: &[u8; 32]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Deref for Digest32 {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Digest32 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for Digest32 {
+    type Err = ParseHashError;
+
+    /// Parse hex, as produced by `Display` and `get_root`.
+    fn from_str(s: &str) -> Result<Self, ParseHashError> {
+        Digest32::from_hex(s)
+    }
+}
+
+impl fmt::Display for Digest32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for Digest32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest32({})", self.to_hex())
+    }
+}
+
+impl fmt::LowerHex for Digest32 {
+    /// Lowercase hex; the alternate form (`{:#x}`) adds a `0x` prefix.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Digest32 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::serde_hex::hash::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Digest32 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_hex::hash::deserialize(deserializer).map(Digest32)
+    }
+}
+
+/// Errors from parsing a `Digest32`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseHashError {
+    /// The input has the wrong number of characters.
+    InvalidLength {
+        /// The number of characters a 32-byte hash needs.
+        expected: usize,
+        /// The number of characters found.
+        found: usize,
+    },
+    /// The input contains a character outside the encoding's alphabet.
+    InvalidCharacter {
+        /// The offending character.
+        character: char,
+        /// Its byte offset in the input.
+        index: usize,
+    },
+    /// Base64 input has non-zero bits after the last byte.
+    NonCanonical,
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseHashError::InvalidLength { expected, found } => {
+                write!(f, "expected {} characters, found {}", expected, found)
+            }
+            ParseHashError::InvalidCharacter { character, index } => {
+                write!(f, "invalid character {:?} at position {}", character, index)
+            }
+            ParseHashError::NonCanonical => write!(f, "base64 input is not canonical"),
+        }
+    }
+}
+
+impl std::error::Error for ParseHashError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Digest32 {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i * 8 + 1) as u8;
+        }
+        Digest32::new(bytes)
+    }
+
+    // =========================================================================
+    // Hex Tests
+    // =========================================================================
+
+    #[test]
+    fn test_hex_round_trip() {
+        let digest = sample();
+        let hex = digest.to_hex();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.starts_with("010911"));
+        assert_eq!(hex, bytes_to_hex(digest.as_bytes()));
+        assert_eq!(Digest32::from_hex(&hex), Ok(digest));
+        assert_eq!(hex.parse::<Digest32>(), Ok(digest));
+    }
+
+    #[test]
+    fn test_hex_prefix_and_case() {
+        let digest = sample();
+        let upper = digest.to_hex().to_uppercase();
+        assert_eq!(Digest32::from_hex(&upper), Ok(digest));
+        assert_eq!(Digest32::from_hex(&format!("0x{}", upper)), Ok(digest));
+        assert_eq!(Digest32::from_hex(&format!("0X{}", digest)), Ok(digest));
+    }
+
+    #[test]
+    fn test_hex_errors() {
+        assert_eq!(
+            Digest32::from_hex("0xabcd"),
+            Err(ParseHashError::InvalidLength {
+                expected: 64,
+                found: 4
+            })
+        );
+        let mut hex = "0x".to_string() + &"0".repeat(64);
+        hex.replace_range(7..8, "g");
+        assert_eq!(
+            Digest32::from_hex(&hex),
+            Err(ParseHashError::InvalidCharacter {
+                character: 'g',
+                index: 7
+            })
+        );
+        // A multi-byte character must not be split
+        let wide = "é".to_string() + &"0".repeat(62);
+        assert_eq!(
+            Digest32::from_hex(&wide),
+            Err(ParseHashError::InvalidCharacter {
+                character: 'é',
+                index: 0
+            })
+        );
+    }
+
+    // =========================================================================
+    // Base64 Tests
+    // =========================================================================
+
+    #[test]
+    fn test_base64_known_values() {
+        assert_eq!(
+            Digest32::new([0u8; 32]).to_base64(),
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+        );
+        assert_eq!(
+            Digest32::new([0xff; 32]).to_base64(),
+            "//////////////////////////////////////////8="
+        );
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        let digest = sample();
+        let base64 = digest.to_base64();
+        assert_eq!(base64.len(), 44);
+        assert_eq!(Digest32::from_base64(&base64), Ok(digest));
+        assert_eq!(Digest32::from_base64(&base64[..43]), Ok(digest));
+    }
+
+    #[test]
+    fn test_base64_errors() {
+        assert_eq!(
+            Digest32::from_base64("AAAA"),
+            Err(ParseHashError::InvalidLength {
+                expected: 44,
+                found: 4
+            })
+        );
+        let mut bad = Digest32::default().to_base64();
+        bad.replace_range(3..4, "-");
+        assert_eq!(
+            Digest32::from_base64(&bad),
+            Err(ParseHashError::InvalidCharacter {
+                character: '-',
+                index: 3
+            })
+        );
+        // The last digit may only carry zero bits past the 32nd byte
+        let non_canonical = "A".repeat(42) + "B=";
+        assert_eq!(
+            Digest32::from_base64(&non_canonical),
+            Err(ParseHashError::NonCanonical)
+        );
+    }
+
+    // =========================================================================
+    // Formatting and Conversion Tests
+    // =========================================================================
+
+    #[test]
+    fn test_formatting() {
+        let digest = sample();
+        assert_eq!(format!("{:x}", digest), digest.to_hex());
+        assert_eq!(format!("{:#x}", digest), format!("0x{}", digest.to_hex()));
+        assert_eq!(format!("{:?}", digest), format!("Digest32({})", digest));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let bytes = [9u8; 32];
+        let digest = Digest32::from(bytes);
+        assert_eq!(<[u8; 32]>::from(digest), bytes);
+        assert_eq!(*digest, bytes);
+        assert_eq!(digest.as_ref(), &bytes[..]);
+        assert_eq!(digest, bytes);
+        assert_ne!(digest, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        assert_eq!(
+            ParseHashError::InvalidLength {
+                expected: 64,
+                found: 3
+            }
+            .to_string(),
+            "expected 64 characters, found 3"
+        );
+        assert_eq!(
+            ParseHashError::InvalidCharacter {
+                character: 'z',
+                index: 5
+            }
+            .to_string(),
+            "invalid character 'z' at position 5"
+        );
+        assert_eq!(
+            ParseHashError::NonCanonical.to_string(),
+            "base64 input is not canonical"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let digest = sample();
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!(json, format!("\"{}\"", digest));
+        assert_eq!(serde_json::from_str::<Digest32>(&json).unwrap(), digest);
+        let bytes = bincode::serialize(&digest).unwrap();
+        assert_eq!(bincode::deserialize::<Digest32>(&bytes).unwrap(), digest);
+    }
+}
@@ -0,0 +1,725 @@
+//! A durable, append-only `NodeStore` in a directory of files.
+//!
+//! The directory holds:
+//!
+//! ```text
+//! leaves.dat     leaf records: u32 LE length, data, 4-byte checksum
+//! level-<l>.dat  hashes of level l, 32 bytes per node at offset 32 * index
+//! checkpoint     version, varint size, root, 4-byte checksum
+//! ```
+//!
+//! Leaves are only ever appended. Node hashes are written in place, but
+//! appending to a tree only rewrites the last node of each level. The
+//! checkpoint is replaced atomically after every file has been synced, so
+//! everything it covers survives a crash.
+//!
+//! `FileStore::open` recovers from a crash between checkpoints: it drops a
+//! torn or garbled record at the end of `leaves.dat`, trims level files to
+//! the recovered size and rehashes every node that covers a leaf written
+//! after the checkpoint, including any upper levels that never reached
+//! the disk.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use sha2::{Digest, Sha256};
+
+use crate::hasher::Hasher;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::codec::{Reader, put_varint, read_varint};
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::snapshot::tree_height;
+
+const CHECKPOINT_VERSION: u8 = 1;
+
+/// Bytes around the data of a leaf record: the length and the checksum.
+const RECORD_OVERHEAD: usize = 8;
+
+/// Appended leaves are served from memory until this many bytes build up,
+/// then the leaf file is mapped again.
+const REMAP_THRESHOLD: usize = 4 << 20;
+
+/// A `NodeStore` that persists a tree to a directory.
+///
+/// Leaf data is read through a memory map, so `get_leaf` does not copy.
+/// Writes reach the files immediately but are only durable after
+/// `checkpoint`, which syncs the files and records the size and root.
+/// The store is append-only: `put_leaf` rejects overwriting a leaf.
+///
+/// Only Unix is supported.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::merkle::file_store::FileStore;
+/// use merkle_trees::{MerkleTree, Sha256Hasher, StoredMerkleTree};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let store = FileStore::open(dir.path(), &Sha256Hasher::new()).unwrap();
+/// let mut tree = StoredMerkleTree::with_store(Sha256Hasher::new(), store);
+/// tree.add_leaf(b"a").unwrap();
+/// tree.store_mut().checkpoint().unwrap();
+/// let root = tree.get_root();
+/// drop(tree);
+///
+/// let store = FileStore::open(dir.path(), &Sha256Hasher::new()).unwrap();
+/// let tree = StoredMerkleTree::with_store(Sha256Hasher::new(), store);
+/// assert_eq!(tree.get_root(), root);
+/// ```
+pub struct FileStore {
+    dir: PathBuf,
+    leaves: File,
+    /// Byte length of `leaves.dat`.
+    leaves_len: u64,
+    /// Offset and length of each leaf's data in `leaves.dat`.
+    offsets: Vec<(u64, u32)>,
+    /// A map of `leaves.dat` up to `mapped_len`.
+    map: Option<Mmap>,
+    mapped_len: u64,
+    /// The bytes of `leaves.dat` after `mapped_len`.
+    tail: Vec<u8>,
+    levels: Vec<LevelFile>,
+    checkpoint_size: u64,
+}
+
+struct LevelFile {
+    file: File,
+    /// Number of hashes in the file.
+    len: u64,
+}
+
+impl FileStore {
+    /// Open the store in `dir`, creating it if needed, and recover from
+    /// any crash since the last checkpoint.
+    ///
+    /// `hasher` must be the hasher of the tree; it is used to rehash the
+    /// nodes written after the checkpoint. Returns
+    /// `MerkleTreeError::CorruptedNode` if leaves covered by the checkpoint
+    /// are missing, `RootMismatch` if the recovered data does not match the
+    /// checkpointed root, and `Io` if a file cannot be read or written.
+    pub fn open<H: Hasher>(dir: impl AsRef<Path>, hasher: &H) -> Result<Self, MerkleTreeError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let (checkpoint_size, checkpoint_root) = read_checkpoint(&dir)?;
+
+        let leaves = open_rw(&dir.join("leaves.dat"))?;
+        let mut store = FileStore {
+            dir,
+            leaves,
+            leaves_len: 0,
+            offsets: Vec::new(),
+            map: None,
+            mapped_len: 0,
+            tail: Vec::new(),
+            levels: Vec::new(),
+            checkpoint_size,
+        };
+        store.recover_leaves()?;
+        let size = store.leaf_count();
+        if size < checkpoint_size {
+            return Err(MerkleTreeError::CorruptedNode {
+                level: 0,
+                index: size,
+            });
+        }
+        store.recover_levels(hasher)?;
+        if checkpoint_size > 0 && store.prefix_root(checkpoint_size, hasher)? != checkpoint_root {
+            return Err(MerkleTreeError::RootMismatch);
+        }
+        store.remap()?;
+        Ok(store)
+    }
+
+    /// Sync every file and record the current size and root as durable.
+    pub fn checkpoint(&mut self) -> Result<(), MerkleTreeError> {
+        self.leaves.sync_data()?;
+        for level in &self.levels {
+            level.file.sync_data()?;
+        }
+
+        let size = self.leaf_count();
+        let mut contents = vec![CHECKPOINT_VERSION];
+        put_varint(&mut contents, size);
+        if size > 0 {
+            let root = self
+                .get_hash(tree_height(size), 0)
+                .ok_or(MerkleTreeError::MissingHash {
+                    level: tree_height(size),
+                    index: 0,
+                })?;
+            contents.extend_from_slice(&root);
+        }
+        contents.extend_from_slice(&checksum(&contents));
+
+        // Replace the checkpoint atomically
+        let temp = self.dir.join("checkpoint.tmp");
+        let mut file = File::create(&temp)?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, self.dir.join("checkpoint"))?;
+        File::open(&self.dir)?.sync_all()?;
+
+        self.checkpoint_size = size;
+        self.remap()
+    }
+
+    /// Get the number of leaves covered by the last checkpoint.
+    pub fn checkpoint_size(&self) -> u64 {
+        self.checkpoint_size
+    }
+
+    /// Read the leaf records, truncating the file after the last valid one.
+    fn recover_leaves(&mut self) -> Result<(), MerkleTreeError> {
+        let file_len = self.leaves.metadata()?.len();
+        let mut offset = 0;
+        if file_len > 0 {
+            // SAFETY: the store owns the file and the map is dropped before
+            // the file is truncated.
+            let contents = unsafe { Mmap::map(&self.leaves)? };
+            while let Some(len) = record_len(&contents[offset..]) {
+                self.offsets.push((offset as u64 + 4, len as u32));
+                offset += len + RECORD_OVERHEAD;
+            }
+        }
+        if (offset as u64) < file_len {
+            self.leaves.set_len(offset as u64)?;
+        }
+        self.leaves_len = offset as u64;
+        Ok(())
+    }
+
+    /// Trim the level files and rehash the nodes that may be stale.
+    ///
+    /// A node at `(level, index)` is unchanged since the checkpoint when
+    /// all of its leaves are older than the checkpoint, that is when
+    /// `index < checkpoint_size >> level`. Every later node is recomputed.
+    fn recover_levels<H: Hasher>(&mut self, hasher: &H) -> Result<(), MerkleTreeError> {
+        let size = self.leaf_count();
+        let levels = if size == 0 { 0 } else { tree_height(size) + 1 };
+
+        for level in 0..levels {
+            let width = ((size - 1) >> level) + 1;
+            let file = self.level_file(level)?;
+            let kept = (file.file.metadata()?.len() / 32).min(width);
+            file.file.set_len(kept * 32)?;
+            file.len = kept;
+
+            let start = (self.checkpoint_size >> level).min(kept);
+            for index in start..width {
+                let hash = if level == 0 {
+                    let data = self.read_leaf(index)?;
+                    hasher.hash_leaf(&data)
+                } else {
+                    let left = self.stored_hash(level - 1, 2 * index)?;
+                    let right = match self.get_hash(level - 1, 2 * index + 1) {
+                        Some(right) => right,
+                        None => left,
+                    };
+                    hasher.hash_internal(&left, &right)
+                };
+                self.put_hash(level, index, hash)?;
+            }
+        }
+
+        // Levels above the root are left over from a larger, lost tree
+        let mut level = levels;
+        while self.dir.join(level_file_name(level)).exists() {
+            self.level_file(level)?;
+            level += 1;
+        }
+        for level in self.levels.iter_mut().skip(levels as usize) {
+            level.file.set_len(0)?;
+            level.len = 0;
+        }
+        Ok(())
+    }
+
+    /// Compute the root of the tree made of the first `size` leaves.
+    ///
+    /// The last node of each level of that tree is rebuilt from its left
+    /// sibling, whose leaves all precede `size` and are therefore intact.
+    fn prefix_root<H: Hasher>(&self, size: u64, hasher: &H) -> Result<[u8; 32], MerkleTreeError> {
+        let mut hash = self.stored_hash(0, size - 1)?;
+        for level in 0..tree_height(size) {
+            let position = (size - 1) >> level;
+            hash = if position & 1 == 1 {
+                let left = self.stored_hash(level, position - 1)?;
+                hasher.hash_internal(&left, &hash)
+            } else {
+                hasher.hash_internal(&hash, &hash)
+            };
+        }
+        Ok(hash)
+    }
+
+    fn stored_hash(&self, level: u32, index: u64) -> Result<[u8; 32], MerkleTreeError> {
+        self.get_hash(level, index)
+            .ok_or(MerkleTreeError::MissingHash { level, index })
+    }
+
+    /// Read a leaf straight from the file, for use before it is mapped.
+    fn read_leaf(&self, index: u64) -> Result<Vec<u8>, MerkleTreeError> {
+        let (offset, len) = self.offsets[index as usize];
+        let mut data = vec![0u8; len as usize];
+        self.leaves.read_exact_at(&mut data, offset)?;
+        Ok(data)
+    }
+
+    /// Map the whole leaf file and clear the in-memory tail.
+    fn remap(&mut self) -> Result<(), MerkleTreeError> {
+        self.map = if self.leaves_len == 0 {
+            None
+        } else {
+            // SAFETY: the store owns the file and only appends to it, so the
+            // mapped bytes never change while the map is alive.
+            Some(unsafe { Mmap::map(&self.leaves)? })
+        };
+        self.mapped_len = self.leaves_len;
+        self.tail.clear();
+        Ok(())
+    }
+
+    fn level_file(&mut self, level: u32) -> Result<&mut LevelFile, MerkleTreeError> {
+        while self.levels.len() <= level as usize {
+            let path = self.dir.join(level_file_name(self.levels.len() as u32));
+            let file = open_rw(&path)?;
+            let len = file.metadata()?.len() / 32;
+            self.levels.push(LevelFile { file, len });
+        }
+        Ok(&mut self.levels[level as usize])
+    }
+}
+
+impl NodeStore for FileStore {
+    fn get_hash(&self, level: u32, index: u64) -> Option<[u8; 32]> {
+        let level = self.levels.get(level as usize)?;
+        if index >= level.len {
+            return None;
+        }
+        let mut hash = [0u8; 32];
+        level.file.read_exact_at(&mut hash, index * 32).ok()?;
+        Some(hash)
+    }
+
+    fn put_hash(&mut self, level: u32, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        let file = self.level_file(level)?;
+        if index > file.len {
+            return Err(MerkleTreeError::InvalidIndex {
+                index,
+                tree_size: file.len,
+            });
+        }
+        file.file.write_all_at(&hash, index * 32)?;
+        file.len = file.len.max(index + 1);
+        Ok(())
+    }
+
+    fn get_leaf(&self, index: u64) -> Option<&[u8]> {
+        let &(offset, len) = self.offsets.get(usize::try_from(index).ok()?)?;
+        let (start, end) = (offset as usize, offset as usize + len as usize);
+        if offset < self.mapped_len {
+            Some(&self.map.as_ref()?[start..end])
+        } else {
+            let base = self.mapped_len as usize;
+            Some(&self.tail[start - base..end - base])
+        }
+    }
+
+    /// Append a leaf; overwriting an existing leaf is rejected with
+    /// `MerkleTreeError::InvalidIndex`.
+    fn put_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError> {
+        let count = self.leaf_count();
+        if index != count {
+            return Err(MerkleTreeError::InvalidIndex {
+                index,
+                tree_size: count,
+            });
+        }
+        let len = u32::try_from(data.len()).map_err(|_| MerkleTreeError::InvalidEncoding)?;
+
+        let mut record = Vec::with_capacity(data.len() + RECORD_OVERHEAD);
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(data);
+        record.extend_from_slice(&checksum(&record));
+        self.leaves.write_all_at(&record, self.leaves_len)?;
+
+        self.offsets.push((self.leaves_len + 4, len));
+        self.leaves_len += record.len() as u64;
+        self.tail.extend_from_slice(&record);
+        if self.tail.len() >= REMAP_THRESHOLD {
+            self.remap()?;
+        }
+        Ok(())
+    }
+
+    fn leaf_count(&self) -> u64 {
+        self.offsets.len() as u64
+    }
+}
+
+fn level_file_name(level: u32) -> String {
+    format!("level-{}.dat", level)
+}
+
+fn open_rw(path: &Path) -> Result<File, MerkleTreeError> {
+    Ok(OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?)
+}
+
+/// The first four bytes of the SHA-256 of `bytes`.
+fn checksum(bytes: &[u8]) -> [u8; 4] {
+    let digest = Sha256::digest(bytes);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Returns the data length of the complete, valid record at the start of
+/// `input`, or None if there is none.
+fn record_len(input: &[u8]) -> Option<usize> {
+    let len = u32::from_le_bytes(input.get(..4)?.try_into().ok()?) as usize;
+    let record = input.get(..len + RECORD_OVERHEAD)?;
+    let (body, check) = record.split_at(len + 4);
+    (len > 0 && checksum(body) == check).then_some(len)
+}
+
+/// Read the checkpointed size and root, or `(0, [0; 32])` if there is none.
+fn read_checkpoint(dir: &Path) -> Result<(u64, [u8; 32]), MerkleTreeError> {
+    let _ = fs::remove_file(dir.join("checkpoint.tmp"));
+    let contents = match fs::read(dir.join("checkpoint")) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((0, [0u8; 32])),
+        Err(err) => return Err(err.into()),
+    };
+
+    // The file is replaced atomically, so a bad checksum means corruption
+    let (body, check) = contents
+        .split_at_checked(contents.len().saturating_sub(4))
+        .ok_or(MerkleTreeError::TruncatedInput)?;
+    if check.len() != 4 || checksum(body) != check {
+        return Err(MerkleTreeError::InvalidEncoding);
+    }
+    let mut reader = Reader::new(body);
+    let version = reader.u8().ok_or(MerkleTreeError::TruncatedInput)?;
+    if version != CHECKPOINT_VERSION {
+        return Err(MerkleTreeError::UnsupportedVersion { version });
+    }
+    let size = read_varint(&mut reader)?;
+    let root = match size {
+        0 => [0u8; 32],
+        _ => reader.hash().ok_or(MerkleTreeError::TruncatedInput)?,
+    };
+    if !reader.is_empty() {
+        return Err(MerkleTreeError::TrailingBytes {
+            count: reader.rest().len(),
+        });
+    }
+    Ok((size, root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Sha256Hasher, SimpleHasher};
+    use crate::merkle::tests::{leaf, tree_of};
+    use crate::merkle::{MerkleTree, StoredMerkleTree};
+
+    type FileTree = StoredMerkleTree<Sha256Hasher, FileStore>;
+
+    fn open(dir: &Path) -> Result<FileTree, MerkleTreeError> {
+        let store = FileStore::open(dir, &Sha256Hasher::new())?;
+        Ok(StoredMerkleTree::with_store(Sha256Hasher::new(), store))
+    }
+
+    fn append(tree: &mut FileTree, range: std::ops::Range<u64>) {
+        for i in range {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+    }
+
+    fn expected_root(count: u64) -> Option<[u8; 32]> {
+        tree_of(count).get_root_bytes()
+    }
+
+    fn truncate(path: &Path, len: u64) {
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_len(len)
+            .unwrap();
+    }
+
+    fn file_len(path: &Path) -> u64 {
+        fs::metadata(path).unwrap().len()
+    }
+
+    // =========================================================================
+    // Basic Store Tests
+    // =========================================================================
+
+    #[test]
+    fn test_matches_simple_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_root(), None);
+        append(&mut tree, 0..21);
+        assert_eq!(tree.get_root_bytes(), expected_root(21));
+        assert_eq!(tree.get_data(20), Some(leaf(20).as_slice()));
+        assert!(tree.get_data(21).is_none());
+
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove(9).unwrap();
+        assert!(tree.verify(&proof, &leaf(9), &root));
+    }
+
+    #[test]
+    fn test_reopen_after_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..10);
+        tree.store_mut().checkpoint().unwrap();
+        assert_eq!(tree.store().checkpoint_size(), 10);
+        // Leaves stay readable after the file is mapped again
+        assert_eq!(tree.get_data(3), Some(leaf(3).as_slice()));
+        drop(tree);
+
+        let mut tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 10);
+        assert_eq!(tree.get_root_bytes(), expected_root(10));
+        append(&mut tree, 10..12);
+        assert_eq!(tree.get_root_bytes(), expected_root(12));
+        assert_eq!(tree.get_data(0), Some(leaf(0).as_slice()));
+        assert_eq!(tree.get_data(11), Some(leaf(11).as_slice()));
+    }
+
+    #[test]
+    fn test_writes_after_checkpoint_survive_clean_close() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..5);
+        tree.store_mut().checkpoint().unwrap();
+        append(&mut tree, 5..9);
+        drop(tree);
+
+        let tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 9);
+        assert_eq!(tree.store().checkpoint_size(), 5);
+        assert_eq!(tree.get_root_bytes(), expected_root(9));
+    }
+
+    #[test]
+    fn test_rejects_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..2);
+        assert_eq!(
+            tree.update_leaf(0, b"x"),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 0,
+                tree_size: 2
+            })
+        );
+    }
+
+    // =========================================================================
+    // Crash Recovery Tests
+    // =========================================================================
+
+    #[test]
+    fn test_torn_leaf_record_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..6);
+        tree.store_mut().checkpoint().unwrap();
+        append(&mut tree, 6..9);
+        drop(tree);
+
+        // Cut the last record in half
+        let leaves = dir.path().join("leaves.dat");
+        let record = (leaf(8).len() + RECORD_OVERHEAD) as u64;
+        truncate(&leaves, file_len(&leaves) - record / 2);
+
+        let mut tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 8);
+        assert_eq!(tree.get_root_bytes(), expected_root(8));
+        append(&mut tree, 8..10);
+        assert_eq!(tree.get_root_bytes(), expected_root(10));
+    }
+
+    #[test]
+    fn test_garbage_after_last_record_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..3);
+        drop(tree);
+
+        let leaves = dir.path().join("leaves.dat");
+        let len = file_len(&leaves);
+        let garbage = [5, 0, 0, 0, 1, 2, 3, 4, 5, 0, 0, 0, 0];
+        OpenOptions::new()
+            .write(true)
+            .open(&leaves)
+            .unwrap()
+            .write_all_at(&garbage, len)
+            .unwrap();
+
+        let tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 3);
+        assert_eq!(file_len(&leaves), len);
+        assert_eq!(tree.get_root_bytes(), expected_root(3));
+    }
+
+    #[test]
+    fn test_missing_upper_levels_are_rebuilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..8);
+        tree.store_mut().checkpoint().unwrap();
+        append(&mut tree, 8..13);
+        drop(tree);
+
+        // The level writes after the checkpoint never reached the disk
+        for level in 1..=4 {
+            let path = dir.path().join(format!("level-{}.dat", level));
+            let keep = (8u64 >> level) * 32;
+            truncate(&path, keep.min(file_len(&path)));
+        }
+        fs::remove_file(dir.path().join("level-4.dat")).unwrap();
+
+        let tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 13);
+        assert_eq!(file_len(&dir.path().join("level-4.dat")), 32);
+        assert_eq!(tree.get_root_bytes(), expected_root(13));
+        for i in [0, 7, 12] {
+            let proof = tree.prove(i).unwrap();
+            assert!(tree.verify(&proof, &leaf(i), &tree.get_root_bytes().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_stale_level_hash_is_rebuilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..4);
+        tree.store_mut().checkpoint().unwrap();
+        append(&mut tree, 4..6);
+        drop(tree);
+
+        // Level 1 node 2 covers leaves 4 and 5; pretend only leaf 4 was seen
+        let path = dir.path().join("level-1.dat");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .write_all_at(&[0xee; 32], 64)
+            .unwrap();
+
+        let tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_root_bytes(), expected_root(6));
+    }
+
+    #[test]
+    fn test_deleted_level_files_are_rebuilt_without_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..5);
+        drop(tree);
+        for level in 0..=3 {
+            fs::remove_file(dir.path().join(format!("level-{}.dat", level))).unwrap();
+        }
+
+        let tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_root_bytes(), expected_root(5));
+    }
+
+    #[test]
+    fn test_levels_of_lost_leaves_are_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..5);
+        drop(tree);
+
+        // Losing leaves 2-4 leaves level 3 with nothing to cover
+        let leaves = dir.path().join("leaves.dat");
+        truncate(&leaves, 2 * (leaf(0).len() + RECORD_OVERHEAD) as u64);
+        let mut tree = open(dir.path()).unwrap();
+        assert_eq!(tree.get_size(), 2);
+        assert_eq!(file_len(&dir.path().join("level-3.dat")), 0);
+        append(&mut tree, 2..9);
+        assert_eq!(tree.get_root_bytes(), expected_root(9));
+    }
+
+    #[test]
+    fn test_lost_checkpointed_leaves_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..4);
+        tree.store_mut().checkpoint().unwrap();
+        drop(tree);
+
+        let leaves = dir.path().join("leaves.dat");
+        truncate(&leaves, file_len(&leaves) - 1);
+        assert_eq!(
+            open(dir.path()).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 0, index: 3 })
+        );
+    }
+
+    #[test]
+    fn test_corrupted_checkpointed_hash_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..4);
+        tree.store_mut().checkpoint().unwrap();
+        drop(tree);
+
+        let path = dir.path().join("level-1.dat");
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .write_all_at(&[0xee; 32], 0)
+            .unwrap();
+        assert_eq!(open(dir.path()).err(), Some(MerkleTreeError::RootMismatch));
+    }
+
+    #[test]
+    fn test_wrong_hasher_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..3);
+        tree.store_mut().checkpoint().unwrap();
+        drop(tree);
+
+        // Leaf hashes are trusted up to the checkpoint, but the upper nodes
+        // of the prefix are recomputed and no longer match
+        assert_eq!(
+            FileStore::open(dir.path(), &SimpleHasher::new()).err(),
+            Some(MerkleTreeError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_corrupted_checkpoint_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = open(dir.path()).unwrap();
+        append(&mut tree, 0..2);
+        tree.store_mut().checkpoint().unwrap();
+        drop(tree);
+
+        let path = dir.path().join("checkpoint");
+        let mut contents = fs::read(&path).unwrap();
+        contents[2] ^= 0x01;
+        fs::write(&path, &contents).unwrap();
+        assert_eq!(
+            open(dir.path()).err(),
+            Some(MerkleTreeError::InvalidEncoding)
+        );
+    }
+}
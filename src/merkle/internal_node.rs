@@ -27,6 +27,16 @@ impl InternalNode {
         }
     }
 
+    /// Create an internal node whose hash was already computed, e.g. as
+    /// part of a batch by `Hasher::hash_internal_many`.
+    pub(crate) fn with_hash(left: Arc<Node>, right: Arc<Node>, hash_value: [u8; 32]) -> Self {
+        InternalNode {
+            left,
+            right,
+            hash_value,
+        }
+    }
+
     /// Get the left child.
     pub fn left(&self) -> &Node {
         &self.left
@@ -37,13 +47,22 @@ impl InternalNode {
         &self.right
     }
 
-    /// Compute the hash for this internal node (0x01 domain separator).
+    /// Get a shared handle to the left child.
+    ///
+    /// Cloning the returned `Arc` lets another tree reuse the subtree
+    /// without copying it.
+    pub fn left_arc(&self) -> &Arc<Node> {
+        &self.left
+    }
+
+    /// Get a shared handle to the right child.
+    pub fn right_arc(&self) -> &Arc<Node> {
+        &self.right
+    }
+
+    /// Compute the hash for this internal node via `Hasher::hash_internal`.
     fn compute_hash<H: Hasher>(left: &Node, right: &Node, hasher: &H) -> [u8; 32] {
-        let mut to_hash = Vec::with_capacity(65); // 1 + 32 + 32
-        to_hash.push(0x01);
-        to_hash.extend_from_slice(left.hash());
-        to_hash.extend_from_slice(right.hash());
-        hasher.hash_bytes(&to_hash)
+        hasher.hash_internal(left.hash(), right.hash())
     }
 }
 
@@ -80,6 +99,17 @@ mod tests {
         assert_eq!(internal.right().hash(), right_hash.as_slice());
     }
 
+    #[test]
+    fn test_internal_arc_accessors_share_children() {
+        let hasher = SimpleHasher::new();
+        let left = Arc::new(Node::leaf(b"left".to_vec(), &hasher));
+        let right = Arc::new(Node::leaf(b"right".to_vec(), &hasher));
+        let internal = InternalNode::new(Arc::clone(&left), Arc::clone(&right), &hasher);
+
+        assert!(Arc::ptr_eq(internal.left_arc(), &left));
+        assert!(Arc::ptr_eq(internal.right_arc(), &right));
+    }
+
     #[test]
     fn test_order_matters() {
         let hasher = Sha256Hasher::new();
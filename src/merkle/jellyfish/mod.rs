@@ -0,0 +1,131 @@
+//! A Jellyfish Merkle tree: a versioned sparse Merkle tree for state storage.
+//!
+//! Keys are 256-bit hashes, and the tree is logically a 256-level binary
+//! sparse Merkle tree in which empty subtrees hash to a placeholder and a
+//! subtree holding a single leaf collapses to that leaf. Physically, nodes
+//! are 16-ary (one nibble of the key per level) and are written to a node
+//! store under a `(version, nibble path)` key, so every version remains
+//! readable until it is pruned.
+//!
+//! # Hashing
+//!
+//! * Leaves: `Hasher::hash_leaf(key || H(value))`, which is
+//!   H(0x00 || key || H(value)) under the standard scheme
+//! * Internal (binary view): `Hasher::hash_internal(left, right)`, which is
+//!   H(0x01 || left || right) under the standard scheme
+//! * Empty subtree: 32 zero bytes
+//!
+//! # Versions and pruning
+//!
+//! Each call to `JellyfishMerkleTree::put_value_set` creates a new version
+//! and returns a `TreeUpdateBatch` holding the new nodes and the nodes that
+//! became stale. A node stale since version `v` is only needed to read
+//! versions older than `v`, so `MemoryTreeStore::prune` can delete it once
+//! those versions are no longer served.
+
+pub mod node;
+pub mod proof;
+pub mod store;
+pub mod tree;
+
+pub use node::{Child, JmtInternalNode, JmtLeafNode, JmtNode, NodeKey};
+pub use proof::SparseMerkleProof;
+pub use store::{MemoryTreeStore, StaleNodeIndex, TreeReader, TreeUpdateBatch, TreeWriter};
+pub use tree::JellyfishMerkleTree;
+
+use crate::hasher::Hasher;
+
+/// A 256-bit key, usually the hash of an application key.
+pub type KeyHash = [u8; 32];
+
+/// The hash of an empty subtree.
+pub const PLACEHOLDER_HASH: [u8; 32] = [0u8; 32];
+
+/// Compute a leaf hash of `key || value_hash` via `Hasher::hash_leaf`.
+///
+/// Going through the hasher's leaf hook keeps a `HashScheme` or a
+/// field-native hasher such as Poseidon in charge of leaf domain
+/// separation, as it is for the other trees.
+pub(crate) fn hash_leaf<H: Hasher>(key: &KeyHash, value_hash: &[u8; 32], hasher: &H) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(key);
+    data[32..].copy_from_slice(value_hash);
+    hasher.hash_leaf(&data)
+}
+
+/// Compute a binary internal hash via `Hasher::hash_internal`.
+pub(crate) fn hash_internal<H: Hasher>(left: &[u8; 32], right: &[u8; 32], hasher: &H) -> [u8; 32] {
+    hasher.hash_internal(left, right)
+}
+
+/// Get the nibble of the key at the given depth (0 is the high nibble of byte 0).
+pub(crate) fn nibble(key: &KeyHash, depth: usize) -> u8 {
+    let byte = key[depth / 2];
+    if depth.is_multiple_of(2) {
+        byte >> 4
+    } else {
+        byte & 0x0f
+    }
+}
+
+/// Get the bit of the key at the given depth (0 is the most significant bit).
+pub(crate) fn bit(key: &KeyHash, depth: usize) -> bool {
+    (key[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{HashScheme, SchemedHasher, Sha256Hasher};
+
+    #[test]
+    fn test_nibble() {
+        let mut key = [0u8; 32];
+        key[0] = 0xab;
+        key[31] = 0xcd;
+        assert_eq!(nibble(&key, 0), 0xa);
+        assert_eq!(nibble(&key, 1), 0xb);
+        assert_eq!(nibble(&key, 62), 0xc);
+        assert_eq!(nibble(&key, 63), 0xd);
+    }
+
+    #[test]
+    fn test_bit() {
+        let mut key = [0u8; 32];
+        key[0] = 0b1000_0001;
+        assert!(bit(&key, 0));
+        assert!(!bit(&key, 1));
+        assert!(bit(&key, 7));
+        assert!(!bit(&key, 8));
+    }
+
+    #[test]
+    fn test_leaf_and_internal_domain_separation() {
+        let hasher = Sha256Hasher::new();
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+        assert_ne!(hash_leaf(&a, &b, &hasher), hash_internal(&a, &b, &hasher));
+    }
+
+    #[test]
+    fn test_leaf_hash_standard_encoding() {
+        let hasher = Sha256Hasher::new();
+        let key = [1u8; 32];
+        let value_hash = [2u8; 32];
+        assert_eq!(
+            hash_leaf(&key, &value_hash, &hasher),
+            hasher.hash_parts(&[&[0x00], &key, &value_hash])
+        );
+    }
+
+    #[test]
+    fn test_leaf_hash_follows_scheme() {
+        let schemed = SchemedHasher::new(Sha256Hasher::new(), HashScheme::new(b"L", b"N"));
+        let key = [1u8; 32];
+        let value_hash = [2u8; 32];
+        assert_eq!(
+            hash_leaf(&key, &value_hash, &schemed),
+            schemed.inner().hash_parts(&[b"L", &key, &value_hash])
+        );
+    }
+}
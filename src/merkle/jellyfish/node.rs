@@ -0,0 +1,269 @@
+use crate::hasher::Hasher;
+use crate::merkle::jellyfish::{KeyHash, PLACEHOLDER_HASH, hash_internal};
+
+/// The storage key of a node: the version that created it and its path.
+///
+/// The nibble path holds one nibble (0..16) per level, from the root down.
+/// The root of every version lives at `(version, [])`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeKey {
+    version: u64,
+    nibble_path: Vec<u8>,
+}
+
+impl NodeKey {
+    /// Create a node key from a version and a nibble path.
+    pub fn new(version: u64, nibble_path: Vec<u8>) -> Self {
+        NodeKey {
+            version,
+            nibble_path,
+        }
+    }
+
+    /// Create the key of the root node for the given version.
+    pub fn root(version: u64) -> Self {
+        NodeKey::new(version, Vec::new())
+    }
+
+    /// Returns the version that created this node.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the nibbles leading from the root to this node.
+    pub fn nibble_path(&self) -> &[u8] {
+        &self.nibble_path
+    }
+}
+
+/// A reference from an internal node to one of its children.
+///
+/// The child's hash is cached so that parents can be hashed without
+/// loading their children from the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Child {
+    version: u64,
+    hash: [u8; 32],
+    is_leaf: bool,
+}
+
+impl Child {
+    pub(crate) fn new(version: u64, hash: [u8; 32], is_leaf: bool) -> Self {
+        Child {
+            version,
+            hash,
+            is_leaf,
+        }
+    }
+
+    /// Returns the version that created the child node.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the hash of the child node.
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+
+    /// Returns true if the child is a leaf.
+    pub fn is_leaf(&self) -> bool {
+        self.is_leaf
+    }
+}
+
+/// A 16-ary internal node.
+///
+/// Its hash is the root of a 4-level binary tree over the 16 child slots,
+/// where empty slots hash to the placeholder and a subtree holding a single
+/// leaf collapses to that leaf's hash. This keeps proofs binary and
+/// compatible with a 256-level sparse Merkle tree.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JmtInternalNode {
+    children: Box<[Option<Child>; 16]>,
+}
+
+impl JmtInternalNode {
+    /// Returns the child in the given slot.
+    pub fn child(&self, nibble: u8) -> Option<&Child> {
+        self.children[nibble as usize].as_ref()
+    }
+
+    pub(crate) fn set_child(&mut self, nibble: u8, child: Option<Child>) {
+        self.children[nibble as usize] = child;
+    }
+
+    /// Iterate over the occupied slots and their children.
+    pub fn children(&self) -> impl Iterator<Item = (u8, &Child)> {
+        self.children
+            .iter()
+            .enumerate()
+            .filter_map(|(nibble, child)| child.as_ref().map(|c| (nibble as u8, c)))
+    }
+
+    /// Compute the hash of this node.
+    pub fn hash<H: Hasher>(&self, hasher: &H) -> [u8; 32] {
+        self.range_hash(0, 16, hasher)
+    }
+
+    /// Compute the hash of the binary subtree over slots `lo..hi`.
+    pub(crate) fn range_hash<H: Hasher>(&self, lo: u8, hi: u8, hasher: &H) -> [u8; 32] {
+        match self.single_child(lo, hi) {
+            RangeContent::Empty => PLACEHOLDER_HASH,
+            RangeContent::Leaf(_, child) => child.hash,
+            RangeContent::Many if hi - lo == 1 => self.children[lo as usize]
+                .map(|child| child.hash)
+                .unwrap_or(PLACEHOLDER_HASH),
+            RangeContent::Many => {
+                let mid = lo + (hi - lo) / 2;
+                hash_internal(
+                    &self.range_hash(lo, mid, hasher),
+                    &self.range_hash(mid, hi, hasher),
+                    hasher,
+                )
+            }
+        }
+    }
+
+    /// Describe what the slots `lo..hi` contain.
+    pub(crate) fn single_child(&self, lo: u8, hi: u8) -> RangeContent {
+        let mut occupied = (lo..hi).filter_map(|n| self.children[n as usize].map(|c| (n, c)));
+        match (occupied.next(), occupied.next()) {
+            (None, _) => RangeContent::Empty,
+            (Some((nibble, child)), None) if child.is_leaf => RangeContent::Leaf(nibble, child),
+            _ => RangeContent::Many,
+        }
+    }
+}
+
+/// The content of a range of child slots, used to collapse single leaves.
+pub(crate) enum RangeContent {
+    /// No child in the range.
+    Empty,
+    /// Exactly one child, and it is a leaf.
+    Leaf(u8, Child),
+    /// Several children, or a single internal child.
+    Many,
+}
+
+/// A leaf holding a key and its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JmtLeafNode {
+    key: KeyHash,
+    value: Vec<u8>,
+}
+
+impl JmtLeafNode {
+    /// Create a leaf for the given key and value.
+    pub fn new(key: KeyHash, value: Vec<u8>) -> Self {
+        JmtLeafNode { key, value }
+    }
+
+    /// Returns the 256-bit key of this leaf.
+    pub fn key(&self) -> &KeyHash {
+        &self.key
+    }
+
+    /// Returns the value stored in this leaf.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Compute the hash of this leaf.
+    pub fn hash<H: Hasher>(&self, hasher: &H) -> [u8; 32] {
+        crate::merkle::jellyfish::hash_leaf(&self.key, &hasher.hash_bytes(&self.value), hasher)
+    }
+}
+
+/// A node of a Jellyfish Merkle tree as kept in the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JmtNode {
+    /// The root of an empty tree.
+    Null,
+    Internal(JmtInternalNode),
+    Leaf(JmtLeafNode),
+}
+
+impl JmtNode {
+    /// Compute the hash of this node.
+    pub fn hash<H: Hasher>(&self, hasher: &H) -> [u8; 32] {
+        match self {
+            JmtNode::Null => PLACEHOLDER_HASH,
+            JmtNode::Internal(internal) => internal.hash(hasher),
+            JmtNode::Leaf(leaf) => leaf.hash(hasher),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+
+    fn leaf_child(byte: u8) -> Child {
+        Child::new(0, [byte; 32], true)
+    }
+
+    #[test]
+    fn test_node_key_ordering() {
+        let a = NodeKey::new(1, vec![0x2]);
+        let b = NodeKey::new(2, vec![]);
+        assert!(a < b);
+        assert_eq!(NodeKey::root(3).nibble_path(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn test_empty_internal_hashes_to_placeholder() {
+        let node = JmtInternalNode::default();
+        assert_eq!(node.hash(&Sha256Hasher::new()), PLACEHOLDER_HASH);
+    }
+
+    #[test]
+    fn test_single_leaf_collapses() {
+        let mut node = JmtInternalNode::default();
+        node.set_child(5, Some(leaf_child(7)));
+        assert_eq!(node.hash(&Sha256Hasher::new()), [7u8; 32]);
+    }
+
+    #[test]
+    fn test_single_internal_child_does_not_collapse() {
+        let mut node = JmtInternalNode::default();
+        node.set_child(0, Some(Child::new(0, [7u8; 32], false)));
+        assert_ne!(node.hash(&Sha256Hasher::new()), [7u8; 32]);
+    }
+
+    #[test]
+    fn test_two_children_hash_as_binary_tree() {
+        let hasher = Sha256Hasher::new();
+        let mut node = JmtInternalNode::default();
+        node.set_child(0, Some(leaf_child(1)));
+        node.set_child(15, Some(leaf_child(2)));
+
+        // Slots 0 and 15 are split at the first binary level
+        let expected = hash_internal(&[1u8; 32], &[2u8; 32], &hasher);
+        assert_eq!(node.hash(&hasher), expected);
+    }
+
+    #[test]
+    fn test_children_iterator() {
+        let mut node = JmtInternalNode::default();
+        node.set_child(3, Some(leaf_child(1)));
+        node.set_child(9, Some(leaf_child(2)));
+        let slots: Vec<u8> = node.children().map(|(n, _)| n).collect();
+        assert_eq!(slots, vec![3, 9]);
+    }
+
+    #[test]
+    fn test_leaf_hash_depends_on_key_and_value() {
+        let hasher = Sha256Hasher::new();
+        let leaf = JmtLeafNode::new([1u8; 32], b"v".to_vec());
+        assert_ne!(
+            leaf.hash(&hasher),
+            JmtLeafNode::new([2u8; 32], b"v".to_vec()).hash(&hasher)
+        );
+        assert_ne!(
+            leaf.hash(&hasher),
+            JmtLeafNode::new([1u8; 32], b"w".to_vec()).hash(&hasher)
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::hasher::Hasher;
+use crate::merkle::jellyfish::{KeyHash, PLACEHOLDER_HASH, bit, hash_internal, hash_leaf};
+
+/// A proof of inclusion or exclusion of a key in a Jellyfish Merkle tree.
+///
+/// The siblings are the binary sibling hashes along the key's path, ordered
+/// from the root toward the leaf. The proof ends at the first subtree on the
+/// path that is either empty or holds a single leaf:
+///
+/// - for an inclusion proof, that leaf holds the key;
+/// - for an exclusion proof, the subtree is empty (`leaf` is `None`) or holds
+///   a different key that shares the path so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMerkleProof {
+    /// The key and value hash of the leaf the path ends at, if any.
+    leaf: Option<(KeyHash, [u8; 32])>,
+    /// Sibling hashes ordered from the root toward the leaf.
+    siblings: Vec<[u8; 32]>,
+}
+
+impl SparseMerkleProof {
+    /// Create a proof from the leaf the path ends at and the sibling hashes.
+    pub fn new(leaf: Option<(KeyHash, [u8; 32])>, siblings: Vec<[u8; 32]>) -> Self {
+        SparseMerkleProof { leaf, siblings }
+    }
+
+    /// Returns the key and value hash of the leaf the path ends at, if any.
+    pub fn leaf(&self) -> Option<&(KeyHash, [u8; 32])> {
+        self.leaf.as_ref()
+    }
+
+    /// Returns the sibling hashes ordered from the root toward the leaf.
+    pub fn siblings(&self) -> &[[u8; 32]] {
+        &self.siblings
+    }
+
+    /// Verify the proof against the expected root.
+    ///
+    /// With `Some(value)`, checks that the key maps to that value. With
+    /// `None`, checks that the key is absent from the tree.
+    pub fn verify<H: Hasher>(
+        &self,
+        expected_root: &[u8; 32],
+        key: &KeyHash,
+        value: Option<&[u8]>,
+        hasher: &H,
+    ) -> bool {
+        if self.siblings.len() > 256 {
+            return false;
+        }
+
+        match (value, &self.leaf) {
+            (Some(value), Some((leaf_key, value_hash))) => {
+                if leaf_key != key || *value_hash != hasher.hash_bytes(value) {
+                    return false;
+                }
+            }
+            (Some(_), None) => return false,
+            (None, Some((leaf_key, _))) => {
+                // The other leaf must occupy the subtree the key would fall into
+                if leaf_key == key
+                    || (0..self.siblings.len()).any(|depth| bit(leaf_key, depth) != bit(key, depth))
+                {
+                    return false;
+                }
+            }
+            (None, None) => {}
+        }
+
+        let mut current_hash = match &self.leaf {
+            Some((leaf_key, value_hash)) => hash_leaf(leaf_key, value_hash, hasher),
+            None => PLACEHOLDER_HASH,
+        };
+
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            current_hash = if bit(key, depth) {
+                hash_internal(sibling, &current_hash, hasher)
+            } else {
+                hash_internal(&current_hash, sibling, hasher)
+            };
+        }
+
+        current_hash == *expected_root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+
+    fn key_with_first_byte(byte: u8) -> KeyHash {
+        let mut key = [0u8; 32];
+        key[0] = byte;
+        key
+    }
+
+    #[test]
+    fn test_single_leaf_inclusion() {
+        let hasher = Sha256Hasher::new();
+        let key = key_with_first_byte(0x80);
+        let value_hash = hasher.hash_bytes(b"value");
+        let root = hash_leaf(&key, &value_hash, &hasher);
+
+        let proof = SparseMerkleProof::new(Some((key, value_hash)), vec![]);
+        assert!(proof.verify(&root, &key, Some(b"value"), &hasher));
+        assert!(!proof.verify(&root, &key, Some(b"other"), &hasher));
+        assert!(!proof.verify(&root, &key, None, &hasher));
+    }
+
+    #[test]
+    fn test_exclusion_by_other_leaf() {
+        let hasher = Sha256Hasher::new();
+        let present = key_with_first_byte(0x80);
+        let value_hash = hasher.hash_bytes(b"value");
+        let root = hash_leaf(&present, &value_hash, &hasher);
+
+        let proof = SparseMerkleProof::new(Some((present, value_hash)), vec![]);
+        assert!(proof.verify(&root, &key_with_first_byte(0x01), None, &hasher));
+    }
+
+    #[test]
+    fn test_exclusion_rejects_leaf_off_path() {
+        let hasher = Sha256Hasher::new();
+        let left = key_with_first_byte(0x00);
+        let right = key_with_first_byte(0x80);
+        let left_hash = hash_leaf(&left, &hasher.hash_bytes(b"l"), &hasher);
+        let right_value_hash = hasher.hash_bytes(b"r");
+        let right_hash = hash_leaf(&right, &right_value_hash, &hasher);
+        let root = hash_internal(&left_hash, &right_hash, &hasher);
+
+        // A key on the left cannot be excluded using the right leaf
+        let proof = SparseMerkleProof::new(Some((right, right_value_hash)), vec![left_hash]);
+        assert!(!proof.verify(&root, &key_with_first_byte(0x01), None, &hasher));
+    }
+
+    #[test]
+    fn test_empty_tree_exclusion() {
+        let hasher = Sha256Hasher::new();
+        let proof = SparseMerkleProof::new(None, vec![]);
+        assert!(proof.verify(&PLACEHOLDER_HASH, &[7u8; 32], None, &hasher));
+        assert!(!proof.verify(&PLACEHOLDER_HASH, &[7u8; 32], Some(b"v"), &hasher));
+    }
+
+    #[test]
+    fn test_rejects_too_many_siblings() {
+        let hasher = Sha256Hasher::new();
+        let proof = SparseMerkleProof::new(None, vec![[0u8; 32]; 257]);
+        assert!(!proof.verify(&PLACEHOLDER_HASH, &[0u8; 32], None, &hasher));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::merkle::jellyfish::node::{JmtNode, NodeKey};
+
+/// Read access to the nodes of a Jellyfish Merkle tree.
+pub trait TreeReader {
+    /// Get the node stored under the given key, if any.
+    fn get_node(&self, node_key: &NodeKey) -> Option<JmtNode>;
+}
+
+/// Write access to the nodes of a Jellyfish Merkle tree.
+pub trait TreeWriter {
+    /// Persist the new nodes and stale-node records produced by an update.
+    fn write_tree_update_batch(&mut self, batch: TreeUpdateBatch);
+}
+
+/// Records that a node is no longer part of the tree from a given version.
+///
+/// Versions older than `stale_since_version` may still read the node, so it
+/// can only be deleted once those versions are pruned.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StaleNodeIndex {
+    /// The first version in which the node is no longer reachable.
+    pub stale_since_version: u64,
+    /// The key of the stale node.
+    pub node_key: NodeKey,
+}
+
+/// The changes produced by a single `put_value_set` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeUpdateBatch {
+    /// Nodes created by the update, keyed by their storage key.
+    pub node_batch: BTreeMap<NodeKey, JmtNode>,
+    /// Nodes made stale by the update.
+    pub stale_node_index_batch: BTreeSet<StaleNodeIndex>,
+}
+
+/// An in-memory node store, intended for tests and small trees.
+#[derive(Debug, Default)]
+pub struct MemoryTreeStore {
+    nodes: HashMap<NodeKey, JmtNode>,
+    stale_nodes: BTreeSet<StaleNodeIndex>,
+}
+
+impl MemoryTreeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of nodes currently stored.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Iterate over the stale-node records in order of staleness.
+    pub fn stale_nodes(&self) -> impl Iterator<Item = &StaleNodeIndex> {
+        self.stale_nodes.iter()
+    }
+
+    /// Delete every node that became stale at or before `version`.
+    ///
+    /// After pruning, versions older than `version` can no longer be read;
+    /// `version` itself and every later version remain intact. Returns the
+    /// number of nodes deleted.
+    pub fn prune(&mut self, version: u64) -> usize {
+        // The root key of version 0 sorts before every other key
+        let retained = self.stale_nodes.split_off(&StaleNodeIndex {
+            stale_since_version: version.saturating_add(1),
+            node_key: NodeKey::root(0),
+        });
+        let expired = std::mem::replace(&mut self.stale_nodes, retained);
+
+        expired
+            .into_iter()
+            .filter(|index| self.nodes.remove(&index.node_key).is_some())
+            .count()
+    }
+}
+
+impl TreeReader for MemoryTreeStore {
+    fn get_node(&self, node_key: &NodeKey) -> Option<JmtNode> {
+        self.nodes.get(node_key).cloned()
+    }
+}
+
+impl TreeWriter for MemoryTreeStore {
+    fn write_tree_update_batch(&mut self, batch: TreeUpdateBatch) {
+        self.nodes.extend(batch.node_batch);
+        self.stale_nodes.extend(batch.stale_node_index_batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(nodes: &[NodeKey], stale: &[(u64, NodeKey)]) -> TreeUpdateBatch {
+        TreeUpdateBatch {
+            node_batch: nodes.iter().map(|k| (k.clone(), JmtNode::Null)).collect(),
+            stale_node_index_batch: stale
+                .iter()
+                .map(|(version, key)| StaleNodeIndex {
+                    stale_since_version: *version,
+                    node_key: key.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let mut store = MemoryTreeStore::new();
+        store.write_tree_update_batch(batch(&[NodeKey::root(0)], &[]));
+
+        assert_eq!(store.get_node(&NodeKey::root(0)), Some(JmtNode::Null));
+        assert_eq!(store.get_node(&NodeKey::root(1)), None);
+        assert_eq!(store.num_nodes(), 1);
+    }
+
+    #[test]
+    fn test_prune_only_removes_stale_up_to_version() {
+        let mut store = MemoryTreeStore::new();
+        store.write_tree_update_batch(batch(&[NodeKey::root(0), NodeKey::root(1)], &[]));
+        store.write_tree_update_batch(batch(
+            &[NodeKey::root(2)],
+            &[(1, NodeKey::root(0)), (2, NodeKey::root(1))],
+        ));
+
+        assert_eq!(store.prune(1), 1);
+        assert!(store.get_node(&NodeKey::root(0)).is_none());
+        assert!(store.get_node(&NodeKey::root(1)).is_some());
+        assert_eq!(store.stale_nodes().count(), 1);
+
+        assert_eq!(store.prune(2), 1);
+        assert_eq!(store.num_nodes(), 1);
+    }
+}
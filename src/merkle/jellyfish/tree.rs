@@ -0,0 +1,713 @@
+use std::collections::BTreeMap;
+
+use crate::hasher::Hasher;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::jellyfish::node::{
+    Child, JmtInternalNode, JmtLeafNode, JmtNode, NodeKey, RangeContent,
+};
+use crate::merkle::jellyfish::proof::SparseMerkleProof;
+use crate::merkle::jellyfish::store::{StaleNodeIndex, TreeReader, TreeUpdateBatch};
+use crate::merkle::jellyfish::{KeyHash, nibble};
+
+/// A versioned Jellyfish Merkle tree over a node store.
+///
+/// The tree itself is stateless: it reads nodes through a `TreeReader` and
+/// returns the changes of each update as a `TreeUpdateBatch`, which the
+/// caller writes back with a `TreeWriter`. Version `v` is built on top of
+/// version `v - 1`; version 0 starts from an empty tree.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::Sha256Hasher;
+/// use merkle_trees::merkle::jellyfish::{JellyfishMerkleTree, MemoryTreeStore, TreeWriter};
+///
+/// let mut store = MemoryTreeStore::new();
+/// let key = [0x42u8; 32];
+///
+/// let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+/// let (root, batch) = tree.put_value_set(vec![(key, Some(b"value".to_vec()))], 0).unwrap();
+/// store.write_tree_update_batch(batch);
+///
+/// let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+/// let (value, proof) = tree.get_with_proof(&key, 0).unwrap();
+/// assert_eq!(value.as_deref(), Some(b"value".as_slice()));
+/// assert!(proof.verify(&root, &key, Some(b"value"), &Sha256Hasher::new()));
+/// ```
+pub struct JellyfishMerkleTree<'a, R: TreeReader, H: Hasher> {
+    reader: &'a R,
+    hasher: H,
+}
+
+impl<'a, R: TreeReader, H: Hasher> JellyfishMerkleTree<'a, R, H> {
+    pub fn new(reader: &'a R, hasher: H) -> Self {
+        Self { reader, hasher }
+    }
+
+    /// Apply a set of changes as a new version.
+    ///
+    /// Each entry sets a key to a value, or deletes it when the value is
+    /// `None`. Returns the new root hash and the batch of nodes to write.
+    ///
+    /// Returns `MerkleTreeError::MissingNode` if the previous version (or a
+    /// node it references) is not in the store.
+    pub fn put_value_set(
+        &self,
+        value_set: Vec<(KeyHash, Option<Vec<u8>>)>,
+        version: u64,
+    ) -> Result<([u8; 32], TreeUpdateBatch), MerkleTreeError> {
+        let mut update = Update {
+            reader: self.reader,
+            hasher: &self.hasher,
+            version,
+            node_batch: BTreeMap::new(),
+            stale_node_index_batch: Default::default(),
+        };
+
+        let mut root_key = match version.checked_sub(1) {
+            Some(base_version) => {
+                let key = NodeKey::root(base_version);
+                update.get(&key)?;
+                Some(key)
+            }
+            None => None,
+        };
+
+        for (key, value) in value_set {
+            root_key = match value {
+                Some(value) => {
+                    let leaf = JmtLeafNode::new(key, value);
+                    update.insert(root_key, Vec::new(), leaf)?;
+                    Some(NodeKey::root(version))
+                }
+                None => match root_key {
+                    Some(root) => match update.remove(root.clone(), &key)? {
+                        Removal::NotFound => Some(root),
+                        Removal::Removed(None) => None,
+                        Removal::Removed(Some(_)) => Some(NodeKey::root(version)),
+                    },
+                    None => None,
+                },
+            };
+        }
+
+        // Every version needs its own root, even if nothing changed
+        let root = NodeKey::root(version);
+        match root_key {
+            Some(key) if key == root => {}
+            Some(key) => {
+                let node = update.get(&key)?;
+                update.retire(key);
+                update.put(root.clone(), node);
+            }
+            None => update.put(root.clone(), JmtNode::Null),
+        }
+
+        let root_hash = update.get(&root)?.hash(&self.hasher);
+        let batch = TreeUpdateBatch {
+            node_batch: update.node_batch,
+            stale_node_index_batch: update.stale_node_index_batch,
+        };
+        Ok((root_hash, batch))
+    }
+
+    /// Get the root hash of the given version.
+    pub fn get_root_hash(&self, version: u64) -> Result<[u8; 32], MerkleTreeError> {
+        let root = self.get_node(&NodeKey::root(version))?;
+        Ok(root.hash(&self.hasher))
+    }
+
+    /// Get the value of a key at the given version.
+    pub fn get(&self, key: &KeyHash, version: u64) -> Result<Option<Vec<u8>>, MerkleTreeError> {
+        self.get_with_proof(key, version).map(|(value, _)| value)
+    }
+
+    /// Get the value of a key at the given version, with a proof of
+    /// inclusion (or of exclusion when the key is absent).
+    ///
+    /// # Complexity
+    ///
+    /// - Time: O(log n) node reads, each hashing at most 16 children
+    /// - Space: O(log n) sibling hashes
+    pub fn get_with_proof(
+        &self,
+        key: &KeyHash,
+        version: u64,
+    ) -> Result<(Option<Vec<u8>>, SparseMerkleProof), MerkleTreeError> {
+        let mut siblings = Vec::new();
+        let mut node_key = NodeKey::root(version);
+
+        loop {
+            let node = self.get_node(&node_key)?;
+            let depth = node_key.nibble_path().len();
+
+            let internal = match node {
+                JmtNode::Null => return Ok((None, SparseMerkleProof::new(None, siblings))),
+                JmtNode::Leaf(leaf) => return Ok(self.leaf_proof(key, leaf, siblings)),
+                JmtNode::Internal(internal) => internal,
+            };
+
+            // Walk the 4 binary levels inside the node toward the key's nibble
+            let target = nibble(key, depth);
+            let (mut lo, mut hi) = (0u8, 16u8);
+            while hi - lo > 1 {
+                match internal.single_child(lo, hi) {
+                    RangeContent::Empty => {
+                        return Ok((None, SparseMerkleProof::new(None, siblings)));
+                    }
+                    RangeContent::Leaf(slot, child) => {
+                        let leaf = self.get_leaf(child_key(&node_key, slot, &child))?;
+                        return Ok(self.leaf_proof(key, leaf, siblings));
+                    }
+                    RangeContent::Many => {}
+                }
+                let mid = lo + (hi - lo) / 2;
+                if target < mid {
+                    siblings.push(internal.range_hash(mid, hi, &self.hasher));
+                    hi = mid;
+                } else {
+                    siblings.push(internal.range_hash(lo, mid, &self.hasher));
+                    lo = mid;
+                }
+            }
+
+            match internal.child(target) {
+                Some(child) => node_key = child_key(&node_key, target, child),
+                None => return Ok((None, SparseMerkleProof::new(None, siblings))),
+            }
+        }
+    }
+
+    fn leaf_proof(
+        &self,
+        key: &KeyHash,
+        leaf: JmtLeafNode,
+        siblings: Vec<[u8; 32]>,
+    ) -> (Option<Vec<u8>>, SparseMerkleProof) {
+        let value_hash = self.hasher.hash_bytes(leaf.value());
+        let proof = SparseMerkleProof::new(Some((*leaf.key(), value_hash)), siblings);
+        let value = (leaf.key() == key).then(|| leaf.value().to_vec());
+        (value, proof)
+    }
+
+    fn get_node(&self, node_key: &NodeKey) -> Result<JmtNode, MerkleTreeError> {
+        self.reader
+            .get_node(node_key)
+            .ok_or(MerkleTreeError::MissingNode {
+                version: node_key.version(),
+            })
+    }
+
+    fn get_leaf(&self, node_key: NodeKey) -> Result<JmtLeafNode, MerkleTreeError> {
+        match self.get_node(&node_key)? {
+            JmtNode::Leaf(leaf) => Ok(leaf),
+            _ => Err(MerkleTreeError::MissingNode {
+                version: node_key.version(),
+            }),
+        }
+    }
+}
+
+/// Build the storage key of a child from its parent's key.
+fn child_key(parent: &NodeKey, slot: u8, child: &Child) -> NodeKey {
+    let mut path = parent.nibble_path().to_vec();
+    path.push(slot);
+    NodeKey::new(child.version(), path)
+}
+
+/// The outcome of removing a key from a subtree.
+enum Removal {
+    /// The key was not in the subtree, which is unchanged.
+    NotFound,
+    /// The key was removed; holds the new subtree, or `None` if it is empty.
+    Removed(Option<Child>),
+}
+
+/// The state of a single `put_value_set` call.
+///
+/// New nodes are written at `(version, path)`. Replaced nodes from earlier
+/// versions are recorded as stale; replaced nodes from this version are
+/// simply dropped from the batch.
+struct Update<'a, R: TreeReader, H: Hasher> {
+    reader: &'a R,
+    hasher: &'a H,
+    version: u64,
+    node_batch: BTreeMap<NodeKey, JmtNode>,
+    stale_node_index_batch: std::collections::BTreeSet<StaleNodeIndex>,
+}
+
+impl<'a, R: TreeReader, H: Hasher> Update<'a, R, H> {
+    fn get(&self, node_key: &NodeKey) -> Result<JmtNode, MerkleTreeError> {
+        self.node_batch
+            .get(node_key)
+            .cloned()
+            .or_else(|| self.reader.get_node(node_key))
+            .ok_or(MerkleTreeError::MissingNode {
+                version: node_key.version(),
+            })
+    }
+
+    fn put(&mut self, node_key: NodeKey, node: JmtNode) {
+        self.node_batch.insert(node_key, node);
+    }
+
+    fn retire(&mut self, node_key: NodeKey) {
+        if node_key.version() == self.version {
+            self.node_batch.remove(&node_key);
+        } else {
+            self.stale_node_index_batch.insert(StaleNodeIndex {
+                stale_since_version: self.version,
+                node_key,
+            });
+        }
+    }
+
+    /// Write a leaf at the given path and return a reference to it.
+    fn put_leaf(&mut self, path: Vec<u8>, leaf: JmtLeafNode) -> Child {
+        let hash = leaf.hash(self.hasher);
+        self.put(NodeKey::new(self.version, path), JmtNode::Leaf(leaf));
+        Child::new(self.version, hash, true)
+    }
+
+    /// Write an internal node at the given path and return a reference to it.
+    fn put_internal(&mut self, path: Vec<u8>, internal: JmtInternalNode) -> Child {
+        let hash = internal.hash(self.hasher);
+        self.put(
+            NodeKey::new(self.version, path),
+            JmtNode::Internal(internal),
+        );
+        Child::new(self.version, hash, false)
+    }
+
+    /// Insert a leaf into the subtree stored under `node_key` at `path`.
+    fn insert(
+        &mut self,
+        node_key: Option<NodeKey>,
+        path: Vec<u8>,
+        leaf: JmtLeafNode,
+    ) -> Result<Child, MerkleTreeError> {
+        let Some(node_key) = node_key else {
+            return Ok(self.put_leaf(path, leaf));
+        };
+        let node = self.get(&node_key)?;
+        self.retire(node_key.clone());
+
+        match node {
+            JmtNode::Null => Ok(self.put_leaf(path, leaf)),
+            JmtNode::Leaf(existing) if existing.key() == leaf.key() => {
+                Ok(self.put_leaf(path, leaf))
+            }
+            JmtNode::Leaf(existing) => Ok(self.split(path, existing, leaf)),
+            JmtNode::Internal(mut internal) => {
+                let slot = nibble(leaf.key(), path.len());
+                let child_key = internal
+                    .child(slot)
+                    .map(|child| self::child_key(&node_key, slot, child));
+                let mut child_path = path.clone();
+                child_path.push(slot);
+
+                let child = self.insert(child_key, child_path, leaf)?;
+                internal.set_child(slot, Some(child));
+                Ok(self.put_internal(path, internal))
+            }
+        }
+    }
+
+    /// Replace a leaf by internal nodes down to where two keys diverge.
+    fn split(&mut self, path: Vec<u8>, a: JmtLeafNode, b: JmtLeafNode) -> Child {
+        let depth = path.len();
+        let (slot_a, slot_b) = (nibble(a.key(), depth), nibble(b.key(), depth));
+        let mut internal = JmtInternalNode::default();
+
+        let child_path = |slot| {
+            let mut p = path.clone();
+            p.push(slot);
+            p
+        };
+
+        if slot_a == slot_b {
+            let child = self.split(child_path(slot_a), a, b);
+            internal.set_child(slot_a, Some(child));
+        } else {
+            let child_a = self.put_leaf(child_path(slot_a), a);
+            let child_b = self.put_leaf(child_path(slot_b), b);
+            internal.set_child(slot_a, Some(child_a));
+            internal.set_child(slot_b, Some(child_b));
+        }
+
+        self.put_internal(path, internal)
+    }
+
+    /// Remove a key from the subtree stored under `node_key`.
+    ///
+    /// An internal node left with a single leaf child is replaced by that
+    /// leaf, so the tree stays in its canonical shape.
+    fn remove(&mut self, node_key: NodeKey, key: &KeyHash) -> Result<Removal, MerkleTreeError> {
+        let path = node_key.nibble_path().to_vec();
+
+        match self.get(&node_key)? {
+            JmtNode::Null => Ok(Removal::NotFound),
+            JmtNode::Leaf(leaf) if leaf.key() == key => {
+                self.retire(node_key);
+                Ok(Removal::Removed(None))
+            }
+            JmtNode::Leaf(_) => Ok(Removal::NotFound),
+            JmtNode::Internal(mut internal) => {
+                let slot = nibble(key, path.len());
+                let Some(child) = internal.child(slot).copied() else {
+                    return Ok(Removal::NotFound);
+                };
+
+                let new_child = match self.remove(child_key(&node_key, slot, &child), key)? {
+                    Removal::NotFound => return Ok(Removal::NotFound),
+                    Removal::Removed(new_child) => new_child,
+                };
+                self.retire(node_key.clone());
+                internal.set_child(slot, new_child);
+
+                match internal.single_child(0, 16) {
+                    RangeContent::Empty => Ok(Removal::Removed(None)),
+                    RangeContent::Leaf(remaining_slot, remaining) => {
+                        // Move the last leaf up to take this node's place
+                        let leaf_key = child_key(&node_key, remaining_slot, &remaining);
+                        let JmtNode::Leaf(leaf) = self.get(&leaf_key)? else {
+                            return Err(MerkleTreeError::MissingNode {
+                                version: leaf_key.version(),
+                            });
+                        };
+                        self.retire(leaf_key);
+                        Ok(Removal::Removed(Some(self.put_leaf(path, leaf))))
+                    }
+                    RangeContent::Many => {
+                        Ok(Removal::Removed(Some(self.put_internal(path, internal))))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Hasher, Sha256Hasher};
+    use crate::merkle::jellyfish::store::{MemoryTreeStore, TreeWriter};
+    use crate::merkle::jellyfish::{PLACEHOLDER_HASH, hash_internal, hash_leaf};
+
+    /// Apply a value set at the given version and persist it.
+    fn commit(
+        store: &mut MemoryTreeStore,
+        values: Vec<(KeyHash, Option<Vec<u8>>)>,
+        version: u64,
+    ) -> [u8; 32] {
+        let tree = JellyfishMerkleTree::new(&*store, Sha256Hasher::new());
+        let (root, batch) = tree.put_value_set(values, version).unwrap();
+        store.write_tree_update_batch(batch);
+        root
+    }
+
+    fn key(bytes: &[u8]) -> KeyHash {
+        let mut key = [0u8; 32];
+        key[..bytes.len()].copy_from_slice(bytes);
+        key
+    }
+
+    fn hashed_key(i: u32) -> KeyHash {
+        Sha256Hasher::new().hash_bytes(&i.to_be_bytes())
+    }
+
+    fn assert_included(store: &MemoryTreeStore, key: &KeyHash, value: &[u8], version: u64) {
+        let hasher = Sha256Hasher::new();
+        let tree = JellyfishMerkleTree::new(store, hasher.clone());
+        let root = tree.get_root_hash(version).unwrap();
+        let (found, proof) = tree.get_with_proof(key, version).unwrap();
+        assert_eq!(found.as_deref(), Some(value));
+        assert!(proof.verify(&root, key, Some(value), &hasher));
+    }
+
+    fn assert_excluded(store: &MemoryTreeStore, key: &KeyHash, version: u64) {
+        let hasher = Sha256Hasher::new();
+        let tree = JellyfishMerkleTree::new(store, hasher.clone());
+        let root = tree.get_root_hash(version).unwrap();
+        let (found, proof) = tree.get_with_proof(key, version).unwrap();
+        assert_eq!(found, None);
+        assert!(proof.verify(&root, key, None, &hasher));
+    }
+
+    // =========================================================================
+    // Root Hash Tests
+    // =========================================================================
+
+    #[test]
+    fn test_empty_version_root_is_placeholder() {
+        let mut store = MemoryTreeStore::new();
+        let root = commit(&mut store, vec![], 0);
+        assert_eq!(root, PLACEHOLDER_HASH);
+        assert_excluded(&store, &key(&[1]), 0);
+    }
+
+    #[test]
+    fn test_single_leaf_root_is_leaf_hash() {
+        let hasher = Sha256Hasher::new();
+        let mut store = MemoryTreeStore::new();
+        let k = key(&[0x12]);
+        let root = commit(&mut store, vec![(k, Some(b"v".to_vec()))], 0);
+        assert_eq!(root, hash_leaf(&k, &hasher.hash_bytes(b"v"), &hasher));
+    }
+
+    #[test]
+    fn test_root_matches_sparse_merkle_definition() {
+        // Keys 0x00.. and 0x80.. split at the first bit
+        let hasher = Sha256Hasher::new();
+        let mut store = MemoryTreeStore::new();
+        let (a, b) = (key(&[0x00]), key(&[0x80]));
+        let root = commit(
+            &mut store,
+            vec![(a, Some(b"a".to_vec())), (b, Some(b"b".to_vec()))],
+            0,
+        );
+
+        let expected = hash_internal(
+            &hash_leaf(&a, &hasher.hash_bytes(b"a"), &hasher),
+            &hash_leaf(&b, &hasher.hash_bytes(b"b"), &hasher),
+            &hasher,
+        );
+        assert_eq!(root, expected);
+    }
+
+    #[test]
+    fn test_root_independent_of_insertion_order() {
+        let values: Vec<(KeyHash, Option<Vec<u8>>)> = (0..50)
+            .map(|i| (hashed_key(i), Some(i.to_be_bytes().to_vec())))
+            .collect();
+        let mut reversed = values.clone();
+        reversed.reverse();
+
+        let mut store1 = MemoryTreeStore::new();
+        let mut store2 = MemoryTreeStore::new();
+        assert_eq!(
+            commit(&mut store1, values, 0),
+            commit(&mut store2, reversed, 0)
+        );
+    }
+
+    #[test]
+    fn test_root_independent_of_batching() {
+        let mut store1 = MemoryTreeStore::new();
+        let mut store2 = MemoryTreeStore::new();
+
+        let all: Vec<_> = (0..10)
+            .map(|i| (hashed_key(i), Some(vec![i as u8])))
+            .collect();
+        let root1 = commit(&mut store1, all.clone(), 0);
+
+        commit(&mut store2, all[..5].to_vec(), 0);
+        let root2 = commit(&mut store2, all[5..].to_vec(), 1);
+        assert_eq!(root1, root2);
+    }
+
+    // =========================================================================
+    // Proof Tests
+    // =========================================================================
+
+    #[test]
+    fn test_inclusion_and_exclusion_proofs() {
+        let mut store = MemoryTreeStore::new();
+        let values: Vec<_> = (0..100)
+            .map(|i| (hashed_key(i), Some(i.to_be_bytes().to_vec())))
+            .collect();
+        commit(&mut store, values, 0);
+
+        for i in 0..100 {
+            assert_included(&store, &hashed_key(i), &i.to_be_bytes(), 0);
+        }
+        for i in 100..150 {
+            assert_excluded(&store, &hashed_key(i), 0);
+        }
+    }
+
+    #[test]
+    fn test_keys_sharing_long_prefix() {
+        let mut store = MemoryTreeStore::new();
+        let mut a = [0xabu8; 32];
+        let mut b = [0xabu8; 32];
+        a[31] = 0x00;
+        b[31] = 0x01;
+        commit(
+            &mut store,
+            vec![(a, Some(b"a".to_vec())), (b, Some(b"b".to_vec()))],
+            0,
+        );
+
+        assert_included(&store, &a, b"a", 0);
+        assert_included(&store, &b, b"b", 0);
+
+        let mut c = [0xabu8; 32];
+        c[31] = 0x02;
+        assert_excluded(&store, &c, 0);
+    }
+
+    #[test]
+    fn test_proof_rejects_wrong_value() {
+        let hasher = Sha256Hasher::new();
+        let mut store = MemoryTreeStore::new();
+        let k = hashed_key(1);
+        let root = commit(&mut store, vec![(k, Some(b"v".to_vec()))], 0);
+
+        let tree = JellyfishMerkleTree::new(&store, hasher.clone());
+        let (_, proof) = tree.get_with_proof(&k, 0).unwrap();
+        assert!(!proof.verify(&root, &k, Some(b"other"), &hasher));
+        assert!(!proof.verify(&root, &k, None, &hasher));
+    }
+
+    // =========================================================================
+    // Versioning Tests
+    // =========================================================================
+
+    #[test]
+    fn test_proofs_at_old_versions() {
+        let mut store = MemoryTreeStore::new();
+        let k = hashed_key(7);
+        let root0 = commit(&mut store, vec![(k, Some(b"v0".to_vec()))], 0);
+        let root1 = commit(&mut store, vec![(k, Some(b"v1".to_vec()))], 1);
+        assert_ne!(root0, root1);
+
+        assert_included(&store, &k, b"v0", 0);
+        assert_included(&store, &k, b"v1", 1);
+    }
+
+    #[test]
+    fn test_unchanged_version_keeps_root() {
+        let mut store = MemoryTreeStore::new();
+        let root0 = commit(&mut store, vec![(hashed_key(1), Some(b"v".to_vec()))], 0);
+        let root1 = commit(&mut store, vec![], 1);
+        assert_eq!(root0, root1);
+    }
+
+    #[test]
+    fn test_missing_base_version() {
+        let store = MemoryTreeStore::new();
+        let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+        assert_eq!(
+            tree.put_value_set(vec![], 5).err(),
+            Some(MerkleTreeError::MissingNode { version: 4 })
+        );
+        assert_eq!(
+            tree.get_root_hash(0),
+            Err(MerkleTreeError::MissingNode { version: 0 })
+        );
+    }
+
+    // =========================================================================
+    // Deletion Tests
+    // =========================================================================
+
+    #[test]
+    fn test_delete_restores_previous_root() {
+        let mut store = MemoryTreeStore::new();
+        let base: Vec<_> = (0..20)
+            .map(|i| (hashed_key(i), Some(vec![i as u8])))
+            .collect();
+        let root0 = commit(&mut store, base, 0);
+        commit(&mut store, vec![(hashed_key(99), Some(b"x".to_vec()))], 1);
+        let root2 = commit(&mut store, vec![(hashed_key(99), None)], 2);
+
+        assert_eq!(root0, root2);
+        assert_excluded(&store, &hashed_key(99), 2);
+        assert_included(&store, &hashed_key(99), b"x", 1);
+    }
+
+    #[test]
+    fn test_delete_collapses_to_single_leaf() {
+        let hasher = Sha256Hasher::new();
+        let mut store = MemoryTreeStore::new();
+        let mut a = [0x11u8; 32];
+        let b = [0x11u8; 32];
+        a[31] = 0x10;
+        commit(
+            &mut store,
+            vec![(a, Some(b"a".to_vec())), (b, Some(b"b".to_vec()))],
+            0,
+        );
+        let root = commit(&mut store, vec![(a, None)], 1);
+
+        assert_eq!(root, hash_leaf(&b, &hasher.hash_bytes(b"b"), &hasher));
+        assert_included(&store, &b, b"b", 1);
+    }
+
+    #[test]
+    fn test_delete_everything() {
+        let mut store = MemoryTreeStore::new();
+        commit(&mut store, vec![(hashed_key(1), Some(b"v".to_vec()))], 0);
+        let root = commit(&mut store, vec![(hashed_key(1), None)], 1);
+        assert_eq!(root, PLACEHOLDER_HASH);
+    }
+
+    #[test]
+    fn test_delete_missing_key_is_noop() {
+        let mut store = MemoryTreeStore::new();
+        let root0 = commit(&mut store, vec![(hashed_key(1), Some(b"v".to_vec()))], 0);
+        let root1 = commit(&mut store, vec![(hashed_key(2), None)], 1);
+        assert_eq!(root0, root1);
+    }
+
+    // =========================================================================
+    // Stale Node and Pruning Tests
+    // =========================================================================
+
+    #[test]
+    fn test_update_marks_replaced_nodes_stale() {
+        let mut store = MemoryTreeStore::new();
+        let values: Vec<_> = (0..30)
+            .map(|i| (hashed_key(i), Some(vec![i as u8])))
+            .collect();
+        commit(&mut store, values, 0);
+
+        let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+        let (_, batch) = tree
+            .put_value_set(vec![(hashed_key(0), Some(b"new".to_vec()))], 1)
+            .unwrap();
+
+        // Every replaced node belongs to version 0 and becomes stale at version 1
+        assert!(!batch.stale_node_index_batch.is_empty());
+        for index in &batch.stale_node_index_batch {
+            assert_eq!(index.stale_since_version, 1);
+            assert_eq!(index.node_key.version(), 0);
+        }
+        // One new node per level on the path, none left over from splits
+        assert_eq!(batch.node_batch.len(), batch.stale_node_index_batch.len());
+    }
+
+    #[test]
+    fn test_prune_keeps_latest_version_readable() {
+        let mut store = MemoryTreeStore::new();
+        for version in 0..5u64 {
+            let values = (0..10)
+                .map(|i| (hashed_key(i), Some(vec![i as u8, version as u8])))
+                .collect();
+            commit(&mut store, values, version);
+        }
+
+        let before = store.num_nodes();
+        let pruned = store.prune(4);
+        assert!(pruned > 0);
+        assert_eq!(store.num_nodes(), before - pruned);
+
+        for i in 0..10 {
+            assert_included(&store, &hashed_key(i), &[i as u8, 4], 4);
+        }
+        let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+        assert!(tree.get_root_hash(3).is_err());
+    }
+
+    #[test]
+    fn test_nodes_created_and_replaced_in_same_version_are_not_stale() {
+        let store = MemoryTreeStore::new();
+        let tree = JellyfishMerkleTree::new(&store, Sha256Hasher::new());
+        let values = (0..10)
+            .map(|i| (hashed_key(i), Some(vec![i as u8])))
+            .collect();
+        let (_, batch) = tree.put_value_set(values, 0).unwrap();
+        assert!(batch.stale_node_index_batch.is_empty());
+    }
+}
@@ -19,17 +19,20 @@ impl LeafNode {
         LeafNode { data, hash_value }
     }
 
+    /// Create a leaf whose hash was already computed with
+    /// `Hasher::hash_leaf`, for example as part of a batch.
+    pub(crate) fn with_hash(data: Vec<u8>, hash_value: [u8; 32]) -> Self {
+        LeafNode { data, hash_value }
+    }
+
     /// Get the data stored in this leaf.
     pub fn data(&self) -> &[u8] {
         &self.data
     }
 
-    /// Compute the hash for this leaf (0x00 domain separator).
+    /// Compute the hash for this leaf via `Hasher::hash_leaf`.
     fn compute_hash<H: Hasher>(data: &[u8], hasher: &H) -> [u8; 32] {
-        let mut to_hash = Vec::with_capacity(1 + data.len());
-        to_hash.push(0x00);
-        to_hash.extend_from_slice(data);
-        hasher.hash_bytes(&to_hash)
+        hasher.hash_leaf(data)
     }
 }
 
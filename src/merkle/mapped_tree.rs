@@ -0,0 +1,499 @@
+//! A read-only tree served from a memory-mapped file.
+//!
+//! The file is a 32-byte header followed by the hashes of every level,
+//! from the leaves up to the root, with no gaps:
+//!
+//! ```text
+//! magic: 8 bytes ("MRKLTREE")
+//! version: u8 (= 1)
+//! reserved: 7 zero bytes
+//! hash id: u64 LE multicodec code, 0 if not recorded
+//! size: u64 LE number of leaves
+//! levels: for each level l from 0 to the root, ceil(size / 2^l) hashes
+//! ```
+//!
+//! Every offset follows from the size, so opening the file only checks the
+//! header and the file length; no hash is read until it is needed.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::bytes_to_hex;
+use crate::hasher::{HashId, Hasher};
+use crate::merkle::node_store::NodeStore;
+use crate::merkle::proof::Proof;
+use crate::merkle::simple_tree::{SimpleMerkleTree, verify_proof};
+use crate::merkle::snapshot::tree_height;
+use crate::merkle::stored_tree::StoredMerkleTree;
+use crate::merkle::{MerkleTree, MerkleTreeError};
+
+const MAGIC: &[u8; 8] = b"MRKLTREE";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 32;
+
+/// A frozen tree whose node hashes are read straight from a memory map.
+///
+/// Opening is O(1) whatever the size of the tree, and `prove` reads one
+/// hash per level from the mapping. Leaf data is not stored, so the tree
+/// can prove and verify membership but not return leaves.
+///
+/// Write the file with `SimpleMerkleTree::write_mapped` or
+/// `StoredMerkleTree::write_mapped`.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MappedMerkleTree, MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+/// tree.add_leaf(b"a").unwrap();
+/// tree.add_leaf(b"b").unwrap();
+///
+/// let file = tempfile::NamedTempFile::new().unwrap();
+/// tree.write_mapped(file.as_file()).unwrap();
+///
+/// let mapped = MappedMerkleTree::open(file.path()).unwrap();
+/// assert_eq!(mapped.get_root_bytes(), tree.get_root_bytes());
+/// assert_eq!(mapped.prove(1).unwrap(), tree.prove(1).unwrap());
+/// ```
+pub struct MappedMerkleTree {
+    map: Mmap,
+    size: u64,
+    hash_id: Option<HashId>,
+    /// Byte offset of the first hash of each level.
+    level_offsets: Vec<usize>,
+}
+
+impl MappedMerkleTree {
+    /// Map the tree file at `path`.
+    ///
+    /// Returns `MerkleTreeError::InvalidEncoding` if the file is not a tree
+    /// file, `UnsupportedVersion` for an unknown version, and
+    /// `TruncatedInput` or `TrailingBytes` if its length does not match the
+    /// size in the header.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MerkleTreeError> {
+        let file = File::open(path)?;
+        // SAFETY: tree files are written once and then only read; the
+        // caller must not modify the file while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_LEN)
+            .ok_or(MerkleTreeError::TruncatedInput)?;
+        if &header[..8] != MAGIC {
+            return Err(MerkleTreeError::InvalidEncoding);
+        }
+        if header[8] != FORMAT_VERSION {
+            return Err(MerkleTreeError::UnsupportedVersion { version: header[8] });
+        }
+        if header[9..16].iter().any(|&byte| byte != 0) {
+            return Err(MerkleTreeError::InvalidEncoding);
+        }
+        let hash_id = match read_u64(&header[16..24]) {
+            0 => None,
+            code => Some(HashId::new(code)),
+        };
+        let size = read_u64(&header[24..32]);
+
+        let level_offsets = level_offsets(size).ok_or(MerkleTreeError::InvalidEncoding)?;
+        let expected = level_offsets.last().copied().unwrap_or(HEADER_LEN);
+        if map.len() < expected {
+            return Err(MerkleTreeError::TruncatedInput);
+        }
+        if map.len() > expected {
+            return Err(MerkleTreeError::TrailingBytes {
+                count: map.len() - expected,
+            });
+        }
+
+        Ok(MappedMerkleTree {
+            map,
+            size,
+            hash_id,
+            level_offsets,
+        })
+    }
+
+    /// Get the number of leaves in the tree.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the hash algorithm recorded in the file, if any.
+    pub fn hash_id(&self) -> Option<HashId> {
+        self.hash_id
+    }
+
+    /// Get the root hash as a hex string, or None if the tree is empty.
+    pub fn get_root(&self) -> Option<String> {
+        self.get_root_bytes().map(|root| bytes_to_hex(&root))
+    }
+
+    /// Get the root hash as raw bytes, or None if the tree is empty.
+    pub fn get_root_bytes(&self) -> Option<[u8; 32]> {
+        match self.size {
+            0 => None,
+            size => self.level_hash(tree_height(size), 0).copied(),
+        }
+    }
+
+    /// Get the hash of the node at the given level and index, borrowed
+    /// from the mapping. Leaves are level 0.
+    pub fn level_hash(&self, level: u32, index: u64) -> Option<&[u8; 32]> {
+        let start = *self.level_offsets.get(level as usize)?;
+        let end = *self.level_offsets.get(level as usize + 1)?;
+        let offset = usize::try_from(index)
+            .ok()?
+            .checked_mul(32)?
+            .checked_add(start)?;
+        if offset >= end {
+            return None;
+        }
+        self.map[offset..offset + 32].try_into().ok()
+    }
+
+    /// Generate a membership proof for the leaf at the given index.
+    ///
+    /// Returns `MerkleTreeError::InvalidIndex` if the index is out of bounds
+    /// or the tree is empty.
+    pub fn prove(&self, index: u64) -> Result<Proof, MerkleTreeError> {
+        let tree_size = self.size;
+        if index >= tree_size {
+            return Err(MerkleTreeError::InvalidIndex { index, tree_size });
+        }
+        let siblings = (0..tree_height(tree_size))
+            .map(|level| {
+                let position = index >> level;
+                // The last node of an odd level is its own sibling
+                self.level_hash(level, position ^ 1)
+                    .or_else(|| self.level_hash(level, position))
+                    .copied()
+                    .expect("offsets were checked on open")
+            })
+            .collect();
+        Ok(Proof::new(index, siblings)
+            .with_hash_id(self.hash_id)
+            .with_tree_size(tree_size))
+    }
+
+    /// Verify that a leaf with the given data belongs to a tree with the
+    /// expected root, using `hasher`.
+    ///
+    /// Returns `MerkleTreeError::HasherMismatch` if the proof was made with
+    /// a different algorithm than `hasher`.
+    pub fn verify<H: Hasher>(
+        &self,
+        proof: &Proof,
+        leaf_data: &[u8],
+        expected_root: &[u8; 32],
+        hasher: &H,
+    ) -> Result<bool, MerkleTreeError> {
+        verify_proof(leaf_data, proof, expected_root, hasher)
+    }
+
+    /// Recompute every internal node from the level below and compare it
+    /// with the file.
+    ///
+    /// This reads the whole file, so it is meant for checking a file once
+    /// after it is written or copied. Returns
+    /// `MerkleTreeError::CorruptedNode` for the first node that differs.
+    pub fn check<H: Hasher>(&self, hasher: &H) -> Result<(), MerkleTreeError> {
+        for level in 1..self.level_offsets.len().saturating_sub(1) as u32 {
+            let mut index = 0;
+            while let Some(hash) = self.level_hash(level, index) {
+                let left = self
+                    .level_hash(level - 1, 2 * index)
+                    .expect("width checked");
+                let right = self.level_hash(level - 1, 2 * index + 1).unwrap_or(left);
+                if hasher.hash_internal(left, right) != *hash {
+                    return Err(MerkleTreeError::CorruptedNode { level, index });
+                }
+                index += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().expect("eight bytes"))
+}
+
+/// Byte offsets of each level in a file for a tree of `size` leaves,
+/// followed by the end of the file. None if the file could not fit in
+/// memory.
+fn level_offsets(size: u64) -> Option<Vec<usize>> {
+    let mut offsets = vec![HEADER_LEN];
+    if size == 0 {
+        return Some(offsets);
+    }
+    let mut offset = HEADER_LEN;
+    for level in 0..=tree_height(size) {
+        let width = usize::try_from(((size - 1) >> level) + 1).ok()?;
+        offset = offset.checked_add(width.checked_mul(32)?)?;
+        offsets.push(offset);
+    }
+    Some(offsets)
+}
+
+/// Write a tree file from a source of node hashes.
+fn write_levels<W: Write>(
+    mut writer: W,
+    size: u64,
+    hash_id: Option<HashId>,
+    mut hash_at: impl FnMut(u32, u64) -> Result<[u8; 32], MerkleTreeError>,
+) -> Result<(), MerkleTreeError> {
+    let mut header = [0u8; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8] = FORMAT_VERSION;
+    header[16..24].copy_from_slice(&hash_id.map_or(0, |id| id.code()).to_le_bytes());
+    header[24..32].copy_from_slice(&size.to_le_bytes());
+    writer.write_all(&header)?;
+
+    if size > 0 {
+        for level in 0..=tree_height(size) {
+            for index in 0..((size - 1) >> level) + 1 {
+                writer.write_all(&hash_at(level, index)?)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl<H: Hasher> SimpleMerkleTree<H> {
+    /// Write the tree as a file for `MappedMerkleTree`.
+    ///
+    /// Wrap files in a `BufWriter`, since hashes are written one by one.
+    pub fn write_mapped<W: Write>(&self, writer: W) -> Result<(), MerkleTreeError> {
+        let levels = self.level_hashes();
+        write_levels(
+            writer,
+            self.get_size(),
+            self.hasher().hash_id(),
+            |level, index| Ok(levels[level as usize][index as usize]),
+        )
+    }
+}
+
+impl<H: Hasher, S: NodeStore> StoredMerkleTree<H, S> {
+    /// Write the tree as a file for `MappedMerkleTree`, reading the hashes
+    /// from the store one by one.
+    ///
+    /// Returns `MerkleTreeError::MissingHash` if the store lacks a node.
+    pub fn write_mapped<W: Write>(&self, writer: W) -> Result<(), MerkleTreeError> {
+        write_levels(
+            writer,
+            self.get_size(),
+            self.hasher().hash_id(),
+            |level, index| {
+                self.store()
+                    .get_hash(level, index)
+                    .ok_or(MerkleTreeError::MissingHash { level, index })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Sha256Hasher, SimpleHasher};
+    use crate::merkle::tests::{leaf, tree_of};
+    use std::io::{Seek, SeekFrom};
+    use tempfile::NamedTempFile;
+
+    fn mapped(tree: &SimpleMerkleTree<Sha256Hasher>) -> (NamedTempFile, MappedMerkleTree) {
+        let file = NamedTempFile::new().unwrap();
+        tree.write_mapped(file.as_file()).unwrap();
+        let mapped = MappedMerkleTree::open(file.path()).unwrap();
+        (file, mapped)
+    }
+
+    fn write_file(bytes: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    // =========================================================================
+    // Proof Serving Tests
+    // =========================================================================
+
+    #[test]
+    fn test_matches_simple_tree() {
+        for count in [1, 2, 3, 5, 8, 13, 32, 33] {
+            let tree = tree_of(count);
+            let (_file, mapped) = mapped(&tree);
+            assert_eq!(mapped.get_size(), count);
+            assert_eq!(mapped.get_root_bytes(), tree.get_root_bytes());
+            assert_eq!(mapped.get_root(), tree.get_root());
+            assert_eq!(mapped.hash_id(), Some(HashId::SHA2_256));
+            for index in 0..count {
+                assert_eq!(mapped.prove(index), tree.prove(index), "index {}", index);
+            }
+            assert_eq!(mapped.check(&Sha256Hasher::new()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let tree = tree_of(6);
+        let (_file, mapped) = mapped(&tree);
+        let root = mapped.get_root_bytes().unwrap();
+        let proof = mapped.prove(4).unwrap();
+        assert_eq!(
+            mapped.verify(&proof, b"leaf-4", &root, &Sha256Hasher::new()),
+            Ok(true)
+        );
+        assert_eq!(
+            mapped.verify(&proof, b"leaf-5", &root, &Sha256Hasher::new()),
+            Ok(false)
+        );
+        assert_eq!(
+            mapped.verify(&proof, b"leaf-4", &root, &SimpleHasher::new()),
+            Err(MerkleTreeError::HasherMismatch {
+                proof: HashId::SHA2_256,
+                verifier: HashId::SIMPLE
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let (file, mapped) = mapped(&tree_of(0));
+        assert_eq!(std::fs::metadata(file.path()).unwrap().len(), 32);
+        assert_eq!(mapped.get_size(), 0);
+        assert!(mapped.get_root_bytes().is_none());
+        assert_eq!(
+            mapped.prove(0),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 0,
+                tree_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_level_hash() {
+        let tree = tree_of(3);
+        let (_file, mapped) = mapped(&tree);
+        let levels = tree.level_hashes();
+        assert_eq!(mapped.level_hash(0, 2), Some(&levels[0][2]));
+        assert_eq!(mapped.level_hash(1, 1), Some(&levels[1][1]));
+        assert!(mapped.level_hash(0, 3).is_none());
+        assert!(mapped.level_hash(3, 0).is_none());
+    }
+
+    #[test]
+    fn test_written_from_stored_tree() {
+        let mut stored = StoredMerkleTree::new(Sha256Hasher::new());
+        for i in 0..11 {
+            stored.add_leaf(&leaf(i)).unwrap();
+        }
+        let mut from_stored = Vec::new();
+        stored.write_mapped(&mut from_stored).unwrap();
+        let mut from_simple = Vec::new();
+        tree_of(11).write_mapped(&mut from_simple).unwrap();
+        assert_eq!(from_stored, from_simple);
+    }
+
+    // =========================================================================
+    // File Validation Tests
+    // =========================================================================
+
+    #[test]
+    fn test_header_layout() {
+        let mut bytes = Vec::new();
+        tree_of(2).write_mapped(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"MRKLTREE");
+        assert_eq!(bytes[8], 1);
+        assert_eq!(read_u64(&bytes[16..24]), 0x12);
+        assert_eq!(read_u64(&bytes[24..32]), 2);
+        // Two leaves and the root
+        assert_eq!(bytes.len(), 32 + 3 * 32);
+    }
+
+    #[test]
+    fn test_rejects_bad_header() {
+        let mut bytes = Vec::new();
+        tree_of(2).write_mapped(&mut bytes).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let file = write_file(&bad_magic);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::InvalidEncoding)
+        );
+
+        let mut bad_version = bytes.clone();
+        bad_version[8] = 2;
+        let file = write_file(&bad_version);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::UnsupportedVersion { version: 2 })
+        );
+
+        let mut reserved = bytes.clone();
+        reserved[12] = 1;
+        let file = write_file(&reserved);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::InvalidEncoding)
+        );
+
+        let file = write_file(&bytes[..20]);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::TruncatedInput)
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_length() {
+        let mut bytes = Vec::new();
+        tree_of(5).write_mapped(&mut bytes).unwrap();
+
+        let file = write_file(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::TruncatedInput)
+        );
+
+        let mut longer = bytes.clone();
+        longer.extend_from_slice(&[0u8; 3]);
+        let file = write_file(&longer);
+        assert_eq!(
+            MappedMerkleTree::open(file.path()).err(),
+            Some(MerkleTreeError::TrailingBytes { count: 3 })
+        );
+
+        // A size that cannot fit in memory
+        let mut huge = bytes[..32].to_vec();
+        huge[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        let file = write_file(&huge);
+        assert!(MappedMerkleTree::open(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_check_finds_corruption() {
+        let tree = tree_of(5);
+        let file = NamedTempFile::new().unwrap();
+        tree.write_mapped(file.as_file()).unwrap();
+
+        // Level 0 has 5 hashes; overwrite node 1 of level 1
+        let mut handle = file.reopen().unwrap();
+        handle.seek(SeekFrom::Start(32 + 6 * 32)).unwrap();
+        handle.write_all(&[0xaa; 32]).unwrap();
+
+        let mapped = MappedMerkleTree::open(file.path()).unwrap();
+        assert_eq!(
+            mapped.check(&Sha256Hasher::new()),
+            Err(MerkleTreeError::CorruptedNode { level: 1, index: 1 })
+        );
+    }
+}
@@ -1,18 +1,49 @@
+pub mod absence_proof;
+pub(crate) mod codec;
+pub mod diff;
+pub mod digest32;
+#[cfg(all(feature = "file-store", unix))]
+pub mod file_store;
 pub mod hash;
 pub mod internal_node;
+pub mod jellyfish;
 pub mod leaf_node;
+#[cfg(feature = "mmap")]
+pub mod mapped_tree;
 pub mod node;
+pub mod node_store;
+pub mod persistent_tree;
 pub mod proof;
 pub mod simple_tree;
+pub mod snapshot;
+pub mod sorted_tree;
+pub mod stored_tree;
+pub mod sync;
+#[cfg(feature = "json")]
+mod tree_dump;
+mod tree_file;
 
 // Re-exports for convenience
+use crate::hasher::HashId;
 pub use crate::hasher::Hasher;
+pub use absence_proof::{AbsenceProof, NeighborProof};
+pub use diff::TreeDiff;
+pub use digest32::{Digest32, ParseHashError};
+#[cfg(all(feature = "file-store", unix))]
+pub use file_store::FileStore;
 pub use hash::Hash;
 pub use internal_node::InternalNode;
 pub use leaf_node::LeafNode;
+#[cfg(feature = "mmap")]
+pub use mapped_tree::MappedMerkleTree;
 pub use node::Node;
+pub use node_store::{HashOnlyNodeStore, MemoryNodeStore, NodeStore, PrunableNodeStore};
+pub use persistent_tree::PersistentMerkleTree;
 pub use proof::Proof;
-pub use simple_tree::SimpleMerkleTree;
+pub use simple_tree::{DynMerkleTree, SimpleMerkleTree};
+pub use snapshot::{Snapshot, TreeSnapshot};
+pub use sorted_tree::SortedMerkleTree;
+pub use stored_tree::StoredMerkleTree;
 
 /// A Merkle tree is a binary tree in which every leaf node
 /// is labelled with a data block and every non-leaf node
@@ -21,9 +52,12 @@ pub use simple_tree::SimpleMerkleTree;
 ///
 /// # Invariants
 /// * A leaf Node is raw data that gets hashed inside the Merkle tree.
-/// * Domain separation:
-///     * Leaves: H(0x00 || leaf_bytes)
-///     * Internal nodes: H(0x01 || left_hash || right_hash)
+/// * Domain separation, chosen by the hasher:
+///     * Leaves: `Hasher::hash_leaf(leaf_bytes)`
+///     * Internal nodes: `Hasher::hash_internal(left_hash, right_hash)`
+///     * Both default to `HashScheme::STANDARD`, i.e. H(0x00 || leaf_bytes)
+///       and H(0x01 || left_hash || right_hash); `SchemedHasher` and
+///       hashers such as Poseidon or `PersonalizedHasher` use their own
 /// * Dealing with Odd Numbers of Nodes:
 ///     * Duplicate the last hash
 /// * Empty Input
@@ -41,11 +75,26 @@ pub trait MerkleTree<H: Hasher> {
     fn add_leaf(&mut self, data: &[u8]) -> Result<(), MerkleTreeError>;
 
     /// Get the root hash of the tree as a hex string, or None if empty.
+    ///
+    /// Parse it back into bytes with `Digest32::from_hex`.
     fn get_root(&self) -> Option<String>;
 
     /// Get the root hash as raw bytes, or None if empty.
     fn get_root_bytes(&self) -> Option<[u8; 32]>;
 
+    /// Get the root hash as a `Digest32`, or None if empty.
+    fn get_root_digest(&self) -> Option<Digest32> {
+        self.get_root_bytes().map(Digest32::from)
+    }
+
+    /// Get the root as a hex multihash, tagged with the hasher's `HashId`.
+    ///
+    /// Returns None if the tree is empty or the hasher has no identifier.
+    /// The default returns None; the trees in this crate override it.
+    fn get_root_multihash(&self) -> Option<String> {
+        None
+    }
+
     /// Get the data at the given leaf index.
     fn get_data(&self, index: u64) -> Option<&[u8]>;
 
@@ -67,11 +116,13 @@ pub trait MerkleTree<H: Hasher> {
     ///
     /// This method requires a proof to have been generated and stored on the tree.
     /// For standalone verification without a tree instance, use `verify_proof()`.
+    /// A proof made with a different hash algorithm does not verify.
     fn verify(&self, proof: &Proof, leaf_data: &[u8], expected_root: &[u8; 32]) -> bool;
 }
 
 /// Errors that can occur when working with a Merkle tree.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MerkleTreeError {
     /// The input data was empty.
     EmptyInput,
@@ -82,6 +133,98 @@ pub enum MerkleTreeError {
         /// The number of leaves in the tree.
         tree_size: u64,
     },
+    /// The operation requires a tree with at least one leaf.
+    EmptyTree,
+    /// The key is present in the tree, so its absence cannot be proven.
+    KeyPresent {
+        /// The index of the leaf holding the key.
+        index: u64,
+    },
+    /// A key is too long for the keyed hash function.
+    InvalidKeyLength {
+        /// The length of the rejected key, in bytes.
+        length: usize,
+        /// The maximum key length, in bytes.
+        max: usize,
+    },
+    /// A node needed by the operation is missing from the node store.
+    MissingNode {
+        /// The version of the missing node.
+        version: u64,
+    },
+    /// No hasher is registered under the requested name.
+    UnknownHasher {
+        /// The name that was looked up.
+        name: String,
+    },
+    /// A proof was made with a different hash algorithm than the verifier's.
+    HasherMismatch {
+        /// The algorithm recorded in the proof.
+        proof: HashId,
+        /// The algorithm of the verifying hasher.
+        verifier: HashId,
+    },
+    /// Encoded input ended before the value was complete.
+    TruncatedInput,
+    /// Encoded input is malformed, such as a non-canonical varint.
+    InvalidEncoding,
+    /// The encoding uses a format version this library does not support.
+    UnsupportedVersion {
+        /// The version found in the input.
+        version: u8,
+    },
+    /// A proof has more siblings than its tree can have levels.
+    ExcessSiblings {
+        /// The number of siblings in the proof.
+        count: u64,
+        /// The maximum number of siblings for the tree size.
+        max: u64,
+    },
+    /// A proof has fewer siblings than its recorded tree size requires.
+    MissingSiblings {
+        /// The number of siblings in the proof.
+        count: u64,
+        /// The number of siblings a proof for the tree size has.
+        expected: u64,
+    },
+    /// Bytes remain after a complete encoded value.
+    TrailingBytes {
+        /// The number of unread bytes.
+        count: usize,
+    },
+    /// Rebuilding a tree did not reproduce its recorded root.
+    RootMismatch,
+    /// A stored node hash does not match the hash recomputed from its data.
+    CorruptedNode {
+        /// The level of the node, where leaves are level 0.
+        level: u32,
+        /// The position of the node within its level.
+        index: u64,
+    },
+    /// A node hash needed by the operation is missing from the node store.
+    MissingHash {
+        /// The level of the node, where leaves are level 0.
+        level: u32,
+        /// The position of the node within its level.
+        index: u64,
+    },
+    /// The leaf was dropped by pruning.
+    Pruned {
+        /// The requested leaf index.
+        index: u64,
+        /// The index of the oldest leaf still retained.
+        first_retained: u64,
+    },
+    /// A JSON tree dump is malformed or does not match the schema.
+    InvalidDump {
+        /// What is wrong with the dump.
+        message: String,
+    },
+    /// Reading from or writing to storage failed.
+    Io {
+        /// The underlying error message.
+        message: String,
+    },
 }
 
 impl std::fmt::Display for MerkleTreeError {
@@ -95,15 +238,111 @@ impl std::fmt::Display for MerkleTreeError {
                     index, tree_size
                 )
             }
+            MerkleTreeError::EmptyTree => write!(f, "the tree has no leaves"),
+            MerkleTreeError::KeyPresent { index } => {
+                write!(f, "key is present in the tree at index {}", index)
+            }
+            MerkleTreeError::InvalidKeyLength { length, max } => {
+                write!(f, "key of {} bytes exceeds the maximum of {}", length, max)
+            }
+            MerkleTreeError::MissingNode { version } => {
+                write!(f, "node of version {} is missing from the store", version)
+            }
+            MerkleTreeError::UnknownHasher { name } => {
+                write!(f, "no hasher is registered as \"{}\"", name)
+            }
+            MerkleTreeError::HasherMismatch { proof, verifier } => {
+                write!(
+                    f,
+                    "proof was made with {} but is verified with {}",
+                    proof, verifier
+                )
+            }
+            MerkleTreeError::TruncatedInput => write!(f, "input ended unexpectedly"),
+            MerkleTreeError::InvalidEncoding => write!(f, "input is not validly encoded"),
+            MerkleTreeError::UnsupportedVersion { version } => {
+                write!(f, "unsupported format version {}", version)
+            }
+            MerkleTreeError::ExcessSiblings { count, max } => {
+                write!(
+                    f,
+                    "proof has {} siblings but at most {} are possible",
+                    count, max
+                )
+            }
+            MerkleTreeError::MissingSiblings { count, expected } => {
+                write!(
+                    f,
+                    "proof has {} siblings but its tree size needs {}",
+                    count, expected
+                )
+            }
+            MerkleTreeError::TrailingBytes { count } => {
+                write!(f, "{} unexpected bytes after the end of input", count)
+            }
+            MerkleTreeError::RootMismatch => {
+                write!(f, "rebuilt root does not match the recorded root")
+            }
+            MerkleTreeError::CorruptedNode { level, index } => {
+                write!(
+                    f,
+                    "stored hash of node {} at level {} does not match its data",
+                    index, level
+                )
+            }
+            MerkleTreeError::MissingHash { level, index } => {
+                write!(
+                    f,
+                    "hash of node {} at level {} is missing from the store",
+                    index, level
+                )
+            }
+            MerkleTreeError::Pruned {
+                index,
+                first_retained,
+            } => {
+                write!(
+                    f,
+                    "leaf {} has been pruned; leaves before {} are no longer available",
+                    index, first_retained
+                )
+            }
+            MerkleTreeError::InvalidDump { message } => {
+                write!(f, "invalid tree dump: {}", message)
+            }
+            MerkleTreeError::Io { message } => write!(f, "I/O error: {}", message),
         }
     }
 }
 
 impl std::error::Error for MerkleTreeError {}
 
+impl From<std::io::Error> for MerkleTreeError {
+    fn from(err: std::io::Error) -> Self {
+        MerkleTreeError::Io {
+            message: err.to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::hasher::Sha256Hasher;
+
+    /// Data of the leaf at `index` in the trees built by `tree_of`.
+    pub(crate) fn leaf(index: u64) -> Vec<u8> {
+        format!("leaf-{}", index).into_bytes()
+    }
+
+    /// A SHA-256 tree holding `leaf(0)` up to `leaf(count - 1)`.
+    pub(crate) fn tree_of(count: u64) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for index in 0..count {
+            tree.add_leaf(&leaf(index)).unwrap();
+        }
+        tree
+    }
 
     #[test]
     fn test_merkle_tree_error_display_empty_input() {
@@ -123,6 +362,163 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merkle_tree_error_display_empty_tree() {
+        let err = MerkleTreeError::EmptyTree;
+        assert_eq!(err.to_string(), "the tree has no leaves");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_key_present() {
+        let err = MerkleTreeError::KeyPresent { index: 4 };
+        assert_eq!(err.to_string(), "key is present in the tree at index 4");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_invalid_key_length() {
+        let err = MerkleTreeError::InvalidKeyLength {
+            length: 65,
+            max: 64,
+        };
+        assert_eq!(err.to_string(), "key of 65 bytes exceeds the maximum of 64");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_missing_node() {
+        let err = MerkleTreeError::MissingNode { version: 7 };
+        assert_eq!(
+            err.to_string(),
+            "node of version 7 is missing from the store"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_unknown_hasher() {
+        let err = MerkleTreeError::UnknownHasher {
+            name: "md5".to_string(),
+        };
+        assert_eq!(err.to_string(), "no hasher is registered as \"md5\"");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_hasher_mismatch() {
+        let err = MerkleTreeError::HasherMismatch {
+            proof: HashId::SIMPLE,
+            verifier: HashId::SHA2_256,
+        };
+        assert_eq!(
+            err.to_string(),
+            "proof was made with simple but is verified with sha2-256"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_truncated_input() {
+        let err = MerkleTreeError::TruncatedInput;
+        assert_eq!(err.to_string(), "input ended unexpectedly");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_invalid_encoding() {
+        let err = MerkleTreeError::InvalidEncoding;
+        assert_eq!(err.to_string(), "input is not validly encoded");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_unsupported_version() {
+        let err = MerkleTreeError::UnsupportedVersion { version: 9 };
+        assert_eq!(err.to_string(), "unsupported format version 9");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_excess_siblings() {
+        let err = MerkleTreeError::ExcessSiblings { count: 4, max: 2 };
+        assert_eq!(
+            err.to_string(),
+            "proof has 4 siblings but at most 2 are possible"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_missing_siblings() {
+        let err = MerkleTreeError::MissingSiblings {
+            count: 1,
+            expected: 3,
+        };
+        assert_eq!(
+            err.to_string(),
+            "proof has 1 siblings but its tree size needs 3"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_trailing_bytes() {
+        let err = MerkleTreeError::TrailingBytes { count: 3 };
+        assert_eq!(err.to_string(), "3 unexpected bytes after the end of input");
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_root_mismatch() {
+        let err = MerkleTreeError::RootMismatch;
+        assert_eq!(
+            err.to_string(),
+            "rebuilt root does not match the recorded root"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_corrupted_node() {
+        let err = MerkleTreeError::CorruptedNode { level: 1, index: 6 };
+        assert_eq!(
+            err.to_string(),
+            "stored hash of node 6 at level 1 does not match its data"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_missing_hash() {
+        let err = MerkleTreeError::MissingHash { level: 2, index: 1 };
+        assert_eq!(
+            err.to_string(),
+            "hash of node 1 at level 2 is missing from the store"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_pruned() {
+        let err = MerkleTreeError::Pruned {
+            index: 3,
+            first_retained: 8,
+        };
+        assert_eq!(
+            err.to_string(),
+            "leaf 3 has been pruned; leaves before 8 are no longer available"
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_invalid_dump() {
+        let err = MerkleTreeError::InvalidDump {
+            message: "unsupported format \"x\"".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid tree dump: unsupported format \"x\""
+        );
+    }
+
+    #[test]
+    fn test_merkle_tree_error_display_io() {
+        let err = MerkleTreeError::from(std::io::Error::other("disk full"));
+        assert_eq!(
+            err,
+            MerkleTreeError::Io {
+                message: "disk full".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "I/O error: disk full");
+    }
+
     #[test]
     fn test_merkle_tree_error_is_std_error() {
         let err: Box<dyn std::error::Error> = Box::new(MerkleTreeError::EmptyInput);
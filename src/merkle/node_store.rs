@@ -0,0 +1,342 @@
+use crate::merkle::MerkleTreeError;
+
+/// Storage for the leaves and node hashes of a `StoredMerkleTree`.
+///
+/// Nodes are addressed by level and index: level 0 holds the leaf hashes,
+/// and the node at `(level, index)` covers the leaves `index << level` up
+/// to `(index + 1) << level`. A tree of `n` leaves has `ceil(n / 2^level)`
+/// nodes on each level, up to the root at level `ceil(log2(n))`.
+///
+/// Writes go either to an existing position or to the end of a level (or
+/// of the leaves), so a store can be append-only apart from overwrites.
+/// Implementations backed by disk report write failures as
+/// `MerkleTreeError::Io`.
+pub trait NodeStore {
+    /// Get the hash of the node at the given level and index.
+    fn get_hash(&self, level: u32, index: u64) -> Option<[u8; 32]>;
+
+    /// Store the hash of the node at the given level and index.
+    fn put_hash(&mut self, level: u32, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError>;
+
+    /// Get the data of the leaf at the given index.
+    fn get_leaf(&self, index: u64) -> Option<&[u8]>;
+
+    /// Store the data of the leaf at the given index.
+    fn put_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError>;
+
+    /// Get the number of leaves stored.
+    fn leaf_count(&self) -> u64;
+
+    /// Get the index of the oldest leaf that has not been pruned.
+    ///
+    /// Stores that never prune keep the default of 0; see
+    /// `PrunableNodeStore`.
+    fn pruned_before(&self) -> u64 {
+        0
+    }
+}
+
+/// A `NodeStore` that can drop the oldest leaves and the hashes that are
+/// only needed to prove them.
+///
+/// After `prune(before)`, a store keeps the data of leaves `before..` and,
+/// on each level, the hashes from the pair containing `before >> level`
+/// onwards. That is every hash on the proof paths of the retained leaves
+/// and of any leaf appended later; older positions read as missing.
+pub trait PrunableNodeStore: NodeStore {
+    /// Drop leaf data before `before` and the hashes no longer needed.
+    /// Pruning never brings data back, so a smaller `before` than an earlier
+    /// call is a no-op.
+    fn prune(&mut self, before: u64);
+}
+
+/// Index of the first hash kept on `level` after pruning before `before`.
+fn first_kept_node(before: u64, level: u32) -> u64 {
+    (before >> level) & !1
+}
+
+/// Items of one level, where the first `offset` items have been pruned.
+#[derive(Debug, Clone, Default)]
+struct Column<T> {
+    offset: u64,
+    items: Vec<T>,
+}
+
+impl<T> Column<T> {
+    fn len(&self) -> u64 {
+        self.offset + self.items.len() as u64
+    }
+
+    fn get(&self, index: u64) -> Option<&T> {
+        let index = index.checked_sub(self.offset)?;
+        self.items.get(usize::try_from(index).ok()?)
+    }
+
+    /// Overwrite the item at `index`, or append when `index` is the length.
+    fn put(&mut self, index: u64, item: T) -> Result<(), MerkleTreeError> {
+        if index < self.offset {
+            return Err(MerkleTreeError::Pruned {
+                index,
+                first_retained: self.offset,
+            });
+        }
+        let len = self.len();
+        match index {
+            i if i < len => self.items[(i - self.offset) as usize] = item,
+            i if i == len => self.items.push(item),
+            _ => {
+                return Err(MerkleTreeError::InvalidIndex {
+                    index,
+                    tree_size: len,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the items before `index`.
+    fn drop_before(&mut self, index: u64) {
+        let count = index
+            .saturating_sub(self.offset)
+            .min(self.items.len() as u64);
+        self.items.drain(..count as usize);
+        self.items.shrink_to_fit();
+        self.offset += count;
+    }
+}
+
+/// Node hashes by level, shared by the in-memory stores.
+#[derive(Debug, Clone, Default)]
+struct Levels {
+    levels: Vec<Column<[u8; 32]>>,
+}
+
+impl Levels {
+    fn get(&self, level: u32, index: u64) -> Option<[u8; 32]> {
+        self.levels.get(level as usize)?.get(index).copied()
+    }
+
+    fn put(&mut self, level: u32, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        let level = level as usize;
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, Column::default);
+        }
+        self.levels[level].put(index, hash)
+    }
+
+    fn prune(&mut self, before: u64) {
+        for (level, column) in self.levels.iter_mut().enumerate() {
+            column.drop_before(first_kept_node(before, level as u32));
+        }
+    }
+}
+
+/// A `NodeStore` that keeps everything in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryNodeStore {
+    leaves: Column<Vec<u8>>,
+    levels: Levels,
+}
+
+impl MemoryNodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NodeStore for MemoryNodeStore {
+    fn get_hash(&self, level: u32, index: u64) -> Option<[u8; 32]> {
+        self.levels.get(level, index)
+    }
+
+    fn put_hash(&mut self, level: u32, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        self.levels.put(level, index, hash)
+    }
+
+    fn get_leaf(&self, index: u64) -> Option<&[u8]> {
+        self.leaves.get(index).map(Vec::as_slice)
+    }
+
+    fn put_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError> {
+        self.leaves.put(index, data.to_vec())
+    }
+
+    fn leaf_count(&self) -> u64 {
+        self.leaves.len()
+    }
+
+    fn pruned_before(&self) -> u64 {
+        self.leaves.offset
+    }
+}
+
+impl PrunableNodeStore for MemoryNodeStore {
+    fn prune(&mut self, before: u64) {
+        self.leaves.drop_before(before);
+        self.levels.prune(before);
+    }
+}
+
+/// A `NodeStore` that keeps node hashes in memory but drops leaf data.
+///
+/// Each leaf costs only its 32-byte hash (plus the internal nodes above
+/// it), so it suits trees over records that already live elsewhere.
+/// `get_leaf` always returns None, and the leaf count is the number of
+/// leaf hashes.
+#[derive(Debug, Clone, Default)]
+pub struct HashOnlyNodeStore {
+    levels: Levels,
+    pruned_before: u64,
+}
+
+impl HashOnlyNodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NodeStore for HashOnlyNodeStore {
+    fn get_hash(&self, level: u32, index: u64) -> Option<[u8; 32]> {
+        self.levels.get(level, index)
+    }
+
+    fn put_hash(&mut self, level: u32, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        self.levels.put(level, index, hash)
+    }
+
+    fn get_leaf(&self, _index: u64) -> Option<&[u8]> {
+        None
+    }
+
+    /// Discards the data, checking only that the index is in range.
+    fn put_leaf(&mut self, index: u64, _data: &[u8]) -> Result<(), MerkleTreeError> {
+        let tree_size = self.leaf_count();
+        if index > tree_size {
+            return Err(MerkleTreeError::InvalidIndex { index, tree_size });
+        }
+        Ok(())
+    }
+
+    fn leaf_count(&self) -> u64 {
+        self.levels.levels.first().map_or(0, Column::len)
+    }
+
+    fn pruned_before(&self) -> u64 {
+        self.pruned_before
+    }
+}
+
+impl PrunableNodeStore for HashOnlyNodeStore {
+    fn prune(&mut self, before: u64) {
+        self.levels.prune(before);
+        self.pruned_before = self.pruned_before.max(before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_and_get() {
+        let mut store = MemoryNodeStore::new();
+        store.put_leaf(0, b"a").unwrap();
+        store.put_hash(0, 0, [1u8; 32]).unwrap();
+        store.put_hash(2, 0, [2u8; 32]).unwrap();
+
+        assert_eq!(store.leaf_count(), 1);
+        assert_eq!(store.get_leaf(0), Some(b"a".as_slice()));
+        assert_eq!(store.get_leaf(1), None);
+        assert_eq!(store.get_hash(0, 0), Some([1u8; 32]));
+        assert_eq!(store.get_hash(1, 0), None);
+        assert_eq!(store.get_hash(2, 0), Some([2u8; 32]));
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let mut store = MemoryNodeStore::new();
+        store.put_leaf(0, b"a").unwrap();
+        store.put_leaf(0, b"b").unwrap();
+        store.put_hash(0, 0, [1u8; 32]).unwrap();
+        store.put_hash(0, 0, [3u8; 32]).unwrap();
+
+        assert_eq!(store.leaf_count(), 1);
+        assert_eq!(store.get_leaf(0), Some(b"b".as_slice()));
+        assert_eq!(store.get_hash(0, 0), Some([3u8; 32]));
+    }
+
+    #[test]
+    fn test_rejects_gaps() {
+        let mut store = MemoryNodeStore::new();
+        assert_eq!(
+            store.put_leaf(1, b"a"),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 1,
+                tree_size: 0
+            })
+        );
+        assert_eq!(
+            store.put_hash(0, 2, [0u8; 32]),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 2,
+                tree_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_hash_only_store() {
+        let mut store = HashOnlyNodeStore::new();
+        assert_eq!(store.leaf_count(), 0);
+        store.put_leaf(0, b"a").unwrap();
+        store.put_hash(0, 0, [1u8; 32]).unwrap();
+
+        assert_eq!(store.leaf_count(), 1);
+        assert_eq!(store.get_leaf(0), None);
+        assert_eq!(store.get_hash(0, 0), Some([1u8; 32]));
+        assert_eq!(
+            store.put_leaf(2, b"b"),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 2,
+                tree_size: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut store = MemoryNodeStore::new();
+        for i in 0..6 {
+            store.put_leaf(i, &[i as u8]).unwrap();
+            store.put_hash(0, i, [i as u8; 32]).unwrap();
+        }
+        store.put_hash(1, 0, [10u8; 32]).unwrap();
+        store.put_hash(1, 1, [11u8; 32]).unwrap();
+        store.put_hash(1, 2, [12u8; 32]).unwrap();
+
+        store.prune(3);
+        assert_eq!(store.pruned_before(), 3);
+        assert_eq!(store.leaf_count(), 6);
+        assert_eq!(store.get_leaf(2), None);
+        assert_eq!(store.get_leaf(3), Some([3u8].as_slice()));
+        // Leaf 3 still needs its sibling, leaf 2
+        assert_eq!(store.get_hash(0, 1), None);
+        assert_eq!(store.get_hash(0, 2), Some([2u8; 32]));
+        // Level 1 keeps the pair from node 0, the sibling of node 1
+        assert_eq!(store.get_hash(1, 0), Some([10u8; 32]));
+        assert_eq!(
+            store.put_leaf(1, b"x"),
+            Err(MerkleTreeError::Pruned {
+                index: 1,
+                first_retained: 3
+            })
+        );
+
+        // Pruning less than before changes nothing
+        store.prune(1);
+        assert_eq!(store.pruned_before(), 3);
+        assert_eq!(store.get_hash(0, 2), Some([2u8; 32]));
+        store.put_leaf(6, b"g").unwrap();
+        assert_eq!(store.leaf_count(), 7);
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::bytes_to_hex;
+use crate::hasher::Hasher;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::hash::Hash;
+use crate::merkle::node::Node;
+use crate::merkle::proof::Proof;
+use crate::merkle::simple_tree::verify_proof;
+use crate::merkle::snapshot::{Snapshot, collect_siblings, leaf_at, tree_height};
+
+/// A copy-on-write Merkle tree that keeps past versions.
+///
+/// Unlike `SimpleMerkleTree`, which rebuilds every node on each change,
+/// this tree only creates the O(log n) nodes on the path from the modified
+/// leaf to the root. Every other subtree is shared through `Arc` with the
+/// previous version.
+///
+/// Calling `commit` records the current state as a `Snapshot` that can be
+/// queried later with `get_root`, `get_data` and `prove`. Old versions are
+/// freed by calling `release` (or `prune_before`) once no caller still
+/// holds a clone of the snapshot.
+///
+/// Roots are identical to those of a `SimpleMerkleTree` built from the same
+/// leaves, so proofs from either tree verify with `verify_proof`.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MerkleTree, PersistentMerkleTree, Sha256Hasher};
+///
+/// let mut tree = PersistentMerkleTree::new(Sha256Hasher::new());
+/// tree.add_leaf(b"a").unwrap();
+/// let v0 = tree.commit();
+///
+/// tree.add_leaf(b"b").unwrap();
+/// let v1 = tree.commit();
+///
+/// assert_eq!(v0.get_size(), 1);
+/// assert_eq!(v1.get_size(), 2);
+/// assert_ne!(v0.get_root(), v1.get_root());
+/// ```
+pub struct PersistentMerkleTree<H: Hasher> {
+    root: Option<Arc<Node>>,
+    size: u64,
+    hasher: H,
+    next_version: u64,
+    snapshots: BTreeMap<u64, Snapshot>,
+}
+
+impl<H: Hasher> MerkleTree<H> for PersistentMerkleTree<H> {
+    fn add_leaf(&mut self, data: &[u8]) -> Result<(), MerkleTreeError> {
+        if data.is_empty() {
+            return Err(MerkleTreeError::EmptyInput);
+        }
+
+        let leaf = Arc::new(Node::leaf(data.to_vec(), &self.hasher));
+        self.replace_path(self.size, leaf, self.size + 1);
+        Ok(())
+    }
+
+    fn get_root(&self) -> Option<String> {
+        self.root.as_ref().map(|r| bytes_to_hex(r.hash()))
+    }
+
+    fn get_root_bytes(&self) -> Option<[u8; 32]> {
+        self.root.as_ref().map(|r| {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(r.hash());
+            bytes
+        })
+    }
+
+    fn get_root_multihash(&self) -> Option<String> {
+        let hash_id = self.hasher.hash_id()?;
+        let root = self.get_root_bytes()?;
+        Some(bytes_to_hex(&hash_id.multihash(&root)))
+    }
+
+    fn get_data(&self, index: u64) -> Option<&[u8]> {
+        let root = self.root.as_ref()?;
+        if index >= self.size {
+            return None;
+        }
+        leaf_at(root, self.size, index).get_data()
+    }
+
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    fn prove(&self, index: u64) -> Result<Proof, MerkleTreeError> {
+        let tree_size = self.size;
+        match &self.root {
+            Some(root) if index < tree_size => {
+                let siblings = collect_siblings(root, tree_size, index);
+                Ok(Proof::new(index, siblings)
+                    .with_hash_id(self.hasher.hash_id())
+                    .with_tree_size(tree_size))
+            }
+            _ => Err(MerkleTreeError::InvalidIndex { index, tree_size }),
+        }
+    }
+
+    fn verify(&self, proof: &Proof, leaf_data: &[u8], expected_root: &[u8; 32]) -> bool {
+        verify_proof(leaf_data, proof, expected_root, &self.hasher).unwrap_or(false)
+    }
+}
+
+impl<H: Hasher> PersistentMerkleTree<H> {
+    pub fn new(hasher: H) -> Self {
+        Self {
+            root: None,
+            size: 0,
+            hasher,
+            next_version: 0,
+            snapshots: BTreeMap::new(),
+        }
+    }
+
+    /// Replace the data of an existing leaf.
+    ///
+    /// Only the nodes on the path from the leaf to the root are recreated;
+    /// snapshots taken before the update are unaffected.
+    pub fn update_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError> {
+        if data.is_empty() {
+            return Err(MerkleTreeError::EmptyInput);
+        }
+        if index >= self.size {
+            return Err(MerkleTreeError::InvalidIndex {
+                index,
+                tree_size: self.size,
+            });
+        }
+
+        let leaf = Arc::new(Node::leaf(data.to_vec(), &self.hasher));
+        self.replace_path(index, leaf, self.size);
+        Ok(())
+    }
+
+    /// Record the current state as a new version and return its snapshot.
+    ///
+    /// The snapshot is retained by the tree until it is released, and can
+    /// be looked up again with `snapshot`.
+    pub fn commit(&mut self) -> Snapshot {
+        let version = self.next_version;
+        self.next_version += 1;
+
+        let snapshot = Snapshot::new(version, self.root.clone(), self.size, self.hasher.hash_id());
+        self.snapshots.insert(version, snapshot.clone());
+        snapshot
+    }
+
+    /// Get a retained snapshot by version.
+    pub fn snapshot(&self, version: u64) -> Option<&Snapshot> {
+        self.snapshots.get(&version)
+    }
+
+    /// Get the latest retained snapshot, if any.
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.values().next_back()
+    }
+
+    /// Iterate over the retained version numbers in ascending order.
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.snapshots.keys().copied()
+    }
+
+    /// Stop retaining the given version.
+    ///
+    /// Returns `true` if the version was retained. Nodes that are not shared
+    /// with another version are freed once no clone of the snapshot remains.
+    pub fn release(&mut self, version: u64) -> bool {
+        self.snapshots.remove(&version).is_some()
+    }
+
+    /// Stop retaining every version older than the given one.
+    pub fn prune_before(&mut self, version: u64) {
+        self.snapshots = self.snapshots.split_off(&version);
+    }
+
+    /// Rebuild the path to `position` with the given leaf.
+    ///
+    /// `new_size` is either the current size (update) or one more (append).
+    fn replace_path(&mut self, position: u64, leaf: Arc<Node>, new_size: u64) {
+        let old_height = tree_height(self.size);
+        let new_height = tree_height(new_size);
+        let old_root = self.root.take();
+
+        // The old root only lines up with the new root if the height is unchanged;
+        // otherwise it becomes the leftmost node at its old height.
+        let start = if new_height == old_height {
+            old_root.as_ref()
+        } else {
+            None
+        };
+
+        let update = PathUpdate {
+            position,
+            leaf,
+            old_root: old_root.as_ref(),
+            old_height,
+            old_size: self.size,
+            new_size,
+            hasher: &self.hasher,
+        };
+        self.root = Some(update.build(new_height, 0, start));
+        self.size = new_size;
+    }
+}
+
+/// The parameters of a single path-copying update.
+struct PathUpdate<'a, H: Hasher> {
+    position: u64,
+    leaf: Arc<Node>,
+    old_root: Option<&'a Arc<Node>>,
+    old_height: u32,
+    old_size: u64,
+    new_size: u64,
+    hasher: &'a H,
+}
+
+impl<'a, H: Hasher> PathUpdate<'a, H> {
+    /// Build the node at (`level`, `index`) of the new tree.
+    ///
+    /// `old` is the node at the same position in the old tree, if it exists.
+    /// A node is reused when it does not cover the updated leaf and covers
+    /// the same leaves in both trees.
+    fn build(&self, level: u32, index: u64, old: Option<&'a Arc<Node>>) -> Arc<Node> {
+        let start = index << level;
+        let end = start + (1u64 << level);
+
+        if let Some(old) = old
+            && !(start..end).contains(&self.position)
+            && end.min(self.old_size) == end.min(self.new_size)
+        {
+            return Arc::clone(old);
+        }
+
+        if level == 0 {
+            return Arc::clone(&self.leaf);
+        }
+
+        let (old_left, old_right) = self.old_children(level, index, old);
+        let left = self.build(level - 1, 2 * index, old_left);
+        let right = if ((2 * index + 1) << (level - 1)) < self.new_size {
+            self.build(level - 1, 2 * index + 1, old_right)
+        } else {
+            // Odd number of nodes: duplicate the last node
+            Arc::clone(&left)
+        };
+        Arc::new(Node::internal(left, right, self.hasher))
+    }
+
+    /// Find the old nodes at the positions of the children of (`level`, `index`).
+    fn old_children(
+        &self,
+        level: u32,
+        index: u64,
+        old: Option<&'a Arc<Node>>,
+    ) -> (Option<&'a Arc<Node>>, Option<&'a Arc<Node>>) {
+        match old.map(|node| node.as_ref()) {
+            Some(Node::Internal(internal)) => {
+                let right_start = (2 * index + 1) << (level - 1);
+                let right = (right_start < self.old_size).then(|| internal.right_arc());
+                (Some(internal.left_arc()), right)
+            }
+            // The old root becomes the leftmost child once the tree grows taller
+            _ if level - 1 == self.old_height && index == 0 => (self.old_root, None),
+            _ => (None, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{HashId, Sha256Hasher, SimpleHasher};
+    use crate::merkle::simple_tree::SimpleMerkleTree;
+
+    fn persistent_with(count: u8) -> PersistentMerkleTree<Sha256Hasher> {
+        let mut tree = PersistentMerkleTree::new(Sha256Hasher::new());
+        for i in 0..count {
+            tree.add_leaf(&[i]).unwrap();
+        }
+        tree
+    }
+
+    fn simple_with(data: &[Vec<u8>]) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for leaf in data {
+            tree.add_leaf(leaf).unwrap();
+        }
+        tree
+    }
+
+    // =========================================================================
+    // Consistency with SimpleMerkleTree
+    // =========================================================================
+
+    #[test]
+    fn test_roots_match_simple_tree() {
+        let mut tree = PersistentMerkleTree::new(Sha256Hasher::new());
+        let mut data = Vec::new();
+
+        for i in 0..20u8 {
+            tree.add_leaf(&[i]).unwrap();
+            data.push(vec![i]);
+            assert_eq!(
+                tree.get_root(),
+                simple_with(&data).get_root(),
+                "Root mismatch for {} leaves",
+                i + 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_proofs_match_simple_tree() {
+        let tree = persistent_with(11);
+        let simple = simple_with(&(0..11u8).map(|i| vec![i]).collect::<Vec<_>>());
+
+        for i in 0..11 {
+            assert_eq!(tree.prove(i).unwrap(), simple.prove(i).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_update_matches_simple_tree() {
+        let mut data: Vec<Vec<u8>> = (0..7u8).map(|i| vec![i]).collect();
+        let mut tree = persistent_with(7);
+
+        for index in [0usize, 3, 6] {
+            tree.update_leaf(index as u64, b"updated").unwrap();
+            data[index] = b"updated".to_vec();
+            assert_eq!(tree.get_root(), simple_with(&data).get_root());
+        }
+    }
+
+    // =========================================================================
+    // Basic Tree Tests
+    // =========================================================================
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = PersistentMerkleTree::new(SimpleHasher::new());
+        assert_eq!(tree.get_size(), 0);
+        assert!(tree.get_root().is_none());
+        assert!(tree.get_data(0).is_none());
+        assert!(tree.prove(0).is_err());
+    }
+
+    #[test]
+    fn test_empty_data_rejected() {
+        let mut tree = persistent_with(1);
+        assert_eq!(tree.add_leaf(b""), Err(MerkleTreeError::EmptyInput));
+        assert_eq!(tree.update_leaf(0, b""), Err(MerkleTreeError::EmptyInput));
+    }
+
+    #[test]
+    fn test_update_invalid_index() {
+        let mut tree = persistent_with(2);
+        assert_eq!(
+            tree.update_leaf(2, b"x"),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 2,
+                tree_size: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_data() {
+        let mut tree = persistent_with(5);
+        tree.update_leaf(2, b"two").unwrap();
+        assert_eq!(tree.get_data(0), Some([0u8].as_slice()));
+        assert_eq!(tree.get_data(2), Some(b"two".as_slice()));
+        assert_eq!(tree.get_data(4), Some([4u8].as_slice()));
+        assert!(tree.get_data(5).is_none());
+    }
+
+    #[test]
+    fn test_verify_round_trip() {
+        let tree = persistent_with(6);
+        let root = tree.get_root_bytes().unwrap();
+        for i in 0..6u8 {
+            let proof = tree.prove(i as u64).unwrap();
+            assert!(tree.verify(&proof, &[i], &root));
+        }
+    }
+
+    // =========================================================================
+    // Snapshot Tests
+    // =========================================================================
+
+    #[test]
+    fn test_snapshots_keep_old_versions() {
+        let mut tree = persistent_with(3);
+        let v0 = tree.commit();
+
+        tree.add_leaf(b"new").unwrap();
+        tree.update_leaf(0, b"changed").unwrap();
+        let v1 = tree.commit();
+
+        assert_eq!(v0.get_size(), 3);
+        assert_eq!(v0.get_data(0), Some([0u8].as_slice()));
+        assert_eq!(v1.get_size(), 4);
+        assert_eq!(v1.get_data(0), Some(b"changed".as_slice()));
+        assert_ne!(v0.get_root(), v1.get_root());
+    }
+
+    #[test]
+    fn test_prove_against_old_version() {
+        let mut tree = persistent_with(5);
+        let v0 = tree.commit();
+        let old_root = v0.get_root_bytes().unwrap();
+
+        tree.update_leaf(1, b"changed").unwrap();
+        tree.add_leaf(b"more").unwrap();
+
+        let proof = tree.snapshot(0).unwrap().prove(1).unwrap();
+        assert_eq!(
+            verify_proof(&[1u8], &proof, &old_root, &Sha256Hasher::new()),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_proof(&[1u8], &proof, &old_root, &SimpleHasher::new()),
+            Err(MerkleTreeError::HasherMismatch {
+                proof: HashId::SHA2_256,
+                verifier: HashId::SIMPLE
+            })
+        );
+        assert!(!tree.verify(&proof, &[1u8], &tree.get_root_bytes().unwrap()));
+    }
+
+    #[test]
+    fn test_snapshot_proofs_match_tree() {
+        let mut tree = persistent_with(7);
+        let snapshot = tree.commit();
+        for i in 0..7 {
+            assert_eq!(snapshot.prove(i), tree.prove(i), "index {}", i);
+        }
+        assert_eq!(snapshot.get_root_multihash(), tree.get_root_multihash());
+    }
+
+    #[test]
+    fn test_versions_increment() {
+        let mut tree = persistent_with(1);
+        assert_eq!(tree.commit().version(), 0);
+        assert_eq!(tree.commit().version(), 1);
+        assert_eq!(tree.versions().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(tree.latest().unwrap().version(), 1);
+    }
+
+    #[test]
+    fn test_release_and_prune() {
+        let mut tree = persistent_with(1);
+        for _ in 0..4 {
+            tree.commit();
+        }
+
+        assert!(tree.release(1));
+        assert!(!tree.release(1));
+        assert!(tree.snapshot(1).is_none());
+
+        tree.prune_before(3);
+        assert_eq!(tree.versions().collect::<Vec<_>>(), vec![3]);
+    }
+
+    // =========================================================================
+    // Structural Sharing Tests
+    // =========================================================================
+
+    #[test]
+    fn test_append_shares_full_left_subtree() {
+        let mut tree = persistent_with(4);
+        let v0 = tree.commit();
+        tree.add_leaf(b"fifth").unwrap();
+        let v1 = tree.commit();
+
+        // The old root covered leaves 0..4 and becomes the new root's left child
+        let Node::Internal(new_root) = v1.root_node().unwrap().as_ref() else {
+            panic!("expected an internal root");
+        };
+        assert!(Arc::ptr_eq(new_root.left_arc(), v0.root_node().unwrap()));
+    }
+
+    #[test]
+    fn test_update_shares_untouched_sibling() {
+        let mut tree = persistent_with(4);
+        let v0 = tree.commit();
+        tree.update_leaf(0, b"changed").unwrap();
+        let v1 = tree.commit();
+
+        let (Node::Internal(old_root), Node::Internal(new_root)) = (
+            v0.root_node().unwrap().as_ref(),
+            v1.root_node().unwrap().as_ref(),
+        ) else {
+            panic!("expected internal roots");
+        };
+        assert!(Arc::ptr_eq(old_root.right_arc(), new_root.right_arc()));
+        assert!(!Arc::ptr_eq(old_root.left_arc(), new_root.left_arc()));
+    }
+
+    #[test]
+    fn test_released_version_frees_unshared_nodes() {
+        let mut tree = persistent_with(2);
+        tree.commit();
+        let old_root = Arc::clone(tree.snapshot(0).unwrap().root_node().unwrap());
+        tree.update_leaf(1, b"changed").unwrap();
+
+        // Held by the retained snapshot and our local handle
+        assert_eq!(Arc::strong_count(&old_root), 2);
+        tree.release(0);
+        assert_eq!(Arc::strong_count(&old_root), 1);
+    }
+}
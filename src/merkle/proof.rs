@@ -1,3 +1,9 @@
+use crate::hasher::HashId;
+use crate::merkle::Digest32;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::codec::{Reader, put_varint, read_varint};
+use crate::merkle::snapshot::tree_height;
+
 /// A Merkle proof for verifying membership of a leaf in a Merkle tree.
 ///
 /// The proof contains the leaf index and an ordered list of sibling hashes
@@ -26,14 +32,41 @@
 /// assert_eq!(proof.index(), 3);
 /// assert_eq!(proof.siblings().len(), 2);
 /// ```
+///
+/// # Wire Format
+///
+/// `to_bytes` produces a compact, versioned encoding (integers are LEB128
+/// varints):
+///
+/// ```text
+/// version: u8 (= 1)
+/// hash id: varint multicodec code, 0 if not recorded
+/// index: varint
+/// tree size: varint, 0 if not recorded
+/// sibling count: varint
+/// siblings: count * 32 bytes
+/// ```
+///
+/// # Serde
+///
+/// With the `serde` feature, proofs serialize as a struct whose siblings
+/// are hex strings in human-readable formats such as JSON, and raw bytes
+/// in binary formats such as bincode or CBOR.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Proof {
     /// The index of the leaf in the tree (0-based).
     /// Using u64 for platform-independent 64-bit support (up to 2^64 leaves).
     index: u64,
     /// Sibling hashes ordered from leaf level toward the root.
     /// Each hash is exactly 32 bytes.
-    siblings: Vec<[u8; 32]>,
+    siblings: Vec<Digest32>,
+    /// The hash algorithm of the tree that produced the proof, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    hash_id: Option<HashId>,
+    /// The number of leaves in the tree that produced the proof, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    tree_size: Option<u64>,
 }
 
 impl Proof {
@@ -53,7 +86,27 @@ impl Proof {
     /// let proof = Proof::new(0, siblings);
     /// ```
     pub fn new(index: u64, siblings: Vec<[u8; 32]>) -> Self {
-        Proof { index, siblings }
+        Proof {
+            index,
+            siblings: siblings.into_iter().map(Digest32::from).collect(),
+            hash_id: None,
+            tree_size: None,
+        }
+    }
+
+    /// Record the hash algorithm of the tree the proof belongs to.
+    ///
+    /// Trees set this in `prove` from `Hasher::hash_id`, so that
+    /// `verify_proof` can reject a proof checked with the wrong hasher.
+    pub fn with_hash_id(mut self, hash_id: Option<HashId>) -> Self {
+        self.hash_id = hash_id;
+        self
+    }
+
+    /// Record the number of leaves in the tree the proof belongs to.
+    pub fn with_tree_size(mut self, tree_size: u64) -> Self {
+        self.tree_size = Some(tree_size);
+        self
     }
 
     /// Returns the index of the leaf this proof is for.
@@ -62,9 +115,94 @@ impl Proof {
     }
 
     /// Returns the sibling hashes ordered from leaf level toward the root.
-    pub fn siblings(&self) -> &[[u8; 32]] {
+    pub fn siblings(&self) -> &[Digest32] {
         &self.siblings
     }
+
+    /// Returns the hash algorithm of the tree that produced the proof, or
+    /// `None` if it was not recorded.
+    pub fn hash_id(&self) -> Option<HashId> {
+        self.hash_id
+    }
+
+    /// Returns the number of leaves in the tree that produced the proof, or
+    /// `None` if it was not recorded.
+    pub fn tree_size(&self) -> Option<u64> {
+        self.tree_size
+    }
+
+    /// The wire format version written by `to_bytes`.
+    pub const FORMAT_VERSION: u8 = 1;
+
+    /// Encode the proof in the versioned wire format.
+    ///
+    /// A `HashId` with code 0 cannot be represented and is written as
+    /// "not recorded".
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + 32 * self.siblings.len());
+        out.push(Self::FORMAT_VERSION);
+        put_varint(&mut out, self.hash_id.map_or(0, |id| id.code()));
+        put_varint(&mut out, self.index);
+        put_varint(&mut out, self.tree_size.unwrap_or(0));
+        put_varint(&mut out, self.siblings.len() as u64);
+        for sibling in &self.siblings {
+            out.extend_from_slice(sibling.as_bytes());
+        }
+        out
+    }
+
+    /// Decode a proof from the wire format.
+    ///
+    /// Decoding is strict. Returns `MerkleTreeError::UnsupportedVersion` for
+    /// an unknown version, `TruncatedInput` if the input ends early,
+    /// `InvalidEncoding` for non-canonical varints, `InvalidIndex` if the
+    /// index is outside the recorded tree size, `ExcessSiblings` if there
+    /// are more siblings than the tree has levels, and `TrailingBytes` if
+    /// input remains after the proof.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleTreeError> {
+        let mut reader = Reader::new(bytes);
+        let version = reader.u8().ok_or(MerkleTreeError::TruncatedInput)?;
+        if version != Self::FORMAT_VERSION {
+            return Err(MerkleTreeError::UnsupportedVersion { version });
+        }
+
+        let hash_id = match read_varint(&mut reader)? {
+            0 => None,
+            code => Some(HashId::new(code)),
+        };
+        let index = read_varint(&mut reader)?;
+        let tree_size = match read_varint(&mut reader)? {
+            0 => None,
+            size => Some(size),
+        };
+        if let Some(tree_size) = tree_size
+            && index >= tree_size
+        {
+            return Err(MerkleTreeError::InvalidIndex { index, tree_size });
+        }
+
+        // Without a recorded size, a u64 index still bounds the depth
+        let max = tree_size.map_or(u64::BITS, tree_height) as u64;
+        let count = read_varint(&mut reader)?;
+        if count > max {
+            return Err(MerkleTreeError::ExcessSiblings { count, max });
+        }
+        let siblings = (0..count)
+            .map(|_| reader.hash().ok_or(MerkleTreeError::TruncatedInput))
+            .collect::<Result<_, _>>()?;
+
+        if !reader.is_empty() {
+            return Err(MerkleTreeError::TrailingBytes {
+                count: reader.rest().len(),
+            });
+        }
+
+        let proof = Proof::new(index, siblings).with_hash_id(hash_id);
+        Ok(match tree_size {
+            Some(tree_size) => proof.with_tree_size(tree_size),
+            None => proof,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -145,4 +283,218 @@ mod tests {
         // Proof is still valid after siblings vector goes out of scope
         assert_eq!(proof.siblings().len(), 2);
     }
+
+    #[test]
+    fn test_proof_hash_id() {
+        let proof = Proof::new(0, vec![[1u8; 32]]);
+        assert_eq!(proof.hash_id(), None);
+
+        let tagged = proof.clone().with_hash_id(Some(HashId::SHA2_256));
+        assert_eq!(tagged.hash_id(), Some(HashId::SHA2_256));
+        assert_ne!(tagged, proof);
+    }
+
+    // =========================================================================
+    // Wire Format Tests
+    // =========================================================================
+
+    fn sample() -> Proof {
+        Proof::new(2, vec![[0x11; 32], [0x22; 32]])
+            .with_hash_id(Some(HashId::SHA2_256))
+            .with_tree_size(3)
+    }
+
+    #[test]
+    fn test_golden_encoding() {
+        let expected = format!("0112020302{}{}", "11".repeat(32), "22".repeat(32));
+        assert_eq!(crate::bytes_to_hex(&sample().to_bytes()), expected);
+    }
+
+    #[test]
+    fn test_golden_encoding_without_metadata() {
+        // Multi-byte varint index, no hash id, no tree size, no siblings
+        let proof = Proof::new(300, vec![]);
+        assert_eq!(proof.to_bytes(), vec![0x01, 0x00, 0xac, 0x02, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_golden_tree_proof() {
+        use crate::hasher::Sha256Hasher;
+        use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+        }
+        let bytes = tree.prove(2).unwrap().to_bytes();
+        assert_eq!(crate::bytes_to_hex(&bytes), GOLDEN_TREE_PROOF);
+        assert_eq!(Proof::from_bytes(&bytes), tree.prove(2));
+    }
+
+    /// SHA-256 tree of "a", "b", "c", proof for "c": version 1, sha2-256,
+    /// index 2, size 3, then H(0x00 || "c") and H(0x01 || H(0x00 || "a") || H(0x00 || "b")).
+    const GOLDEN_TREE_PROOF: &str = concat!(
+        "0112020302",
+        "597fcb31282d34654c200d3418fca5705c648ebf326ec73d8ddef11841f876d8",
+        "b137985ff484fb600db93107c77b0365c80d78f5b429ded0fd97361d077999eb",
+    );
+
+    #[test]
+    fn test_round_trip() {
+        for proof in [
+            sample(),
+            Proof::new(300, vec![]),
+            Proof::new(0, vec![[7; 32]]),
+        ] {
+            assert_eq!(Proof::from_bytes(&proof.to_bytes()), Ok(proof));
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_version() {
+        let mut bytes = sample().to_bytes();
+        bytes[0] = 2;
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::UnsupportedVersion { version: 2 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_input() {
+        let bytes = sample().to_bytes();
+        for len in 0..bytes.len() {
+            assert_eq!(
+                Proof::from_bytes(&bytes[..len]),
+                Err(MerkleTreeError::TruncatedInput),
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_trailing_bytes() {
+        let mut bytes = sample().to_bytes();
+        bytes.extend_from_slice(&[0, 0]);
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::TrailingBytes { count: 2 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_excess_siblings() {
+        // Three siblings for a tree of three leaves (height 2)
+        let mut bytes = vec![0x01, 0x12, 0x02, 0x03, 0x03];
+        bytes.extend_from_slice(&[0u8; 96]);
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::ExcessSiblings { count: 3, max: 2 })
+        );
+
+        // Without a tree size, no more than 64 levels
+        let bytes = [0x01, 0x00, 0x00, 0x00, 0x41];
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::ExcessSiblings { count: 65, max: 64 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_index_outside_tree() {
+        let bytes = [0x01, 0x12, 0x03, 0x03, 0x00];
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 3,
+                tree_size: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_non_canonical_varint() {
+        // Index 0 written as two bytes
+        let bytes = [0x01, 0x12, 0x80, 0x00, 0x00, 0x00];
+        assert_eq!(
+            Proof::from_bytes(&bytes),
+            Err(MerkleTreeError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_decode_huge_tree_size() {
+        let mut bytes = vec![0x01, 0x00, 0x00];
+        bytes.extend_from_slice(&[0xff; 9]);
+        bytes.push(0x01);
+        bytes.push(0x00);
+        let proof = Proof::from_bytes(&bytes).unwrap();
+        assert_eq!(proof.tree_size(), Some(u64::MAX));
+    }
+
+    // =========================================================================
+    // Serde Tests
+    // =========================================================================
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_uses_hex() {
+        let proof = Proof::new(2, vec![[0xab; 32], [0x01; 32]])
+            .with_hash_id(Some(HashId::SHA2_256))
+            .with_tree_size(3);
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "index": 2,
+                "siblings": ["ab".repeat(32), "01".repeat(32)],
+                "hash_id": 0x12,
+                "tree_size": 3,
+            })
+        );
+        assert_eq!(serde_json::from_value::<Proof>(json).unwrap(), proof);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_optional_fields() {
+        let json = format!("{{\"index\":0,\"siblings\":[\"{}\"]}}", "00".repeat(32));
+        let proof: Proof = serde_json::from_str(&json).unwrap();
+        assert_eq!(proof, Proof::new(0, vec![[0u8; 32]]));
+
+        let short = "{\"index\":0,\"siblings\":[\"00\"]}";
+        assert!(serde_json::from_str::<Proof>(short).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_binary_uses_raw_bytes() {
+        let proof = Proof::new(1, vec![[7u8; 32]; 3]).with_tree_size(8);
+        let bytes = bincode::serialize(&proof).unwrap();
+        // index, sibling count, three length-prefixed hashes, two options
+        assert_eq!(bytes.len(), 8 + 8 + 3 * (8 + 32) + 1 + (1 + 8));
+        assert_eq!(bincode::deserialize::<Proof>(&bytes).unwrap(), proof);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_error() {
+        let err = MerkleTreeError::HasherMismatch {
+            proof: HashId::SIMPLE,
+            verifier: HashId::SHA2_256,
+        };
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(
+            json,
+            "{\"HasherMismatch\":{\"proof\":3145728,\"verifier\":18}}"
+        );
+        assert_eq!(serde_json::from_str::<MerkleTreeError>(&json).unwrap(), err);
+
+        let bytes = bincode::serialize(&MerkleTreeError::RootMismatch).unwrap();
+        assert_eq!(
+            bincode::deserialize::<MerkleTreeError>(&bytes).unwrap(),
+            MerkleTreeError::RootMismatch
+        );
+    }
 }
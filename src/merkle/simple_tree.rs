@@ -1,13 +1,17 @@
+use std::cmp::Ordering;
 use std::sync::Arc;
 
 use crate::bytes_to_hex;
-use crate::hasher::Hasher;
+use crate::hasher::{DynHasher, Hasher, HasherRegistry};
 use crate::merkle::MerkleTree;
 use crate::merkle::MerkleTreeError;
+use crate::merkle::diff::{TreeDiff, diff_trees};
 use crate::merkle::hash::Hash;
+use crate::merkle::internal_node::InternalNode;
 use crate::merkle::leaf_node::LeafNode;
 use crate::merkle::node::Node;
 use crate::merkle::proof::Proof;
+use crate::merkle::snapshot::{TreeSnapshot, tree_height};
 
 /// A Merkle tree implementation.
 pub struct SimpleMerkleTree<H: Hasher> {
@@ -16,6 +20,27 @@ pub struct SimpleMerkleTree<H: Hasher> {
     hasher: H,
 }
 
+/// A `SimpleMerkleTree` whose hasher is chosen at runtime.
+///
+/// ```
+/// use merkle_trees::{DynMerkleTree, MerkleTree};
+///
+/// let mut tree = DynMerkleTree::from_hasher_name("sha256").unwrap();
+/// tree.add_leaf(b"hello").unwrap();
+/// assert!(tree.get_root().is_some());
+/// ```
+pub type DynMerkleTree = SimpleMerkleTree<DynHasher>;
+
+impl DynMerkleTree {
+    /// Create an empty tree using the built-in hasher called `name`.
+    ///
+    /// Returns `MerkleTreeError::UnknownHasher` if there is no such hasher.
+    /// Use `HasherRegistry::get` to pick from a registry with custom entries.
+    pub fn from_hasher_name(name: &str) -> Result<Self, MerkleTreeError> {
+        Ok(Self::new(HasherRegistry::new().get(name)?))
+    }
+}
+
 // Trait implementation (public interface) for SimpleMerkleTree
 impl<H: Hasher> MerkleTree<H> for SimpleMerkleTree<H> {
     fn add_leaf(&mut self, data: &[u8]) -> Result<(), MerkleTreeError> {
@@ -42,6 +67,12 @@ impl<H: Hasher> MerkleTree<H> for SimpleMerkleTree<H> {
         })
     }
 
+    fn get_root_multihash(&self) -> Option<String> {
+        let hash_id = self.hasher.hash_id()?;
+        let root = self.get_root_bytes()?;
+        Some(bytes_to_hex(&hash_id.multihash(&root)))
+    }
+
     fn get_data(&self, index: u64) -> Option<&[u8]> {
         self.leaves.get(index as usize).map(|leaf| leaf.data())
     }
@@ -59,18 +90,20 @@ impl<H: Hasher> MerkleTree<H> for SimpleMerkleTree<H> {
         }
 
         // Single-leaf tree: empty sibling list
-        if tree_size == 1 {
-            return Ok(Proof::new(index, vec![]));
-        }
-
-        // Build level-by-level hash arrays and collect siblings
-        let siblings = self.collect_siblings(index);
+        let siblings = if tree_size == 1 {
+            vec![]
+        } else {
+            // Build level-by-level hash arrays and collect siblings
+            self.collect_siblings(index)
+        };
 
-        Ok(Proof::new(index, siblings))
+        Ok(Proof::new(index, siblings)
+            .with_hash_id(self.hasher.hash_id())
+            .with_tree_size(tree_size))
     }
 
     fn verify(&self, proof: &Proof, leaf_data: &[u8], expected_root: &[u8; 32]) -> bool {
-        verify_proof(leaf_data, proof, expected_root, &self.hasher)
+        verify_proof(leaf_data, proof, expected_root, &self.hasher).unwrap_or(false)
     }
 }
 
@@ -84,6 +117,182 @@ impl<H: Hasher> SimpleMerkleTree<H> {
         }
     }
 
+    /// Find the leaf indices at which `other` differs from this tree.
+    ///
+    /// Subtrees with equal hashes are skipped, so only the paths leading to
+    /// differing leaves are visited. See `TreeDiff` for how indices are
+    /// reported.
+    pub fn diff(&self, other: &SimpleMerkleTree<H>) -> TreeDiff {
+        diff_trees(
+            self.root.as_ref(),
+            self.leaves.len() as u64,
+            other.root.as_ref(),
+            other.leaves.len() as u64,
+        )
+    }
+
+    /// Copy the leaves and root into a self-contained `TreeSnapshot`.
+    pub fn to_snapshot(&self) -> TreeSnapshot {
+        TreeSnapshot::new(
+            self.hasher.hash_id(),
+            self.get_root_digest(),
+            self.leaves
+                .iter()
+                .map(|leaf| leaf.data().to_vec())
+                .collect(),
+        )
+    }
+
+    /// Rebuild a tree from a `TreeSnapshot`.
+    ///
+    /// Returns `MerkleTreeError::EmptyInput` if a leaf is empty, and
+    /// `MerkleTreeError::RootMismatch` if the rebuilt root differs from the
+    /// snapshot's, for example because it was taken with another hasher.
+    pub fn from_snapshot(snapshot: TreeSnapshot, hasher: H) -> Result<Self, MerkleTreeError> {
+        let root = snapshot.root();
+        let updates = snapshot.into_leaves().into_iter().enumerate().collect();
+        let mut tree = Self::new(hasher);
+        tree.apply_leaves(updates, 0)?;
+        if tree.get_root_digest() != root {
+            return Err(MerkleTreeError::RootMismatch);
+        }
+        Ok(tree)
+    }
+
+    /// Get the leaves of the tree in order.
+    pub(crate) fn leaves(&self) -> &[LeafNode] {
+        &self.leaves
+    }
+
+    /// Get the hasher used by this tree.
+    pub(crate) fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Insert a leaf at the given position, shifting later leaves right.
+    ///
+    /// Used by ordered trees that need to keep leaves in a specific order.
+    pub(crate) fn insert_leaf_at(&mut self, index: usize, data: &[u8]) {
+        let leaf = LeafNode::new(data.to_vec(), &self.hasher);
+        self.leaves.insert(index, leaf);
+        self.rebuild_tree();
+    }
+
+    /// Remove the leaf at the given position, shifting later leaves left.
+    pub(crate) fn remove_leaf_at(&mut self, index: usize) {
+        self.leaves.remove(index);
+        self.rebuild_tree();
+    }
+
+    /// Overwrite leaves and resize the tree, rebuilding it once.
+    ///
+    /// The tree is first truncated to `size` leaves. Each update then
+    /// replaces the leaf at its index, or appends it when the index equals
+    /// the current length.
+    ///
+    /// The updates are checked before the tree is touched: returns
+    /// `MerkleTreeError::InvalidIndex` if an update would leave a gap (its
+    /// index is past the length at that point), and `EmptyInput` if a leaf
+    /// is empty.
+    ///
+    /// The new leaves are hashed in one `Hasher::hash_leaf_many` batch.
+    pub(crate) fn apply_leaves(
+        &mut self,
+        updates: Vec<(usize, Vec<u8>)>,
+        size: usize,
+    ) -> Result<(), MerkleTreeError> {
+        let mut len = self.leaves.len().min(size);
+        for (index, data) in &updates {
+            if data.is_empty() {
+                return Err(MerkleTreeError::EmptyInput);
+            }
+            if *index > len {
+                return Err(MerkleTreeError::InvalidIndex {
+                    index: *index as u64,
+                    tree_size: len as u64,
+                });
+            }
+            if *index == len {
+                len += 1;
+            }
+        }
+
+        self.leaves.truncate(size);
+        let data: Vec<&[u8]> = updates.iter().map(|(_, data)| data.as_slice()).collect();
+        let hashes = self.hasher.hash_leaf_many(&data);
+        for ((index, data), hash) in updates.into_iter().zip(hashes) {
+            let leaf = LeafNode::with_hash(data, hash);
+            if index < self.leaves.len() {
+                self.leaves[index] = leaf;
+            } else {
+                self.leaves.push(leaf);
+            }
+        }
+        self.rebuild_tree();
+        Ok(())
+    }
+
+    /// Apply updates like `apply_leaves`, keeping them only if the new root
+    /// is `root`.
+    ///
+    /// The candidate tree is built from a copy of the leaves and swapped in
+    /// once its root matches, so on any error the tree is left unchanged.
+    /// Returns `MerkleTreeError::RootMismatch` if the roots differ.
+    pub(crate) fn apply_leaves_if_root(
+        &mut self,
+        updates: Vec<(usize, Vec<u8>)>,
+        size: usize,
+        root: Option<[u8; 32]>,
+    ) -> Result<(), MerkleTreeError> {
+        let kept = self.leaves.len().min(size);
+        let mut candidate = SimpleMerkleTree {
+            leaves: self.leaves[..kept].to_vec(),
+            root: None,
+            hasher: &self.hasher,
+        };
+        candidate.apply_leaves(updates, size)?;
+        if candidate.get_root_bytes() != root {
+            return Err(MerkleTreeError::RootMismatch);
+        }
+        let SimpleMerkleTree { leaves, root, .. } = candidate;
+        self.leaves = leaves;
+        self.root = root;
+        Ok(())
+    }
+
+    /// Compute the node hashes of every level, from the leaves to the root.
+    ///
+    /// Level `l` holds one hash per node, so the node at index `i` covers
+    /// the leaves `i << l` up to `(i + 1) << l`.
+    pub(crate) fn level_hashes(&self) -> Vec<Vec<[u8; 32]>> {
+        let mut current_level: Vec<[u8; 32]> = self
+            .leaves
+            .iter()
+            .map(|leaf| {
+                let mut arr = [0u8; 32];
+                arr.copy_from_slice(leaf.hash());
+                arr
+            })
+            .collect();
+        if current_level.is_empty() {
+            return Vec::new();
+        }
+
+        let mut levels = Vec::new();
+        while current_level.len() > 1 {
+            let next_level = current_level
+                .chunks(2)
+                .map(|chunk| {
+                    let right = chunk.get(1).unwrap_or(&chunk[0]);
+                    self.hasher.hash_internal(&chunk[0], right)
+                })
+                .collect();
+            levels.push(std::mem::replace(&mut current_level, next_level));
+        }
+        levels.push(current_level);
+        levels
+    }
+
     /// Rebuild the tree from the current leaves.
     fn rebuild_tree(&mut self) {
         // Wrap leaves in Arc
@@ -94,18 +303,28 @@ impl<H: Hasher> SimpleMerkleTree<H> {
             .collect();
 
         while current_level.len() > 1 {
-            let mut next_level = Vec::with_capacity(current_level.len().div_ceil(2));
-
-            for chunk in current_level.chunks(2) {
-                let left = Arc::clone(&chunk[0]);
-                let right = if chunk.len() > 1 {
-                    Arc::clone(&chunk[1])
-                } else {
-                    // Just clone the Arc pointer - no deep copy!
-                    Arc::clone(&chunk[0])
-                };
-                next_level.push(Arc::new(Node::internal(left, right, &self.hasher)));
-            }
+            // The last node is paired with itself on odd levels. Cloning the
+            // Arc pointer is cheap - no deep copy!
+            let pairs: Vec<(&Arc<Node>, &Arc<Node>)> = current_level
+                .chunks(2)
+                .map(|chunk| (&chunk[0], chunk.last().unwrap_or(&chunk[0])))
+                .collect();
+
+            // Hash the whole level at once so batching hashers can use it
+            let child_hashes: Vec<(&[u8], &[u8])> = pairs
+                .iter()
+                .map(|(left, right)| (left.hash(), right.hash()))
+                .collect();
+            let hashes = self.hasher.hash_internal_many(&child_hashes);
+
+            let next_level = pairs
+                .iter()
+                .zip(hashes)
+                .map(|((left, right), hash)| {
+                    let node = InternalNode::with_hash(Arc::clone(left), Arc::clone(right), hash);
+                    Arc::new(Node::Internal(node))
+                })
+                .collect();
 
             current_level = next_level;
         }
@@ -162,7 +381,7 @@ impl<H: Hasher> SimpleMerkleTree<H> {
                 } else {
                     &chunk[0]
                 };
-                next_level.push(compute_internal_hash(left, right, &self.hasher));
+                next_level.push(self.hasher.hash_internal(left, right));
             }
 
             // Move to parent index
@@ -174,23 +393,6 @@ impl<H: Hasher> SimpleMerkleTree<H> {
     }
 }
 
-/// Compute the hash of an internal node using domain separation (0x01 prefix).
-fn compute_internal_hash<H: Hasher>(left: &[u8; 32], right: &[u8; 32], hasher: &H) -> [u8; 32] {
-    let mut to_hash = Vec::with_capacity(1 + 32 + 32);
-    to_hash.push(0x01);
-    to_hash.extend_from_slice(left);
-    to_hash.extend_from_slice(right);
-    hasher.hash_bytes(&to_hash)
-}
-
-/// Compute the leaf hash using domain separation (0x00 prefix).
-fn compute_leaf_hash<H: Hasher>(data: &[u8], hasher: &H) -> [u8; 32] {
-    let mut to_hash = Vec::with_capacity(1 + data.len());
-    to_hash.push(0x00);
-    to_hash.extend_from_slice(data);
-    hasher.hash_bytes(&to_hash)
-}
-
 /// Verify a Merkle proof without requiring access to the original tree.
 ///
 /// This standalone function verifies that a leaf belongs to a Merkle tree
@@ -206,12 +408,17 @@ fn compute_leaf_hash<H: Hasher>(data: &[u8], hasher: &H) -> [u8; 32] {
 ///
 /// # Returns
 ///
-/// `true` if the proof is valid (computed root matches expected root),
-/// `false` otherwise.
+/// `Ok(true)` if the proof is valid (computed root matches expected root),
+/// `Ok(false)` otherwise. Returns `MerkleTreeError::HasherMismatch` if the
+/// proof and the hasher both carry a `HashId` and they differ, since the
+/// proof can then never verify; proofs or hashers without an identifier
+/// are checked by recomputing the root only.
 ///
 /// # Security Properties
 ///
-/// - Uses domain separation (0x00 for leaf, 0x01 for internal nodes)
+/// - Uses the hasher's domain separation (`Hasher::hash_leaf` and
+///   `hash_internal`, by default `HashScheme::STANDARD` with 0x00 for
+///   leaves and 0x01 for internal nodes)
 /// - Uses constant-time comparison for root hash to prevent timing attacks
 /// - Verification complexity is O(log n) hash operations
 ///
@@ -227,33 +434,83 @@ fn compute_leaf_hash<H: Hasher>(data: &[u8], hasher: &H) -> [u8; 32] {
 /// let leaf_data = b"test";
 /// let expected_root = [0u8; 32]; // placeholder
 ///
-/// let is_valid = verify_proof(leaf_data, &proof, &expected_root, &hasher);
+/// let is_valid = verify_proof(leaf_data, &proof, &expected_root, &hasher).unwrap();
 /// ```
 pub fn verify_proof<H: Hasher>(
     leaf_data: &[u8],
     proof: &Proof,
     expected_root: &[u8; 32],
     hasher: &H,
-) -> bool {
+) -> Result<bool, MerkleTreeError> {
     // Compute the leaf hash with domain separation
-    let mut current_hash = compute_leaf_hash(leaf_data, hasher);
+    verify_proof_from_hash(&hasher.hash_leaf(leaf_data), proof, expected_root, hasher)
+}
+
+/// Verify a Merkle proof for a leaf given by its hash rather than its data.
+///
+/// Use this with trees built from precomputed leaf hashes, such as
+/// `StoredMerkleTree::add_leaf_hash`. `verify_proof(data, ..)` is the same
+/// as calling this with `hasher.hash_leaf(data)`.
+///
+/// Returns `MerkleTreeError::HasherMismatch` if the proof records a
+/// different hash algorithm than `hasher`. If the proof records its tree
+/// size, returns `InvalidIndex` for an index outside the tree, and
+/// `ExcessSiblings` or `MissingSiblings` if the sibling count does not
+/// match the tree's height.
+pub fn verify_proof_from_hash<H: Hasher>(
+    leaf_hash: &[u8; 32],
+    proof: &Proof,
+    expected_root: &[u8; 32],
+    hasher: &H,
+) -> Result<bool, MerkleTreeError> {
+    if let (Some(proof_id), Some(verifier_id)) = (proof.hash_id(), hasher.hash_id())
+        && proof_id != verifier_id
+    {
+        return Err(MerkleTreeError::HasherMismatch {
+            proof: proof_id,
+            verifier: verifier_id,
+        });
+    }
+    if let Some(tree_size) = proof.tree_size() {
+        check_proof_shape(proof, tree_size)?;
+    }
+
+    let mut current_hash = *leaf_hash;
     let mut index = proof.index();
 
     // Walk up the tree, combining with siblings
     for sibling in proof.siblings() {
         if index.is_multiple_of(2) {
             // Even index: current is left child, sibling is on right
-            current_hash = compute_internal_hash(&current_hash, sibling, hasher);
+            current_hash = hasher.hash_internal(&current_hash, sibling.as_bytes());
         } else {
             // Odd index: current is right child, sibling is on left
-            current_hash = compute_internal_hash(sibling, &current_hash, hasher);
+            current_hash = hasher.hash_internal(sibling.as_bytes(), &current_hash);
         }
         // Advance to parent level
         index /= 2;
     }
 
     // Constant-time comparison to prevent timing attacks
-    constant_time_compare(&current_hash, expected_root)
+    Ok(constant_time_compare(&current_hash, expected_root))
+}
+
+/// Check a proof's index and sibling count against its recorded tree size.
+fn check_proof_shape(proof: &Proof, tree_size: u64) -> Result<(), MerkleTreeError> {
+    let index = proof.index();
+    if index >= tree_size {
+        return Err(MerkleTreeError::InvalidIndex { index, tree_size });
+    }
+    let count = proof.siblings().len() as u64;
+    let expected = tree_height(tree_size) as u64;
+    match count.cmp(&expected) {
+        Ordering::Greater => Err(MerkleTreeError::ExcessSiblings {
+            count,
+            max: expected,
+        }),
+        Ordering::Less => Err(MerkleTreeError::MissingSiblings { count, expected }),
+        Ordering::Equal => Ok(()),
+    }
 }
 
 /// Constant-time byte array comparison to prevent timing attacks.
@@ -272,7 +529,7 @@ fn constant_time_compare(a: &[u8; 32], b: &[u8; 32]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::hasher::{Sha256Hasher, SimpleHasher};
+    use crate::hasher::{HashId, Sha256Hasher, SimpleHasher};
 
     // =========================================================================
     // Basic Tree Tests
@@ -429,7 +686,7 @@ mod tests {
         tree.add_leaf(b"b").unwrap();
         tree.add_leaf(b"c").unwrap();
 
-        let leaf_hash = compute_leaf_hash(b"c", &SimpleHasher::new());
+        let leaf_hash = SimpleHasher::new().hash_leaf(b"c");
         let proof = tree.prove(2).unwrap();
 
         // At level 0, leaf 2's sibling is itself (duplicated)
@@ -447,7 +704,7 @@ mod tests {
         let proof = tree.prove(4).unwrap();
         assert_eq!(proof.siblings().len(), 3); // ceil(log2(5)) = 3
 
-        let leaf_hash = compute_leaf_hash(b"e", &SimpleHasher::new());
+        let leaf_hash = SimpleHasher::new().hash_leaf(b"e");
         // Level 0 sibling is the leaf's own hash (duplicated)
         assert_eq!(proof.siblings()[0], leaf_hash);
     }
@@ -464,7 +721,7 @@ mod tests {
 
         // Verify the path is correct by checking siblings match
         // At each level, index 0 is even, so sibling is index 1
-        let leaf1_hash = compute_leaf_hash(&[1u8], &SimpleHasher::new());
+        let leaf1_hash = SimpleHasher::new().hash_leaf(&[1u8]);
         assert_eq!(proof.siblings()[0], leaf1_hash);
     }
 
@@ -479,7 +736,7 @@ mod tests {
         let proof = tree.prove(7).unwrap();
 
         // At level 0, index 7 is odd, sibling is index 6
-        let leaf6_hash = compute_leaf_hash(&[6u8], &SimpleHasher::new());
+        let leaf6_hash = SimpleHasher::new().hash_leaf(&[6u8]);
         assert_eq!(proof.siblings()[0], leaf6_hash);
     }
 
@@ -495,13 +752,13 @@ mod tests {
         let hasher = SimpleHasher::new();
 
         // First sibling should be leaf 1's hash (sibling at level 0)
-        let leaf1_hash = compute_leaf_hash(b"b", &hasher);
+        let leaf1_hash = hasher.hash_leaf(b"b");
         assert_eq!(proof.siblings()[0], leaf1_hash);
 
         // Second sibling should be H(leaf2, leaf3) - sibling at level 1
-        let leaf2_hash = compute_leaf_hash(b"c", &hasher);
-        let leaf3_hash = compute_leaf_hash(b"d", &hasher);
-        let level1_sibling = compute_internal_hash(&leaf2_hash, &leaf3_hash, &hasher);
+        let leaf2_hash = hasher.hash_leaf(b"c");
+        let leaf3_hash = hasher.hash_leaf(b"d");
+        let level1_sibling = hasher.hash_internal(&leaf2_hash, &leaf3_hash);
         assert_eq!(proof.siblings()[1], level1_sibling);
     }
 
@@ -642,7 +899,7 @@ mod tests {
         let proof = tree.prove(0).unwrap();
 
         // Create proof with wrong index but same siblings
-        let tampered_proof = Proof::new(1, proof.siblings().to_vec());
+        let tampered_proof = Proof::new(1, proof.siblings().iter().map(|&s| s.into()).collect());
 
         // Verifying "a" with index 1 should fail
         assert!(!tree.verify(&tampered_proof, b"a", &root));
@@ -659,7 +916,7 @@ mod tests {
         let proof = tree.prove(0).unwrap();
 
         // Remove a sibling
-        let truncated_siblings = vec![proof.siblings()[0]];
+        let truncated_siblings = vec![*proof.siblings()[0]];
         let truncated_proof = Proof::new(0, truncated_siblings);
 
         assert!(!tree.verify(&truncated_proof, b"a", &root));
@@ -675,7 +932,8 @@ mod tests {
         let proof = tree.prove(0).unwrap();
 
         // Add an extra sibling
-        let mut extended_siblings = proof.siblings().to_vec();
+        let mut extended_siblings: Vec<[u8; 32]> =
+            proof.siblings().iter().map(|&s| s.into()).collect();
         extended_siblings.push([0xffu8; 32]);
         let extended_proof = Proof::new(0, extended_siblings);
 
@@ -709,7 +967,7 @@ mod tests {
         let proof = tree.prove(0).unwrap();
 
         // Corrupt a single bit in the sibling
-        let mut corrupted_sibling = proof.siblings()[0];
+        let mut corrupted_sibling = *proof.siblings()[0];
         corrupted_sibling[0] ^= 0x01;
         let corrupted_proof = Proof::new(0, vec![corrupted_sibling]);
 
@@ -732,8 +990,8 @@ mod tests {
             data
         };
 
-        let leaf_hash = compute_leaf_hash(&fake_internal_data, &hasher);
-        let internal_hash = compute_internal_hash(&[0u8; 32], &[0u8; 32], &hasher);
+        let leaf_hash = hasher.hash_leaf(&fake_internal_data);
+        let internal_hash = hasher.hash_internal(&[0u8; 32], &[0u8; 32]);
 
         // Domain separation ensures these are different
         assert_ne!(leaf_hash, internal_hash);
@@ -761,18 +1019,18 @@ mod tests {
         let hasher = SimpleHasher::new();
 
         // For index 0, current should be on left, sibling on right
-        let left = compute_leaf_hash(b"left", &hasher);
-        let right = compute_leaf_hash(b"right", &hasher);
-        let expected_parent = compute_internal_hash(&left, &right, &hasher);
+        let left = hasher.hash_leaf(b"left");
+        let right = hasher.hash_leaf(b"right");
+        let expected_parent = hasher.hash_internal(&left, &right);
 
         let proof = Proof::new(0, vec![right]);
         let mut current = left;
         let index = proof.index();
 
-        if index % 2 == 0 {
-            current = compute_internal_hash(&current, &proof.siblings()[0], &hasher);
+        if index.is_multiple_of(2) {
+            current = hasher.hash_internal(&current, proof.siblings()[0].as_bytes());
         } else {
-            current = compute_internal_hash(&proof.siblings()[0], &current, &hasher);
+            current = hasher.hash_internal(proof.siblings()[0].as_bytes(), &current);
         }
 
         assert_eq!(current, expected_parent);
@@ -783,18 +1041,18 @@ mod tests {
         let hasher = SimpleHasher::new();
 
         // For index 1, current should be on right, sibling on left
-        let left = compute_leaf_hash(b"left", &hasher);
-        let right = compute_leaf_hash(b"right", &hasher);
-        let expected_parent = compute_internal_hash(&left, &right, &hasher);
+        let left = hasher.hash_leaf(b"left");
+        let right = hasher.hash_leaf(b"right");
+        let expected_parent = hasher.hash_internal(&left, &right);
 
         let proof = Proof::new(1, vec![left]);
         let mut current = right;
         let index = proof.index();
 
-        if index % 2 == 0 {
-            current = compute_internal_hash(&current, &proof.siblings()[0], &hasher);
+        if index.is_multiple_of(2) {
+            current = hasher.hash_internal(&current, proof.siblings()[0].as_bytes());
         } else {
-            current = compute_internal_hash(&proof.siblings()[0], &current, &hasher);
+            current = hasher.hash_internal(proof.siblings()[0].as_bytes(), &current);
         }
 
         assert_eq!(current, expected_parent);
@@ -853,14 +1111,17 @@ mod tests {
     #[test]
     fn test_verify_empty_siblings_only_for_single_leaf() {
         let hasher = Sha256Hasher::new();
-        let leaf_hash = compute_leaf_hash(b"only", &hasher);
+        let leaf_hash = hasher.hash_leaf(b"only");
 
         // Empty siblings is only valid when leaf hash equals root
         let proof = Proof::new(0, vec![]);
-        assert!(verify_proof(b"only", &proof, &leaf_hash, &hasher));
+        assert_eq!(verify_proof(b"only", &proof, &leaf_hash, &hasher), Ok(true));
 
         // Empty siblings with wrong root should fail
-        assert!(!verify_proof(b"only", &proof, &[0xffu8; 32], &hasher));
+        assert_eq!(
+            verify_proof(b"only", &proof, &[0xffu8; 32], &hasher),
+            Ok(false)
+        );
     }
 
     #[test]
@@ -916,6 +1177,130 @@ mod tests {
         }
     }
 
+    // =========================================================================
+    // Level Hash and Bulk Update Tests
+    // =========================================================================
+
+    #[test]
+    fn test_level_hashes_match_root() {
+        let hasher = Sha256Hasher::new();
+        let mut tree = SimpleMerkleTree::new(hasher.clone());
+        for data in [b"a", b"b", b"c", b"d", b"e"] {
+            tree.add_leaf(data).unwrap();
+        }
+
+        let levels = tree.level_hashes();
+        let sizes: Vec<usize> = levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![5, 3, 2, 1]);
+        assert_eq!(levels[3][0], tree.get_root_bytes().unwrap());
+        assert_eq!(levels[0][4], hasher.hash_leaf(b"e"));
+        // The odd node at level 1 pairs with itself
+        assert_eq!(
+            levels[1][2],
+            hasher.hash_internal(&levels[0][4], &levels[0][4])
+        );
+    }
+
+    #[test]
+    fn test_level_hashes_empty_tree() {
+        let tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        assert!(tree.level_hashes().is_empty());
+    }
+
+    #[test]
+    fn test_apply_leaves_replaces_appends_and_truncates() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+        }
+
+        tree.apply_leaves(vec![(1, b"x".to_vec()), (3, b"d".to_vec())], 4)
+            .unwrap();
+        let mut expected = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"x", b"c", b"d"] {
+            expected.add_leaf(data).unwrap();
+        }
+        assert_eq!(tree.get_root(), expected.get_root());
+
+        tree.apply_leaves(vec![], 1).unwrap();
+        assert_eq!(tree.get_size(), 1);
+        assert_eq!(tree.get_data(0), Some(b"a".as_slice()));
+    }
+
+    #[test]
+    fn test_apply_leaves_rejects_gaps_and_empty_leaves() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+        }
+        let root = tree.get_root();
+
+        // Index 3 is only reachable after truncating to 2 if 2 is written first
+        assert_eq!(
+            tree.apply_leaves(vec![(3, b"d".to_vec())], 2),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 3,
+                tree_size: 2,
+            })
+        );
+        assert_eq!(
+            tree.apply_leaves(vec![(2, b"c".to_vec()), (4, b"e".to_vec())], 2),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 4,
+                tree_size: 3,
+            })
+        );
+        assert_eq!(
+            tree.apply_leaves(vec![(1, Vec::new())], 3),
+            Err(MerkleTreeError::EmptyInput)
+        );
+
+        // Rejected updates leave the tree untouched
+        assert_eq!(tree.get_size(), 3);
+        assert_eq!(tree.get_root(), root);
+
+        tree.apply_leaves(vec![(2, b"x".to_vec()), (3, b"d".to_vec())], 2)
+            .unwrap();
+        assert_eq!(tree.get_data(3), Some(b"d".as_slice()));
+    }
+
+    /// Counts batch calls, and hashes batches through a different code
+    /// path than single leaves, to catch callers that bypass the batch.
+    struct BatchCountingHasher {
+        batches: std::cell::Cell<usize>,
+    }
+
+    impl Hasher for BatchCountingHasher {
+        fn hash_bytes(&self, data: &[u8]) -> [u8; 32] {
+            Sha256Hasher::new().hash_bytes(data)
+        }
+
+        fn hash_leaf_many(&self, leaves: &[&[u8]]) -> Vec<[u8; 32]> {
+            self.batches.set(self.batches.get() + 1);
+            Sha256Hasher::new().hash_leaf_many(leaves)
+        }
+    }
+
+    #[test]
+    fn test_apply_leaves_hashes_in_one_batch() {
+        let hasher = BatchCountingHasher {
+            batches: std::cell::Cell::new(0),
+        };
+        let mut batched = SimpleMerkleTree::new(hasher);
+        let data: Vec<Vec<u8>> = (0..37u32).map(|i| i.to_le_bytes().repeat(3)).collect();
+        batched
+            .apply_leaves(data.iter().cloned().enumerate().collect(), 0)
+            .unwrap();
+        assert_eq!(batched.hasher().batches.get(), 1);
+
+        let mut scalar = SimpleMerkleTree::new(Sha256Hasher::new());
+        for leaf in &data {
+            scalar.add_leaf(leaf).unwrap();
+        }
+        assert_eq!(batched.get_root_bytes(), scalar.get_root_bytes());
+        assert_eq!(batched.level_hashes(), scalar.level_hashes());
+    }
+
     // =========================================================================
     // Standalone verify_proof Tests
     // =========================================================================
@@ -925,17 +1310,240 @@ mod tests {
         let hasher = Sha256Hasher::new();
 
         // Build a simple 2-leaf tree manually
-        let leaf0 = compute_leaf_hash(b"a", &hasher);
-        let leaf1 = compute_leaf_hash(b"b", &hasher);
-        let root = compute_internal_hash(&leaf0, &leaf1, &hasher);
+        let leaf0 = hasher.hash_leaf(b"a");
+        let leaf1 = hasher.hash_leaf(b"b");
+        let root = hasher.hash_internal(&leaf0, &leaf1);
 
         // Create proof for leaf 0 (sibling is leaf 1)
         let proof = Proof::new(0, vec![leaf1]);
 
         // Verify without tree instance
-        assert!(verify_proof(b"a", &proof, &root, &hasher));
+        assert_eq!(verify_proof(b"a", &proof, &root, &hasher), Ok(true));
 
         // Wrong leaf should fail
-        assert!(!verify_proof(b"wrong", &proof, &root, &hasher));
+        assert_eq!(verify_proof(b"wrong", &proof, &root, &hasher), Ok(false));
+    }
+
+    // =========================================================================
+    // Hash Identifier Tests
+    // =========================================================================
+
+    #[test]
+    fn test_proof_records_hash_id() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+        let proof = tree.prove(0).unwrap();
+        assert_eq!(proof.hash_id(), Some(HashId::SHA2_256));
+        assert_eq!(proof.tree_size(), Some(2));
+    }
+
+    #[test]
+    fn test_verify_proof_rejects_other_hasher() {
+        let mut tree = SimpleMerkleTree::new(SimpleHasher::new());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove(0).unwrap();
+
+        assert_eq!(
+            verify_proof(b"a", &proof, &root, &Sha256Hasher::new()),
+            Err(MerkleTreeError::HasherMismatch {
+                proof: HashId::SIMPLE,
+                verifier: HashId::SHA2_256,
+            })
+        );
+        assert_eq!(
+            verify_proof(b"a", &proof, &root, &SimpleHasher::new()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_without_hash_id_recomputes_root() {
+        let mut tree = SimpleMerkleTree::new(SimpleHasher::new());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+        let root = tree.get_root_bytes().unwrap();
+        let untagged = Proof::new(
+            0,
+            tree.prove(0)
+                .unwrap()
+                .siblings()
+                .iter()
+                .map(|&s| s.into())
+                .collect(),
+        );
+
+        assert_eq!(
+            verify_proof(b"a", &untagged, &root, &Sha256Hasher::new()),
+            Ok(false)
+        );
+        assert_eq!(
+            verify_proof(b"a", &untagged, &root, &SimpleHasher::new()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_checks_recorded_tree_size() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c", b"d", b"e"] {
+            tree.add_leaf(data).unwrap();
+        }
+        let root = tree.get_root_bytes().unwrap();
+        let hasher = Sha256Hasher::new();
+        let proof = tree.prove(4).unwrap();
+        assert_eq!(proof.tree_size(), Some(5));
+        assert_eq!(verify_proof(b"e", &proof, &root, &hasher), Ok(true));
+
+        let siblings: Vec<[u8; 32]> = proof.siblings().iter().map(|&s| s.into()).collect();
+        let retag = |index: u64, siblings: Vec<[u8; 32]>, tree_size: u64| {
+            Proof::new(index, siblings)
+                .with_hash_id(proof.hash_id())
+                .with_tree_size(tree_size)
+        };
+
+        assert_eq!(
+            verify_proof(b"e", &retag(5, siblings.clone(), 5), &root, &hasher),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 5,
+                tree_size: 5,
+            })
+        );
+        assert_eq!(
+            verify_proof(b"e", &retag(4, siblings[..2].to_vec(), 5), &root, &hasher),
+            Err(MerkleTreeError::MissingSiblings {
+                count: 2,
+                expected: 3,
+            })
+        );
+        let mut extended = siblings.clone();
+        extended.push([0u8; 32]);
+        assert_eq!(
+            verify_proof(b"e", &retag(4, extended, 5), &root, &hasher),
+            Err(MerkleTreeError::ExcessSiblings { count: 4, max: 3 })
+        );
+    }
+
+    #[test]
+    fn test_get_root_multihash() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        assert_eq!(tree.get_root_multihash(), None);
+
+        tree.add_leaf(b"a").unwrap();
+        let multihash = tree.get_root_multihash().unwrap();
+        assert_eq!(multihash, format!("1220{}", tree.get_root().unwrap()));
+    }
+
+    #[test]
+    fn test_get_root_multihash_unidentified_hasher() {
+        let hasher = crate::hasher::PersonalizedHasher::new(Sha256Hasher::new(), b"tag");
+        let mut tree = SimpleMerkleTree::new(hasher);
+        tree.add_leaf(b"a").unwrap();
+        assert!(tree.get_root().is_some());
+        assert_eq!(tree.get_root_multihash(), None);
+    }
+
+    // =========================================================================
+    // DynMerkleTree Tests
+    // =========================================================================
+
+    #[test]
+    fn test_dyn_tree_matches_static_tree() {
+        let mut dynamic = DynMerkleTree::from_hasher_name("sha256").unwrap();
+        let mut fixed = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            dynamic.add_leaf(data).unwrap();
+            fixed.add_leaf(data).unwrap();
+        }
+        assert_eq!(dynamic.get_root(), fixed.get_root());
+
+        let proof = dynamic.prove(2).unwrap();
+        let root = dynamic.get_root_bytes().unwrap();
+        assert_eq!(
+            verify_proof(b"c", &proof, &root, &Sha256Hasher::new()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_dyn_tree_unknown_hasher() {
+        assert_eq!(
+            DynMerkleTree::from_hasher_name("md5").err(),
+            Some(MerkleTreeError::UnknownHasher {
+                name: "md5".to_string()
+            })
+        );
+    }
+
+    // =========================================================================
+    // Tree Snapshot Tests
+    // =========================================================================
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+        }
+        let snapshot = tree.to_snapshot();
+        assert_eq!(snapshot.hash_id(), Some(HashId::SHA2_256));
+        assert_eq!(snapshot.root(), tree.get_root_digest());
+        assert_eq!(snapshot.size(), 3);
+        assert_eq!(snapshot.leaves()[2], b"c");
+
+        let restored = SimpleMerkleTree::from_snapshot(snapshot, Sha256Hasher::new()).unwrap();
+        assert_eq!(restored.get_root(), tree.get_root());
+        assert_eq!(restored.prove(2), tree.prove(2));
+    }
+
+    #[test]
+    fn test_snapshot_of_empty_tree() {
+        let tree = SimpleMerkleTree::new(SimpleHasher::new());
+        let snapshot = tree.to_snapshot();
+        assert_eq!(snapshot.root(), None);
+        let restored = SimpleMerkleTree::from_snapshot(snapshot, SimpleHasher::new()).unwrap();
+        assert_eq!(restored.get_size(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_rejects_other_hasher() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"a").unwrap();
+        assert_eq!(
+            SimpleMerkleTree::from_snapshot(tree.to_snapshot(), SimpleHasher::new()).err(),
+            Some(MerkleTreeError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_rejects_empty_leaf() {
+        let snapshot = TreeSnapshot::new(None, None, vec![b"a".to_vec(), Vec::new()]);
+        assert_eq!(
+            SimpleMerkleTree::from_snapshot(snapshot, SimpleHasher::new()).err(),
+            Some(MerkleTreeError::EmptyInput)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_serde() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"hi").unwrap();
+        let snapshot = tree.to_snapshot();
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["hash_id"], 0x12);
+        assert_eq!(json["root"], tree.get_root().unwrap());
+        assert_eq!(json["leaves"], serde_json::json!(["6869"]));
+        let decoded: TreeSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        let bytes = bincode::serialize(&snapshot).unwrap();
+        assert_eq!(
+            bincode::deserialize::<TreeSnapshot>(&bytes).unwrap(),
+            snapshot
+        );
     }
 }
@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use crate::bytes_to_hex;
+use crate::hasher::HashId;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::digest32::Digest32;
+use crate::merkle::hash::Hash;
+use crate::merkle::node::Node;
+use crate::merkle::proof::Proof;
+
+/// An immutable view of a tree at a given version.
+///
+/// A snapshot only holds an `Arc` to the root node, so cloning it is cheap
+/// and it shares every subtree with the tree it came from and with the
+/// other snapshots taken from that tree. Subtrees are freed once the last
+/// snapshot (or tree) referencing them is dropped.
+///
+/// Snapshots are produced by `PersistentMerkleTree::commit`.
+#[derive(Clone)]
+pub struct Snapshot {
+    version: u64,
+    root: Option<Arc<Node>>,
+    size: u64,
+    hash_id: Option<HashId>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(
+        version: u64,
+        root: Option<Arc<Node>>,
+        size: u64,
+        hash_id: Option<HashId>,
+    ) -> Self {
+        Snapshot {
+            version,
+            root,
+            size,
+            hash_id,
+        }
+    }
+
+    /// Get the version number of this snapshot.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get the root hash as a hex string, or None if the snapshot is empty.
+    pub fn get_root(&self) -> Option<String> {
+        self.root.as_ref().map(|r| bytes_to_hex(r.hash()))
+    }
+
+    /// Get the root hash as raw bytes, or None if the snapshot is empty.
+    pub fn get_root_bytes(&self) -> Option<[u8; 32]> {
+        self.root.as_ref().map(|r| {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(r.hash());
+            bytes
+        })
+    }
+
+    /// Get the root hash as a hex-encoded multihash, or None if the snapshot
+    /// is empty or its tree's hasher has no `HashId`.
+    pub fn get_root_multihash(&self) -> Option<String> {
+        let hash_id = self.hash_id?;
+        let root = self.get_root_bytes()?;
+        Some(bytes_to_hex(&hash_id.multihash(&root)))
+    }
+
+    /// Get the hash algorithm of the tree the snapshot was taken from.
+    pub fn hash_id(&self) -> Option<HashId> {
+        self.hash_id
+    }
+
+    /// Get the root hash as a `Digest32`, or None if the snapshot is empty.
+    pub fn get_root_digest(&self) -> Option<Digest32> {
+        self.get_root_bytes().map(Digest32::from)
+    }
+
+    /// Get the data at the given leaf index.
+    pub fn get_data(&self, index: u64) -> Option<&[u8]> {
+        let root = self.root.as_ref()?;
+        if index >= self.size {
+            return None;
+        }
+        leaf_at(root, self.size, index).get_data()
+    }
+
+    /// Get the number of leaves in the snapshot.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Generate a membership proof for the leaf at the given index.
+    ///
+    /// Returns `MerkleTreeError::InvalidIndex` if the index is out of bounds
+    /// or the snapshot is empty.
+    pub fn prove(&self, index: u64) -> Result<Proof, MerkleTreeError> {
+        let tree_size = self.size;
+        match &self.root {
+            Some(root) if index < tree_size => {
+                let siblings = collect_siblings(root, tree_size, index);
+                Ok(Proof::new(index, siblings)
+                    .with_hash_id(self.hash_id)
+                    .with_tree_size(tree_size))
+            }
+            _ => Err(MerkleTreeError::InvalidIndex { index, tree_size }),
+        }
+    }
+
+    /// Get the root node, or None if the snapshot is empty.
+    ///
+    /// The node is shared with the tree and with other snapshots.
+    pub fn root_node(&self) -> Option<&Arc<Node>> {
+        self.root.as_ref()
+    }
+}
+
+/// A self-contained copy of a tree's leaves and root.
+///
+/// Unlike `Snapshot`, which shares nodes with a live tree, a `TreeSnapshot`
+/// owns plain data and can be stored or sent elsewhere. Rebuild the tree
+/// with `SimpleMerkleTree::from_snapshot`, which checks the leaves against
+/// the recorded root.
+///
+/// With the `serde` feature, the root and leaves serialize as hex strings
+/// in human-readable formats and as raw bytes in binary formats.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+/// tree.add_leaf(b"a").unwrap();
+/// tree.add_leaf(b"b").unwrap();
+///
+/// let snapshot = tree.to_snapshot();
+/// let restored = SimpleMerkleTree::from_snapshot(snapshot, Sha256Hasher::new()).unwrap();
+/// assert_eq!(restored.get_root(), tree.get_root());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeSnapshot {
+    #[cfg_attr(feature = "serde", serde(default))]
+    hash_id: Option<HashId>,
+    root: Option<Digest32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_vec"))]
+    leaves: Vec<Vec<u8>>,
+}
+
+impl TreeSnapshot {
+    pub(crate) fn new(
+        hash_id: Option<HashId>,
+        root: Option<Digest32>,
+        leaves: Vec<Vec<u8>>,
+    ) -> Self {
+        TreeSnapshot {
+            hash_id,
+            root,
+            leaves,
+        }
+    }
+
+    /// Get the hash algorithm of the tree, if known.
+    pub fn hash_id(&self) -> Option<HashId> {
+        self.hash_id
+    }
+
+    /// Get the root hash, or None if the tree was empty.
+    pub fn root(&self) -> Option<Digest32> {
+        self.root
+    }
+
+    /// Get the leaf data, in order.
+    pub fn leaves(&self) -> &[Vec<u8>] {
+        &self.leaves
+    }
+
+    /// Get the number of leaves.
+    pub fn size(&self) -> u64 {
+        self.leaves.len() as u64
+    }
+
+    pub(crate) fn into_leaves(self) -> Vec<Vec<u8>> {
+        self.leaves
+    }
+}
+
+/// Number of levels above the leaves in a tree of the given size.
+///
+/// A tree with one leaf has height 0; otherwise the height is ceil(log2(n)).
+pub(crate) fn tree_height(size: u64) -> u32 {
+    size.checked_next_power_of_two()
+        .map_or(u64::BITS, u64::trailing_zeros)
+}
+
+/// Descend from the root to the leaf at the given index.
+///
+/// The bits of the index, from most to least significant, select the child
+/// to follow at each level.
+pub(crate) fn leaf_at(root: &Node, size: u64, index: u64) -> &Node {
+    let mut node = root;
+    for level in (0..tree_height(size)).rev() {
+        node = match node {
+            Node::Internal(internal) if (index >> level) & 1 == 0 => internal.left(),
+            Node::Internal(internal) => internal.right(),
+            Node::Leaf(_) => break,
+        };
+    }
+    node
+}
+
+/// Collect the sibling hashes for a leaf, ordered from leaf level to root.
+///
+/// Duplicated nodes are stored as the same `Arc` on both sides, so a node
+/// without a right neighbour naturally gets itself as its sibling.
+pub(crate) fn collect_siblings(root: &Node, size: u64, index: u64) -> Vec<[u8; 32]> {
+    let mut siblings = Vec::new();
+    let mut node = root;
+    for level in (0..tree_height(size)).rev() {
+        let Node::Internal(internal) = node else {
+            break;
+        };
+        let (next, sibling) = if (index >> level) & 1 == 0 {
+            (internal.left(), internal.right())
+        } else {
+            (internal.right(), internal.left())
+        };
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(sibling.hash());
+        siblings.push(hash);
+        node = next;
+    }
+    siblings.reverse();
+    siblings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::SimpleHasher;
+
+    fn four_leaf_root() -> Arc<Node> {
+        let hasher = SimpleHasher::new();
+        let leaves: Vec<Arc<Node>> = [b"a", b"b", b"c", b"d"]
+            .iter()
+            .map(|data| Arc::new(Node::leaf(data.to_vec(), &hasher)))
+            .collect();
+        let n1 = Arc::new(Node::internal(
+            Arc::clone(&leaves[0]),
+            Arc::clone(&leaves[1]),
+            &hasher,
+        ));
+        let n2 = Arc::new(Node::internal(
+            Arc::clone(&leaves[2]),
+            Arc::clone(&leaves[3]),
+            &hasher,
+        ));
+        Arc::new(Node::internal(n1, n2, &hasher))
+    }
+
+    #[test]
+    fn test_tree_height() {
+        assert_eq!(tree_height(1), 0);
+        assert_eq!(tree_height(2), 1);
+        assert_eq!(tree_height(3), 2);
+        assert_eq!(tree_height(4), 2);
+        assert_eq!(tree_height(5), 3);
+    }
+
+    #[test]
+    fn test_leaf_at() {
+        let root = four_leaf_root();
+        assert_eq!(leaf_at(&root, 4, 0).get_data(), Some(b"a".as_slice()));
+        assert_eq!(leaf_at(&root, 4, 3).get_data(), Some(b"d".as_slice()));
+    }
+
+    #[test]
+    fn test_empty_snapshot() {
+        let snapshot = Snapshot::new(0, None, 0, None);
+        assert!(snapshot.get_root().is_none());
+        assert!(snapshot.get_data(0).is_none());
+        assert_eq!(
+            snapshot.prove(0),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 0,
+                tree_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_snapshot_accessors() {
+        let snapshot = Snapshot::new(7, Some(four_leaf_root()), 4, Some(HashId::SIMPLE));
+        assert_eq!(snapshot.version(), 7);
+        assert_eq!(snapshot.hash_id(), Some(HashId::SIMPLE));
+        assert_eq!(snapshot.get_size(), 4);
+        assert_eq!(
+            snapshot.get_root_digest().map(|root| root.to_hex()),
+            snapshot.get_root()
+        );
+        assert_eq!(snapshot.get_data(2), Some(b"c".as_slice()));
+        assert!(snapshot.get_data(4).is_none());
+        let proof = snapshot.prove(1).unwrap();
+        assert_eq!(proof.siblings().len(), 2);
+        assert_eq!(proof.hash_id(), Some(HashId::SIMPLE));
+        assert_eq!(proof.tree_size(), Some(4));
+        let root = snapshot.get_root_bytes().unwrap();
+        assert_eq!(
+            snapshot.get_root_multihash(),
+            Some(bytes_to_hex(&HashId::SIMPLE.multihash(&root)))
+        );
+    }
+}
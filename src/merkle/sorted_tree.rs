@@ -0,0 +1,593 @@
+use crate::hasher::Hasher;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::absence_proof::{AbsenceProof, NeighborProof};
+use crate::merkle::proof::Proof;
+use crate::merkle::simple_tree::{SimpleMerkleTree, verify_proof};
+
+/// A Merkle tree whose leaves are kept sorted by key.
+///
+/// Each leaf's data is its key, and keys are ordered lexicographically by
+/// their bytes. Keeping the leaves sorted makes it possible to prove that a
+/// key is *absent*: the two leaves that would surround it are shown to be
+/// adjacent in the tree.
+///
+/// The tree behaves like a set: inserting a key that is already present
+/// leaves the tree unchanged.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SortedMerkleTree};
+///
+/// let mut tree = SortedMerkleTree::new(Sha256Hasher::new());
+/// tree.insert(b"carol").unwrap();
+/// tree.insert(b"alice").unwrap();
+///
+/// let root = tree.get_root_bytes().unwrap();
+/// let proof = tree.prove_absence(b"bob").unwrap();
+/// assert!(tree.verify_absence(&proof, b"bob", &root));
+/// ```
+pub struct SortedMerkleTree<H: Hasher> {
+    tree: SimpleMerkleTree<H>,
+}
+
+impl<H: Hasher> MerkleTree<H> for SortedMerkleTree<H> {
+    /// Insert the data as a key, keeping the leaves sorted.
+    fn add_leaf(&mut self, data: &[u8]) -> Result<(), MerkleTreeError> {
+        self.insert(data).map(|_| ())
+    }
+
+    fn get_root(&self) -> Option<String> {
+        self.tree.get_root()
+    }
+
+    fn get_root_bytes(&self) -> Option<[u8; 32]> {
+        self.tree.get_root_bytes()
+    }
+
+    fn get_root_multihash(&self) -> Option<String> {
+        self.tree.get_root_multihash()
+    }
+
+    fn get_data(&self, index: u64) -> Option<&[u8]> {
+        self.tree.get_data(index)
+    }
+
+    fn get_size(&self) -> u64 {
+        self.tree.get_size()
+    }
+
+    fn prove(&self, index: u64) -> Result<Proof, MerkleTreeError> {
+        self.tree.prove(index)
+    }
+
+    fn verify(&self, proof: &Proof, leaf_data: &[u8], expected_root: &[u8; 32]) -> bool {
+        self.tree.verify(proof, leaf_data, expected_root)
+    }
+}
+
+impl<H: Hasher> SortedMerkleTree<H> {
+    pub fn new(hasher: H) -> Self {
+        Self {
+            tree: SimpleMerkleTree::new(hasher),
+        }
+    }
+
+    /// Insert a key into the tree at its sorted position.
+    ///
+    /// Returns `true` if the key was inserted, or `false` if it was already
+    /// present. Returns `MerkleTreeError::EmptyInput` for an empty key.
+    pub fn insert(&mut self, key: &[u8]) -> Result<bool, MerkleTreeError> {
+        if key.is_empty() {
+            return Err(MerkleTreeError::EmptyInput);
+        }
+
+        match self.search(key) {
+            Ok(_) => Ok(false),
+            Err(position) => {
+                self.tree.insert_leaf_at(position, key);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove a key from the tree.
+    ///
+    /// Returns `true` if the key was present and has been removed.
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        match self.search(key) {
+            Ok(position) => {
+                self.tree.remove_leaf_at(position);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Check whether the key is present in the tree.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Get the leaf index of the key, or `None` if it is absent.
+    pub fn index_of(&self, key: &[u8]) -> Option<u64> {
+        self.search(key).ok().map(|position| position as u64)
+    }
+
+    /// Generate a proof that the key is absent from the tree.
+    ///
+    /// Returns `MerkleTreeError::EmptyTree` if the tree has no leaves and
+    /// `MerkleTreeError::KeyPresent` if the key is in the tree.
+    ///
+    /// # Complexity
+    ///
+    /// - Time: O(log n) to locate the neighbours, plus two proof generations
+    /// - Space: O(log n) for the two membership proofs
+    pub fn prove_absence(&self, key: &[u8]) -> Result<AbsenceProof, MerkleTreeError> {
+        let tree_size = self.tree.get_size();
+        if tree_size == 0 {
+            return Err(MerkleTreeError::EmptyTree);
+        }
+
+        let position = match self.search(key) {
+            Ok(index) => {
+                return Err(MerkleTreeError::KeyPresent {
+                    index: index as u64,
+                });
+            }
+            Err(position) => position as u64,
+        };
+
+        let left = match position {
+            0 => None,
+            _ => Some(self.neighbor(position - 1)?),
+        };
+        let right = if position < tree_size {
+            Some(self.neighbor(position)?)
+        } else {
+            None
+        };
+
+        Ok(AbsenceProof::new(left, right))
+    }
+
+    /// Verify that the key is absent from a tree with the expected root.
+    ///
+    /// For standalone verification without a tree instance, use
+    /// `verify_absence_proof()`.
+    pub fn verify_absence(
+        &self,
+        proof: &AbsenceProof,
+        key: &[u8],
+        expected_root: &[u8; 32],
+    ) -> bool {
+        verify_absence_proof(key, proof, expected_root, self.tree.hasher())
+    }
+
+    /// Binary search for the key among the sorted leaves.
+    fn search(&self, key: &[u8]) -> Result<usize, usize> {
+        self.tree
+            .leaves()
+            .binary_search_by(|leaf| leaf.data().cmp(key))
+    }
+
+    /// Build the neighbour proof for the leaf at the given index.
+    fn neighbor(&self, index: u64) -> Result<NeighborProof, MerkleTreeError> {
+        let proof = self.tree.prove(index)?;
+        let key = self.tree.get_data(index).unwrap_or_default().to_vec();
+        Ok(NeighborProof::new(key, proof))
+    }
+}
+
+/// Verify an absence proof without requiring access to the original tree.
+///
+/// The proof is accepted when:
+///
+/// - every neighbour's membership proof verifies against the expected root,
+/// - the left neighbour's key sorts strictly before `key` and the right
+///   neighbour's key sorts strictly after it,
+/// - with two neighbours, the right index is exactly the left index plus one,
+/// - with only a right neighbour, its index is 0,
+/// - with only a left neighbour, its proof follows the rightmost path of the
+///   tree (at every level where it is a left child, its sibling is itself).
+///
+/// # Security Properties
+///
+/// - Indices are bound to the authentication path: an index with bits beyond
+///   the proof length is rejected, so adjacency cannot be faked by wrapping
+/// - The rightmost-path check relies on keys being unique, so no subtree can
+///   hash to the same value as its duplicated neighbour
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::merkle::sorted_tree::{SortedMerkleTree, verify_absence_proof};
+/// use merkle_trees::{MerkleTree, Sha256Hasher};
+///
+/// let mut tree = SortedMerkleTree::new(Sha256Hasher::new());
+/// tree.insert(b"a").unwrap();
+/// tree.insert(b"c").unwrap();
+///
+/// let root = tree.get_root_bytes().unwrap();
+/// let proof = tree.prove_absence(b"b").unwrap();
+/// assert!(verify_absence_proof(b"b", &proof, &root, &Sha256Hasher::new()));
+/// ```
+pub fn verify_absence_proof<H: Hasher>(
+    key: &[u8],
+    proof: &AbsenceProof,
+    expected_root: &[u8; 32],
+    hasher: &H,
+) -> bool {
+    let neighbor_valid = |neighbor: &NeighborProof| {
+        index_fits_path(neighbor.proof())
+            && verify_proof(neighbor.key(), neighbor.proof(), expected_root, hasher) == Ok(true)
+    };
+
+    match (proof.left(), proof.right()) {
+        (None, None) => false,
+        (Some(left), Some(right)) => {
+            left.key() < key
+                && key < right.key()
+                && left.proof().index().checked_add(1) == Some(right.proof().index())
+                && neighbor_valid(left)
+                && neighbor_valid(right)
+        }
+        (None, Some(right)) => {
+            key < right.key() && right.proof().index() == 0 && neighbor_valid(right)
+        }
+        (Some(left), None) => {
+            left.key() < key
+                && neighbor_valid(left)
+                && is_rightmost_path(left.key(), left.proof(), hasher)
+        }
+    }
+}
+
+/// Check that the proof's index has no bits beyond its authentication path.
+///
+/// `verify_proof` only consumes one index bit per sibling, so higher bits
+/// would otherwise be ignored and the index could be forged.
+fn index_fits_path(proof: &Proof) -> bool {
+    let depth = proof.siblings().len() as u32;
+    depth >= u64::BITS || proof.index() >> depth == 0
+}
+
+/// Check that the proof describes the last leaf of the tree.
+///
+/// The last leaf is always the last node of its level. When it is a left
+/// child (even index) it has no right neighbour, so its sibling must be
+/// its own duplicate. A leaf that passes this check at every level has no
+/// leaves to its right.
+fn is_rightmost_path<H: Hasher>(leaf_data: &[u8], proof: &Proof, hasher: &H) -> bool {
+    let mut current_hash = hasher.hash_leaf(leaf_data);
+    let mut index = proof.index();
+
+    for sibling in proof.siblings() {
+        if index.is_multiple_of(2) {
+            if *sibling != current_hash {
+                return false;
+            }
+            current_hash = hasher.hash_internal(&current_hash, sibling.as_bytes());
+        } else {
+            current_hash = hasher.hash_internal(sibling.as_bytes(), &current_hash);
+        }
+        index /= 2;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Sha256Hasher, SimpleHasher};
+
+    fn tree_with(keys: &[&[u8]]) -> SortedMerkleTree<Sha256Hasher> {
+        let mut tree = SortedMerkleTree::new(Sha256Hasher::new());
+        for key in keys {
+            tree.insert(key).unwrap();
+        }
+        tree
+    }
+
+    // =========================================================================
+    // Set Operation Tests
+    // =========================================================================
+
+    #[test]
+    fn test_insert_keeps_leaves_sorted() {
+        let tree = tree_with(&[b"delta", b"alpha", b"charlie", b"bravo"]);
+
+        let keys: Vec<&[u8]> = (0..tree.get_size())
+            .map(|i| tree.get_data(i).unwrap())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![b"alpha".as_slice(), b"bravo", b"charlie", b"delta"]
+        );
+    }
+
+    #[test]
+    fn test_insert_duplicate_returns_false() {
+        let mut tree = tree_with(&[b"a"]);
+        let root = tree.get_root();
+
+        assert!(!tree.insert(b"a").unwrap());
+        assert_eq!(tree.get_size(), 1);
+        assert_eq!(tree.get_root(), root);
+    }
+
+    #[test]
+    fn test_insert_empty_key_rejected() {
+        let mut tree = SortedMerkleTree::new(SimpleHasher::new());
+        assert_eq!(tree.insert(b""), Err(MerkleTreeError::EmptyInput));
+    }
+
+    #[test]
+    fn test_root_independent_of_insertion_order() {
+        let tree1 = tree_with(&[b"a", b"b", b"c"]);
+        let tree2 = tree_with(&[b"c", b"a", b"b"]);
+        assert_eq!(tree1.get_root(), tree2.get_root());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = tree_with(&[b"a", b"b", b"c"]);
+        let expected = tree_with(&[b"a", b"c"]);
+
+        assert!(tree.remove(b"b"));
+        assert!(!tree.remove(b"b"));
+        assert!(!tree.contains(b"b"));
+        assert_eq!(tree.get_root(), expected.get_root());
+    }
+
+    #[test]
+    fn test_remove_last_key_empties_tree() {
+        let mut tree = tree_with(&[b"only"]);
+        assert!(tree.remove(b"only"));
+        assert_eq!(tree.get_size(), 0);
+        assert!(tree.get_root().is_none());
+    }
+
+    #[test]
+    fn test_contains_and_index_of() {
+        let tree = tree_with(&[b"b", b"a"]);
+        assert!(tree.contains(b"a"));
+        assert!(!tree.contains(b"c"));
+        assert_eq!(tree.index_of(b"b"), Some(1));
+        assert_eq!(tree.index_of(b"c"), None);
+    }
+
+    #[test]
+    fn test_add_leaf_inserts_sorted() {
+        let mut tree = SortedMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"z").unwrap();
+        tree.add_leaf(b"y").unwrap();
+        assert_eq!(tree.get_data(0), Some(b"y".as_slice()));
+    }
+
+    #[test]
+    fn test_membership_proofs_still_verify() {
+        let tree = tree_with(&[b"c", b"a", b"b"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        let index = tree.index_of(b"b").unwrap();
+        let proof = tree.prove(index).unwrap();
+        assert!(tree.verify(&proof, b"b", &root));
+    }
+
+    // =========================================================================
+    // Absence Proof Generation Tests
+    // =========================================================================
+
+    #[test]
+    fn test_prove_absence_empty_tree() {
+        let tree = SortedMerkleTree::new(Sha256Hasher::new());
+        assert_eq!(tree.prove_absence(b"a"), Err(MerkleTreeError::EmptyTree));
+    }
+
+    #[test]
+    fn test_prove_absence_present_key() {
+        let tree = tree_with(&[b"a", b"b"]);
+        assert_eq!(
+            tree.prove_absence(b"b"),
+            Err(MerkleTreeError::KeyPresent { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_prove_absence_middle_has_adjacent_neighbors() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let proof = tree.prove_absence(b"d").unwrap();
+
+        let left = proof.left().unwrap();
+        let right = proof.right().unwrap();
+        assert_eq!(left.key(), b"c");
+        assert_eq!(right.key(), b"e");
+        assert_eq!(left.proof().index() + 1, right.proof().index());
+    }
+
+    #[test]
+    fn test_prove_absence_before_first() {
+        let tree = tree_with(&[b"b", b"c"]);
+        let proof = tree.prove_absence(b"a").unwrap();
+        assert!(proof.left().is_none());
+        assert_eq!(proof.right().unwrap().proof().index(), 0);
+    }
+
+    #[test]
+    fn test_prove_absence_after_last() {
+        let tree = tree_with(&[b"b", b"c"]);
+        let proof = tree.prove_absence(b"d").unwrap();
+        assert_eq!(proof.left().unwrap().proof().index(), 1);
+        assert!(proof.right().is_none());
+    }
+
+    // =========================================================================
+    // Absence Proof Verification Tests
+    // =========================================================================
+
+    #[test]
+    fn test_verify_absence_all_gaps() {
+        // Odd and even sizes exercise the duplicated rightmost path
+        for size in 1..=9u8 {
+            let keys: Vec<Vec<u8>> = (0..size).map(|i| vec![b'b' + 2 * i]).collect();
+            let key_refs: Vec<&[u8]> = keys.iter().map(|k| k.as_slice()).collect();
+            let tree = tree_with(&key_refs);
+            let root = tree.get_root_bytes().unwrap();
+
+            for gap in 0..=size {
+                let missing = [b'a' + 2 * gap];
+                let proof = tree.prove_absence(&missing).unwrap();
+                assert!(
+                    tree.verify_absence(&proof, &missing, &root),
+                    "Failed for gap {} in tree of {} keys",
+                    gap,
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_present_key() {
+        let tree = tree_with(&[b"a", b"c"]);
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove_absence(b"b").unwrap();
+
+        // The same neighbours do not prove absence of one of them
+        assert!(!tree.verify_absence(&proof, b"a", &root));
+        assert!(!tree.verify_absence(&proof, b"c", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_key_outside_gap() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove_absence(b"b").unwrap();
+
+        // "d" is absent, but not between "a" and "c"
+        assert!(!tree.verify_absence(&proof, b"d", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_non_adjacent_neighbors() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        // Skip over "c" by pairing leaf 0 with leaf 2
+        let proof = AbsenceProof::new(
+            Some(NeighborProof::new(b"a".to_vec(), tree.prove(0).unwrap())),
+            Some(NeighborProof::new(b"e".to_vec(), tree.prove(2).unwrap())),
+        );
+        assert!(!tree.verify_absence(&proof, b"c", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_forged_right_index() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        // Leaf 2 relabelled with index 1 does not verify against the root
+        let forged = Proof::new(
+            1,
+            tree.prove(2)
+                .unwrap()
+                .siblings()
+                .iter()
+                .map(|&s| s.into())
+                .collect(),
+        );
+        let proof = AbsenceProof::new(
+            Some(NeighborProof::new(b"a".to_vec(), tree.prove(0).unwrap())),
+            Some(NeighborProof::new(b"e".to_vec(), forged)),
+        );
+        assert!(!tree.verify_absence(&proof, b"c", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_index_beyond_path() {
+        let tree = tree_with(&[b"b", b"d"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        // Index 2 has the same low bit as index 0 but exceeds a 1-level path
+        let wrapped = Proof::new(
+            2,
+            tree.prove(0)
+                .unwrap()
+                .siblings()
+                .iter()
+                .map(|&s| s.into())
+                .collect(),
+        );
+        let proof = AbsenceProof::new(
+            Some(NeighborProof::new(b"d".to_vec(), tree.prove(1).unwrap())),
+            Some(NeighborProof::new(b"b".to_vec(), wrapped)),
+        );
+        assert!(!tree.verify_absence(&proof, b"c", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_non_rightmost_left_only() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        // Claim "d" sorts after everything by presenting only "c"
+        let proof = AbsenceProof::new(
+            Some(NeighborProof::new(b"c".to_vec(), tree.prove(1).unwrap())),
+            None,
+        );
+        assert!(!tree.verify_absence(&proof, b"d", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_non_first_right_only() {
+        let tree = tree_with(&[b"a", b"c", b"e"]);
+        let root = tree.get_root_bytes().unwrap();
+
+        // Claim "b" sorts before everything by presenting only "c"
+        let proof = AbsenceProof::new(
+            None,
+            Some(NeighborProof::new(b"c".to_vec(), tree.prove(1).unwrap())),
+        );
+        assert!(!tree.verify_absence(&proof, b"b", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_empty_proof() {
+        let tree = tree_with(&[b"a"]);
+        let root = tree.get_root_bytes().unwrap();
+        let proof = AbsenceProof::new(None, None);
+        assert!(!tree.verify_absence(&proof, b"b", &root));
+    }
+
+    #[test]
+    fn test_verify_absence_rejects_wrong_root() {
+        let tree = tree_with(&[b"a", b"c"]);
+        let proof = tree.prove_absence(b"b").unwrap();
+        assert!(!tree.verify_absence(&proof, b"b", &[0u8; 32]));
+    }
+
+    #[test]
+    fn test_verify_absence_proof_standalone() {
+        let tree = tree_with(&[b"a", b"c"]);
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove_absence(b"d").unwrap();
+
+        assert!(verify_absence_proof(
+            b"d",
+            &proof,
+            &root,
+            &Sha256Hasher::new()
+        ));
+    }
+
+    #[test]
+    fn test_index_fits_path() {
+        assert!(index_fits_path(&Proof::new(0, vec![])));
+        assert!(!index_fits_path(&Proof::new(1, vec![])));
+        assert!(index_fits_path(&Proof::new(3, vec![[0u8; 32]; 2])));
+        assert!(!index_fits_path(&Proof::new(4, vec![[0u8; 32]; 2])));
+    }
+}
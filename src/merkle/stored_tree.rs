@@ -0,0 +1,747 @@
+use crate::bytes_to_hex;
+use crate::hasher::Hasher;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeError;
+use crate::merkle::node_store::{HashOnlyNodeStore, MemoryNodeStore, NodeStore, PrunableNodeStore};
+use crate::merkle::proof::Proof;
+use crate::merkle::simple_tree::verify_proof;
+use crate::merkle::snapshot::tree_height;
+
+/// A Merkle tree that keeps its leaves and node hashes in a `NodeStore`.
+///
+/// The tree itself only holds the hasher and the store, so its size is
+/// bounded by the store rather than by memory. Adding or updating a leaf
+/// rewrites the O(log n) hashes on the path to the root, and proofs read
+/// one hash per level.
+///
+/// Roots are identical to those of a `SimpleMerkleTree` built from the same
+/// leaves, so proofs from either tree verify with `verify_proof`.
+///
+/// This is a separate type: `SimpleMerkleTree` keeps its in-memory nodes
+/// and does not go through a `NodeStore`. Use `StoredMerkleTree` when the
+/// tree should live in a store.
+///
+/// A failed store write is returned as an error and leaves the size and
+/// root as they were.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree, StoredMerkleTree};
+///
+/// let mut stored = StoredMerkleTree::new(Sha256Hasher::new());
+/// let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+/// for data in [b"a", b"b", b"c"] {
+///     stored.add_leaf(data).unwrap();
+///     simple.add_leaf(data).unwrap();
+/// }
+/// assert_eq!(stored.get_root(), simple.get_root());
+/// ```
+pub struct StoredMerkleTree<H: Hasher, S: NodeStore = MemoryNodeStore> {
+    store: S,
+    hasher: H,
+}
+
+impl<H: Hasher> StoredMerkleTree<H> {
+    /// Create an empty tree backed by a `MemoryNodeStore`.
+    pub fn new(hasher: H) -> Self {
+        Self::with_store(hasher, MemoryNodeStore::new())
+    }
+}
+
+impl<H: Hasher> StoredMerkleTree<H, HashOnlyNodeStore> {
+    /// Create an empty tree that keeps only leaf hashes.
+    ///
+    /// `add_leaf` hashes the data and drops it, and `get_data` always
+    /// returns None. Use `add_leaf_hash` when the leaf hashes are already
+    /// known.
+    pub fn hash_only(hasher: H) -> Self {
+        Self::with_store(hasher, HashOnlyNodeStore::new())
+    }
+
+    /// Append a leaf by its precomputed hash.
+    ///
+    /// For proofs to verify against the leaf data with `verify_proof`, the
+    /// hash must be `hasher.hash_leaf(data)`; otherwise verify them with
+    /// `verify_proof_from_hash`.
+    pub fn add_leaf_hash(&mut self, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        let size = self.get_size();
+        self.write_leaf_hash(size, hash, size + 1)
+    }
+
+    /// Replace the hash of an existing leaf.
+    pub fn update_leaf_hash(&mut self, index: u64, hash: [u8; 32]) -> Result<(), MerkleTreeError> {
+        let size = self.get_size();
+        if index >= size {
+            return Err(MerkleTreeError::InvalidIndex {
+                index,
+                tree_size: size,
+            });
+        }
+        self.check_retained(index)?;
+        self.write_leaf_hash(index, hash, size)
+    }
+}
+
+impl<H: Hasher, S: NodeStore> StoredMerkleTree<H, S> {
+    /// Create a tree over an existing store.
+    ///
+    /// The tree takes its size from `store.leaf_count()`, so a store that
+    /// already holds a tree built with the same hasher is reopened as is.
+    pub fn with_store(hasher: H, store: S) -> Self {
+        StoredMerkleTree { store, hasher }
+    }
+
+    /// Get the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Get the underlying store mutably.
+    ///
+    /// Writing through it can leave the tree inconsistent; it is meant for
+    /// store-specific operations such as flushing.
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    #[cfg(feature = "mmap")]
+    pub(crate) fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Consume the tree and return its store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Replace the data of an existing leaf.
+    ///
+    /// Store errors, such as `MerkleTreeError::Io`, are returned as is, and
+    /// the tree keeps its previous root.
+    pub fn update_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError> {
+        if data.is_empty() {
+            return Err(MerkleTreeError::EmptyInput);
+        }
+        let size = self.get_size();
+        if index >= size {
+            return Err(MerkleTreeError::InvalidIndex {
+                index,
+                tree_size: size,
+            });
+        }
+        self.check_retained(index)?;
+        self.write_leaf(index, data, size)
+    }
+
+    /// Get the index of the oldest leaf that has not been pruned.
+    pub fn pruned_before(&self) -> u64 {
+        self.store.pruned_before()
+    }
+
+    /// Check that a leaf in range has not been pruned.
+    fn check_retained(&self, index: u64) -> Result<(), MerkleTreeError> {
+        let first_retained = self.store.pruned_before();
+        if index < first_retained {
+            return Err(MerkleTreeError::Pruned {
+                index,
+                first_retained,
+            });
+        }
+        Ok(())
+    }
+
+    /// Store a leaf and rehash the path above it in a tree of `size` leaves.
+    fn write_leaf(&mut self, index: u64, data: &[u8], size: u64) -> Result<(), MerkleTreeError> {
+        let path = self.path_hashes(index, self.hasher.hash_leaf(data), size)?;
+        self.write_path(&path, |store| store.put_leaf(index, data))
+    }
+
+    /// Store a leaf hash and rehash the path above it in a tree of `size`
+    /// leaves.
+    fn write_leaf_hash(
+        &mut self,
+        index: u64,
+        hash: [u8; 32],
+        size: u64,
+    ) -> Result<(), MerkleTreeError> {
+        let path = self.path_hashes(index, hash, size)?;
+        self.write_path(&path, |_| Ok(()))
+    }
+
+    /// Compute the new hashes of the nodes from the leaf at `index` up to
+    /// the root, as `(level, position, hash)`, without writing anything.
+    fn path_hashes(
+        &self,
+        index: u64,
+        mut hash: [u8; 32],
+        size: u64,
+    ) -> Result<Vec<(u32, u64, [u8; 32])>, MerkleTreeError> {
+        let mut path = vec![(0, index, hash)];
+        for level in 0..tree_height(size) {
+            let position = index >> level;
+            let sibling = self.sibling_hash(level, position, size, hash)?;
+            hash = if position & 1 == 0 {
+                self.hasher.hash_internal(&hash, &sibling)
+            } else {
+                self.hasher.hash_internal(&sibling, &hash)
+            };
+            path.push((level + 1, position >> 1, hash));
+        }
+        Ok(path)
+    }
+
+    /// Write the path hashes, then call `record_leaf` to store the leaf.
+    ///
+    /// The leaf goes last so that the store never counts a leaf whose path
+    /// is missing. If any write fails, the hashes that were overwritten are
+    /// put back, so the tree keeps its previous size and root. Restoring is
+    /// best effort: if it fails as well, the first error is still returned.
+    fn write_path(
+        &mut self,
+        path: &[(u32, u64, [u8; 32])],
+        record_leaf: impl FnOnce(&mut S) -> Result<(), MerkleTreeError>,
+    ) -> Result<(), MerkleTreeError> {
+        let previous: Vec<Option<[u8; 32]>> = path
+            .iter()
+            .map(|&(level, position, _)| self.store.get_hash(level, position))
+            .collect();
+        let result = path
+            .iter()
+            .try_for_each(|&(level, position, hash)| self.store.put_hash(level, position, hash))
+            .and_then(|()| record_leaf(&mut self.store));
+        if result.is_err() {
+            for (&(level, position, _), hash) in path.iter().zip(previous) {
+                if let Some(hash) = hash {
+                    // Best effort: the original error is what gets reported
+                    let _ = self.store.put_hash(level, position, hash);
+                }
+            }
+        }
+        result
+    }
+
+    /// Get the sibling of the node at `(level, position)`.
+    ///
+    /// The last node of a level with an odd number of nodes is its own
+    /// sibling, whose hash the caller passes as `own_hash`.
+    fn sibling_hash(
+        &self,
+        level: u32,
+        position: u64,
+        size: u64,
+        own_hash: [u8; 32],
+    ) -> Result<[u8; 32], MerkleTreeError> {
+        let sibling = position ^ 1;
+        if sibling >= level_width(size, level) {
+            return Ok(own_hash);
+        }
+        self.store
+            .get_hash(level, sibling)
+            .ok_or(MerkleTreeError::MissingHash {
+                level,
+                index: sibling,
+            })
+    }
+}
+
+impl<H: Hasher, S: PrunableNodeStore> StoredMerkleTree<H, S> {
+    /// Drop the data of the leaves before `before`, along with every hash
+    /// that is no longer needed.
+    ///
+    /// The tree keeps the hashes on the proof paths of the retained leaves,
+    /// so it can still prove them, update them and append new leaves, and
+    /// its root is unchanged. Proving or updating a pruned leaf returns
+    /// `MerkleTreeError::Pruned`. Pruning less than an earlier call is a
+    /// no-op.
+    ///
+    /// Returns `MerkleTreeError::InvalidIndex` if `before` is past the end
+    /// of the tree.
+    pub fn prune(&mut self, before: u64) -> Result<(), MerkleTreeError> {
+        let size = self.get_size();
+        if before > size {
+            return Err(MerkleTreeError::InvalidIndex {
+                index: before,
+                tree_size: size,
+            });
+        }
+        self.store.prune(before);
+        Ok(())
+    }
+}
+
+/// Number of nodes on a level of a non-empty tree of the given size.
+fn level_width(size: u64, level: u32) -> u64 {
+    ((size - 1) >> level) + 1
+}
+
+impl<H: Hasher, S: NodeStore> MerkleTree<H> for StoredMerkleTree<H, S> {
+    fn add_leaf(&mut self, data: &[u8]) -> Result<(), MerkleTreeError> {
+        if data.is_empty() {
+            return Err(MerkleTreeError::EmptyInput);
+        }
+        let size = self.get_size();
+        self.write_leaf(size, data, size + 1)
+    }
+
+    fn get_root(&self) -> Option<String> {
+        self.get_root_bytes().map(|root| bytes_to_hex(&root))
+    }
+
+    fn get_root_bytes(&self) -> Option<[u8; 32]> {
+        match self.get_size() {
+            0 => None,
+            size => self.store.get_hash(tree_height(size), 0),
+        }
+    }
+
+    fn get_root_multihash(&self) -> Option<String> {
+        let hash_id = self.hasher.hash_id()?;
+        let root = self.get_root_bytes()?;
+        Some(bytes_to_hex(&hash_id.multihash(&root)))
+    }
+
+    fn get_data(&self, index: u64) -> Option<&[u8]> {
+        if index >= self.get_size() {
+            return None;
+        }
+        self.store.get_leaf(index)
+    }
+
+    fn get_size(&self) -> u64 {
+        self.store.leaf_count()
+    }
+
+    /// Returns `MerkleTreeError::Pruned` for a pruned leaf and
+    /// `MissingHash` if the store lacks a hash on the path, in addition to
+    /// `InvalidIndex`.
+    fn prove(&self, index: u64) -> Result<Proof, MerkleTreeError> {
+        let tree_size = self.get_size();
+        if index >= tree_size {
+            return Err(MerkleTreeError::InvalidIndex { index, tree_size });
+        }
+        self.check_retained(index)?;
+
+        let mut siblings = Vec::new();
+        for level in 0..tree_height(tree_size) {
+            let position = index >> level;
+            let own_hash =
+                self.store
+                    .get_hash(level, position)
+                    .ok_or(MerkleTreeError::MissingHash {
+                        level,
+                        index: position,
+                    })?;
+            siblings.push(self.sibling_hash(level, position, tree_size, own_hash)?);
+        }
+        Ok(Proof::new(index, siblings)
+            .with_hash_id(self.hasher.hash_id())
+            .with_tree_size(tree_size))
+    }
+
+    fn verify(&self, proof: &Proof, leaf_data: &[u8], expected_root: &[u8; 32]) -> bool {
+        verify_proof(leaf_data, proof, expected_root, &self.hasher).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Sha256Hasher, SimpleHasher};
+    use crate::merkle::SimpleMerkleTree;
+    use crate::merkle::simple_tree::verify_proof_from_hash;
+    use crate::merkle::tests::leaf;
+
+    // =========================================================================
+    // Basic Tree Tests
+    // =========================================================================
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = StoredMerkleTree::new(SimpleHasher::new());
+        assert_eq!(tree.get_size(), 0);
+        assert!(tree.get_root().is_none());
+        assert!(tree.get_data(0).is_none());
+        assert_eq!(
+            tree.prove(0),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 0,
+                tree_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_empty_leaf() {
+        let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+        assert_eq!(tree.add_leaf(b""), Err(MerkleTreeError::EmptyInput));
+        assert_eq!(tree.get_size(), 0);
+    }
+
+    #[test]
+    fn test_matches_simple_tree() {
+        let mut stored = StoredMerkleTree::new(Sha256Hasher::new());
+        let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..40 {
+            stored.add_leaf(&leaf(i)).unwrap();
+            simple.add_leaf(&leaf(i)).unwrap();
+            assert_eq!(
+                stored.get_root_bytes(),
+                simple.get_root_bytes(),
+                "size {}",
+                i + 1
+            );
+        }
+        for i in 0..40 {
+            assert_eq!(stored.prove(i), simple.prove(i), "index {}", i);
+            assert_eq!(stored.get_data(i), simple.get_data(i));
+        }
+        assert_eq!(stored.get_root_multihash(), simple.get_root_multihash());
+    }
+
+    #[test]
+    fn test_proofs_verify() {
+        let mut tree = StoredMerkleTree::new(Sha256Hasher::new());
+        for i in 0..13 {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+        let root = tree.get_root_bytes().unwrap();
+        for i in 0..13 {
+            let proof = tree.prove(i).unwrap();
+            assert!(tree.verify(&proof, &leaf(i), &root));
+            assert!(!tree.verify(&proof, b"other", &root));
+        }
+    }
+
+    #[test]
+    fn test_update_leaf() {
+        let mut stored = StoredMerkleTree::new(Sha256Hasher::new());
+        let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..7 {
+            let data = leaf(i);
+            stored.add_leaf(&data).unwrap();
+            simple
+                .add_leaf(if i == 6 { b"new" } else { &data })
+                .unwrap();
+        }
+        stored.update_leaf(6, b"new").unwrap();
+        assert_eq!(stored.get_root_bytes(), simple.get_root_bytes());
+        assert_eq!(stored.prove(5), simple.prove(5));
+
+        assert_eq!(
+            stored.update_leaf(7, b"x"),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 7,
+                tree_size: 7
+            })
+        );
+        assert_eq!(stored.update_leaf(0, b""), Err(MerkleTreeError::EmptyInput));
+    }
+
+    // =========================================================================
+    // Store Tests
+    // =========================================================================
+
+    #[test]
+    fn test_reopen_store() {
+        let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+        for i in 0..5 {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+        let root = tree.get_root_bytes();
+        let reopened = StoredMerkleTree::with_store(SimpleHasher::new(), tree.into_store());
+        assert_eq!(reopened.get_size(), 5);
+        assert_eq!(reopened.get_root_bytes(), root);
+    }
+
+    #[test]
+    fn test_store_layout() {
+        let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+        for i in 0..3 {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+        let hasher = SimpleHasher::new();
+        let h2 = hasher.hash_leaf(&leaf(2));
+        assert_eq!(tree.store().get_hash(0, 2), Some(h2));
+        assert_eq!(
+            tree.store().get_hash(1, 1),
+            Some(hasher.hash_internal(&h2, &h2))
+        );
+        assert_eq!(tree.store().get_hash(2, 0), tree.get_root_bytes());
+    }
+
+    #[test]
+    fn test_missing_hash() {
+        let mut store = MemoryNodeStore::new();
+        for i in 0..3 {
+            store.put_leaf(i, &leaf(i)).unwrap();
+        }
+        let tree = StoredMerkleTree::with_store(SimpleHasher::new(), store);
+        assert!(tree.get_root().is_none());
+        assert_eq!(
+            tree.prove(1),
+            Err(MerkleTreeError::MissingHash { level: 0, index: 1 })
+        );
+    }
+
+    /// A `MemoryNodeStore` where one write, counted from 0, fails.
+    struct FailingStore {
+        inner: MemoryNodeStore,
+        writes: usize,
+        fail_at: Option<usize>,
+    }
+
+    impl FailingStore {
+        fn spend(&mut self) -> Result<(), MerkleTreeError> {
+            let write = self.writes;
+            self.writes += 1;
+            if self.fail_at == Some(write) {
+                return Err(MerkleTreeError::Io {
+                    message: "disk full".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    impl NodeStore for FailingStore {
+        fn get_hash(&self, level: u32, index: u64) -> Option<[u8; 32]> {
+            self.inner.get_hash(level, index)
+        }
+
+        fn put_hash(
+            &mut self,
+            level: u32,
+            index: u64,
+            hash: [u8; 32],
+        ) -> Result<(), MerkleTreeError> {
+            self.spend()?;
+            self.inner.put_hash(level, index, hash)
+        }
+
+        fn get_leaf(&self, index: u64) -> Option<&[u8]> {
+            self.inner.get_leaf(index)
+        }
+
+        fn put_leaf(&mut self, index: u64, data: &[u8]) -> Result<(), MerkleTreeError> {
+            self.spend()?;
+            self.inner.put_leaf(index, data)
+        }
+
+        fn leaf_count(&self) -> u64 {
+            self.inner.leaf_count()
+        }
+    }
+
+    #[test]
+    fn test_failed_write_keeps_size_and_root() {
+        // Appending to 3 leaves writes 3 hashes and the leaf; fail each step
+        for fail_at in 0..4 {
+            let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+            for i in 0..3 {
+                tree.add_leaf(&leaf(i)).unwrap();
+            }
+            let root = tree.get_root_bytes();
+            let mut tree = StoredMerkleTree::with_store(
+                SimpleHasher::new(),
+                FailingStore {
+                    inner: tree.into_store(),
+                    writes: 0,
+                    fail_at: Some(fail_at),
+                },
+            );
+
+            assert!(matches!(
+                tree.add_leaf(&leaf(3)),
+                Err(MerkleTreeError::Io { .. })
+            ));
+            assert_eq!(tree.get_size(), 3, "failed write {}", fail_at);
+            assert_eq!(tree.get_root_bytes(), root, "failed write {}", fail_at);
+            for i in 0..3 {
+                let proof = tree.prove(i).unwrap();
+                assert!(tree.verify(&proof, &leaf(i), &root.unwrap()));
+            }
+
+            // The tree keeps working once writes succeed again
+            tree.add_leaf(&leaf(3)).unwrap();
+            let mut expected = StoredMerkleTree::new(SimpleHasher::new());
+            for i in 0..4 {
+                expected.add_leaf(&leaf(i)).unwrap();
+            }
+            assert_eq!(tree.get_root_bytes(), expected.get_root_bytes());
+        }
+    }
+
+    // =========================================================================
+    // Hash-Only Tests
+    // =========================================================================
+
+    #[test]
+    fn test_hash_only_matches_simple_tree() {
+        let hasher = Sha256Hasher::new();
+        let mut by_hash = StoredMerkleTree::hash_only(Sha256Hasher::new());
+        let mut by_data = StoredMerkleTree::hash_only(Sha256Hasher::new());
+        let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..9 {
+            by_hash.add_leaf_hash(hasher.hash_leaf(&leaf(i))).unwrap();
+            by_data.add_leaf(&leaf(i)).unwrap();
+            simple.add_leaf(&leaf(i)).unwrap();
+        }
+        assert_eq!(by_hash.get_root_bytes(), simple.get_root_bytes());
+        assert_eq!(by_data.get_root_bytes(), simple.get_root_bytes());
+        assert_eq!(by_hash.get_size(), 9);
+        assert!(by_hash.get_data(0).is_none());
+        assert!(by_data.get_data(0).is_none());
+
+        let root = by_hash.get_root_bytes().unwrap();
+        let proof = by_hash.prove(7).unwrap();
+        assert_eq!(proof, simple.prove(7).unwrap());
+        assert!(by_hash.verify(&proof, &leaf(7), &root));
+    }
+
+    #[test]
+    fn test_update_leaf_hash() {
+        let hasher = SimpleHasher::new();
+        let mut tree = StoredMerkleTree::hash_only(SimpleHasher::new());
+        let mut simple = SimpleMerkleTree::new(SimpleHasher::new());
+        for i in 0..5 {
+            let data = leaf(i);
+            tree.add_leaf_hash(hasher.hash_leaf(&data)).unwrap();
+            simple
+                .add_leaf(if i == 2 { b"new" } else { &data })
+                .unwrap();
+        }
+        tree.update_leaf_hash(2, hasher.hash_leaf(b"new")).unwrap();
+        assert_eq!(tree.get_root_bytes(), simple.get_root_bytes());
+        assert_eq!(
+            tree.update_leaf_hash(5, [0u8; 32]),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 5,
+                tree_size: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_opaque_leaf_hashes() {
+        let mut tree = StoredMerkleTree::hash_only(Sha256Hasher::new());
+        for i in 0..4u8 {
+            tree.add_leaf_hash([i; 32]).unwrap();
+        }
+        let root = tree.get_root_bytes().unwrap();
+        let proof = tree.prove(3).unwrap();
+        let hasher = Sha256Hasher::new();
+        assert_eq!(
+            verify_proof_from_hash(&[3u8; 32], &proof, &root, &hasher),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_proof_from_hash(&[2u8; 32], &proof, &root, &hasher),
+            Ok(false)
+        );
+    }
+
+    // =========================================================================
+    // Pruning Tests
+    // =========================================================================
+
+    #[test]
+    fn test_prune_keeps_proofs_of_retained_leaves() {
+        for before in [0, 1, 5, 8, 12, 13] {
+            let mut stored = StoredMerkleTree::new(Sha256Hasher::new());
+            let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+            for i in 0..13 {
+                stored.add_leaf(&leaf(i)).unwrap();
+                simple.add_leaf(&leaf(i)).unwrap();
+            }
+            stored.prune(before).unwrap();
+            assert_eq!(stored.pruned_before(), before);
+            assert_eq!(stored.get_root_bytes(), simple.get_root_bytes());
+            for i in before..13 {
+                assert_eq!(
+                    stored.prove(i),
+                    simple.prove(i),
+                    "before {} index {}",
+                    before,
+                    i
+                );
+                assert_eq!(stored.get_data(i), simple.get_data(i));
+            }
+
+            // Appending still reproduces the full tree
+            for i in 13..40 {
+                stored.add_leaf(&leaf(i)).unwrap();
+                simple.add_leaf(&leaf(i)).unwrap();
+                assert_eq!(stored.get_root_bytes(), simple.get_root_bytes());
+            }
+            for i in before..40 {
+                assert_eq!(stored.prove(i), simple.prove(i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pruned_leaf_errors() {
+        let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+        for i in 0..10 {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+        tree.prune(6).unwrap();
+        let pruned = Err(MerkleTreeError::Pruned {
+            index: 5,
+            first_retained: 6,
+        });
+        assert_eq!(tree.prove(5), pruned);
+        assert_eq!(tree.update_leaf(5, b"x"), pruned.map(|_| ()));
+        assert!(tree.get_data(5).is_none());
+        assert_eq!(tree.get_size(), 10);
+
+        // Updating a retained leaf still works
+        let mut simple = SimpleMerkleTree::new(SimpleHasher::new());
+        for i in 0..10 {
+            let data = leaf(i);
+            simple.add_leaf(if i == 6 { b"x" } else { &data }).unwrap();
+        }
+        tree.update_leaf(6, b"x").unwrap();
+        assert_eq!(tree.get_root_bytes(), simple.get_root_bytes());
+    }
+
+    #[test]
+    fn test_prune_bounds() {
+        let mut tree = StoredMerkleTree::new(SimpleHasher::new());
+        for i in 0..4 {
+            tree.add_leaf(&leaf(i)).unwrap();
+        }
+        assert_eq!(
+            tree.prune(5),
+            Err(MerkleTreeError::InvalidIndex {
+                index: 5,
+                tree_size: 4
+            })
+        );
+        tree.prune(3).unwrap();
+        tree.prune(1).unwrap();
+        assert_eq!(tree.pruned_before(), 3);
+    }
+
+    #[test]
+    fn test_prune_hash_only() {
+        let hasher = Sha256Hasher::new();
+        let mut tree = StoredMerkleTree::hash_only(Sha256Hasher::new());
+        let mut simple = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..9 {
+            tree.add_leaf_hash(hasher.hash_leaf(&leaf(i))).unwrap();
+            simple.add_leaf(&leaf(i)).unwrap();
+        }
+        tree.prune(7).unwrap();
+        assert_eq!(tree.pruned_before(), 7);
+        assert_eq!(tree.prove(8), simple.prove(8));
+        assert_eq!(
+            tree.update_leaf_hash(2, [0u8; 32]),
+            Err(MerkleTreeError::Pruned {
+                index: 2,
+                first_retained: 7
+            })
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::hasher::Hasher;
+use crate::merkle::diff::{child_indices, covered_leaves, same_subtree};
+use crate::merkle::snapshot::tree_height;
+use crate::merkle::sync::{SyncError, SyncMessage};
+use crate::merkle::{MerkleTree, MerkleTreeError, SimpleMerkleTree};
+
+/// Where the client is in the protocol.
+enum State {
+    /// `start` has not been called, or the previous run failed.
+    Idle,
+    AwaitSummary,
+    AwaitHashes {
+        level: u32,
+        indices: Vec<u64>,
+    },
+    AwaitLeaves {
+        indices: Vec<u64>,
+    },
+    Done,
+}
+
+/// The pulling side of the sync protocol.
+///
+/// Drives the exchange with a `SyncServer` and updates the local tree so
+/// that it ends up with exactly the server's leaves. Call `start` for the
+/// first request, then feed each response to `handle` and send whatever it
+/// returns until it returns `None`.
+///
+/// Nodes are compared and expanded with the same helpers as
+/// `SimpleMerkleTree::diff`: a pair of subtrees is treated as equal only
+/// when both their hashes and the number of leaves they cover match, since
+/// the duplication of the last node on odd levels lets trees of different
+/// sizes share a hash.
+///
+/// The size announced by the server cannot be checked until the leaves
+/// arrive, so each request is limited to `max_batch` indices (see
+/// `with_max_batch`). A run that would need more, such as a server
+/// claiming far more leaves than it has, fails with `SyncError::Malformed`.
+pub struct SyncClient<'a, H: Hasher> {
+    tree: &'a mut SimpleMerkleTree<H>,
+    /// Node hashes of the local tree, by level.
+    levels: Vec<Vec<[u8; 32]>>,
+    state: State,
+    remote_size: u64,
+    remote_root: Option<[u8; 32]>,
+    max_batch: usize,
+}
+
+impl<'a, H: Hasher> SyncClient<'a, H> {
+    /// The default limit on the number of indices in one request.
+    pub const DEFAULT_MAX_BATCH: usize = 1 << 20;
+
+    pub fn new(tree: &'a mut SimpleMerkleTree<H>) -> Self {
+        Self {
+            tree,
+            levels: Vec::new(),
+            state: State::Idle,
+            remote_size: 0,
+            remote_root: None,
+            max_batch: Self::DEFAULT_MAX_BATCH,
+        }
+    }
+
+    /// Limit the number of node or leaf indices requested in one round.
+    ///
+    /// Bounds the memory a peer can make the client use. Raise it to sync
+    /// trees that differ in more than `DEFAULT_MAX_BATCH` leaves.
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch;
+        self
+    }
+
+    /// Begin a sync run and return the first encoded request.
+    pub fn start(&mut self) -> Vec<u8> {
+        self.levels = self.tree.level_hashes();
+        self.state = State::AwaitSummary;
+        SyncMessage::GetSummary.encode()
+    }
+
+    /// Returns true once the local tree matches the server's tree.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Handle an encoded response.
+    ///
+    /// Returns the next encoded request, or `None` when the sync is
+    /// complete. Returns `SyncError::RootMismatch` if the updated tree does
+    /// not match the root the server announced.
+    pub fn handle(&mut self, response: &[u8]) -> Result<Option<Vec<u8>>, SyncError> {
+        let message = SyncMessage::decode(response)?;
+        let result = self.step(message);
+        if result.is_err() {
+            self.state = State::Idle;
+        }
+        result
+    }
+
+    fn step(&mut self, message: SyncMessage) -> Result<Option<Vec<u8>>, SyncError> {
+        match (std::mem::replace(&mut self.state, State::Idle), message) {
+            (State::AwaitSummary, SyncMessage::Summary { size, root }) => {
+                // Only an empty tree has no root
+                if (size == 0) != root.is_none() || usize::try_from(size).is_err() {
+                    return Err(SyncError::Malformed);
+                }
+                self.remote_size = size;
+                self.remote_root = root;
+                if size == self.tree.get_size() && root == self.tree.get_root_bytes() {
+                    self.state = State::Done;
+                    return Ok(None);
+                }
+                if size == 0 {
+                    return self.finish(Vec::new());
+                }
+                // The roots differ, so start from the root's children
+                self.descend(tree_height(size), vec![0]).map(Some)
+            }
+            (State::AwaitHashes { level, indices }, SyncMessage::Hashes { level: l, hashes })
+                if l == level && hashes.len() == indices.len() =>
+            {
+                let mismatched: Vec<u64> = indices
+                    .into_iter()
+                    .zip(&hashes)
+                    .filter(|(index, hash)| !self.local_matches(level, *index, hash))
+                    .map(|(index, _)| index)
+                    .collect();
+                if mismatched.is_empty() {
+                    return self.finish(Vec::new());
+                }
+                self.descend(level, mismatched).map(Some)
+            }
+            (State::AwaitLeaves { indices }, SyncMessage::Leaves { leaves })
+                if leaves.len() == indices.len() =>
+            {
+                let updates = indices
+                    .into_iter()
+                    .map(|index| usize::try_from(index).map_err(|_| SyncError::Malformed))
+                    .zip(leaves)
+                    .map(|(index, leaf)| index.map(|index| (index, leaf)))
+                    .collect::<Result<_, _>>()?;
+                self.finish(updates)
+            }
+            _ => Err(SyncError::UnexpectedMessage),
+        }
+    }
+
+    /// Build the request for the children of the mismatched nodes.
+    ///
+    /// Returns `SyncError::Malformed` if the request would exceed
+    /// `max_batch` indices.
+    fn descend(&mut self, level: u32, mismatched: Vec<u64>) -> Result<Vec<u8>, SyncError> {
+        if level == 0 {
+            if mismatched.len() > self.max_batch {
+                return Err(SyncError::Malformed);
+            }
+            let request = SyncMessage::GetLeaves {
+                indices: mismatched.clone(),
+            };
+            self.state = State::AwaitLeaves {
+                indices: mismatched,
+            };
+            return Ok(request.encode());
+        }
+
+        let child_level = level - 1;
+        let indices: Vec<u64> = child_indices(self.remote_size, level, &mismatched)
+            .take(self.max_batch.saturating_add(1))
+            .collect();
+        if indices.len() > self.max_batch {
+            return Err(SyncError::Malformed);
+        }
+        let request = SyncMessage::GetHashes {
+            level: child_level,
+            indices: indices.clone(),
+        };
+        self.state = State::AwaitHashes {
+            level: child_level,
+            indices,
+        };
+        Ok(request.encode())
+    }
+
+    /// Check whether the local node at `level` and `index` matches a remote hash.
+    fn local_matches(&self, level: u32, index: u64, hash: &[u8; 32]) -> bool {
+        let local_hash = self
+            .levels
+            .get(level as usize)
+            .and_then(|nodes| nodes.get(usize::try_from(index).ok()?));
+        local_hash.is_some_and(|local_hash| {
+            same_subtree(
+                local_hash,
+                covered_leaves(self.tree.get_size(), level, index),
+                hash,
+                covered_leaves(self.remote_size, level, index),
+            )
+        })
+    }
+
+    /// Apply the fetched leaves and resize the tree, if the result matches
+    /// the remote root.
+    ///
+    /// The local tree is only replaced once the new root has been checked,
+    /// so a forged or inconsistent response leaves it unchanged.
+    fn finish(&mut self, updates: Vec<(usize, Vec<u8>)>) -> Result<Option<Vec<u8>>, SyncError> {
+        let size = usize::try_from(self.remote_size).map_err(|_| SyncError::Malformed)?;
+        self.tree
+            .apply_leaves_if_root(updates, size, self.remote_root)
+            .map_err(|err| match err {
+                MerkleTreeError::RootMismatch => SyncError::RootMismatch,
+                _ => SyncError::Malformed,
+            })?;
+        self.state = State::Done;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::sync::SyncServer;
+
+    fn tree(leaves: &[&[u8]]) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for leaf in leaves {
+            tree.add_leaf(leaf).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_equal_trees_finish_after_summary() {
+        let remote = tree(&[b"a", b"b"]);
+        let mut local = tree(&[b"a", b"b"]);
+        let server = SyncServer::new(&remote);
+        let mut client = SyncClient::new(&mut local);
+
+        let request = client.start();
+        let response = server.handle(&request).unwrap();
+        assert_eq!(client.handle(&response), Ok(None));
+        assert!(client.is_done());
+    }
+
+    #[test]
+    fn test_requests_only_mismatched_children() {
+        let remote = tree(&[b"a", b"b", b"c", b"d"]);
+        let mut local = tree(&[b"a", b"b", b"c", b"x"]);
+        let server = SyncServer::new(&remote);
+        let mut client = SyncClient::new(&mut local);
+
+        let response = server.handle(&client.start()).unwrap();
+        let request = client.handle(&response).unwrap().unwrap();
+        assert_eq!(
+            SyncMessage::decode(&request).unwrap(),
+            SyncMessage::GetHashes {
+                level: 1,
+                indices: vec![0, 1],
+            }
+        );
+
+        let response = server.handle(&request).unwrap();
+        let request = client.handle(&response).unwrap().unwrap();
+        assert_eq!(
+            SyncMessage::decode(&request).unwrap(),
+            SyncMessage::GetHashes {
+                level: 0,
+                indices: vec![2, 3],
+            }
+        );
+
+        let response = server.handle(&request).unwrap();
+        let request = client.handle(&response).unwrap().unwrap();
+        assert_eq!(
+            SyncMessage::decode(&request).unwrap(),
+            SyncMessage::GetLeaves { indices: vec![3] }
+        );
+    }
+
+    #[test]
+    fn test_rejects_out_of_order_response() {
+        let mut local = tree(&[b"a"]);
+        let mut client = SyncClient::new(&mut local);
+        client.start();
+
+        let response = SyncMessage::Leaves { leaves: vec![] }.encode();
+        assert_eq!(client.handle(&response), Err(SyncError::UnexpectedMessage));
+    }
+
+    #[test]
+    fn test_rejects_response_before_start() {
+        let mut local = tree(&[b"a"]);
+        let mut client = SyncClient::new(&mut local);
+        let response = SyncMessage::Summary {
+            size: 0,
+            root: None,
+        }
+        .encode();
+        assert_eq!(client.handle(&response), Err(SyncError::UnexpectedMessage));
+    }
+
+    #[test]
+    fn test_detects_forged_leaves() {
+        let remote = tree(&[b"a", b"b"]);
+        let mut local = tree(&[b"a", b"x", b"c"]);
+        let local_root = local.get_root_bytes();
+        let server = SyncServer::new(&remote);
+        let mut client = SyncClient::new(&mut local);
+
+        let mut request = client.start();
+        loop {
+            let response = match SyncMessage::decode(&request).unwrap() {
+                SyncMessage::GetLeaves { .. } => SyncMessage::Leaves {
+                    leaves: vec![b"forged".to_vec()],
+                }
+                .encode(),
+                _ => server.handle(&request).unwrap(),
+            };
+            match client.handle(&response) {
+                Ok(Some(next)) => request = next,
+                Ok(None) => panic!("forged leaves were accepted"),
+                Err(err) => {
+                    assert_eq!(err, SyncError::RootMismatch);
+                    break;
+                }
+            }
+        }
+        assert!(!client.is_done());
+
+        // The replica keeps its own leaves rather than the forged ones
+        assert_eq!(local.get_size(), 3);
+        assert_eq!(local.get_root_bytes(), local_root);
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_summary() {
+        for (size, root) in [(0, Some([7u8; 32])), (3, None)] {
+            let mut local = tree(&[b"a"]);
+            let mut client = SyncClient::new(&mut local);
+            client.start();
+            let response = SyncMessage::Summary { size, root }.encode();
+            assert_eq!(client.handle(&response), Err(SyncError::Malformed));
+        }
+    }
+
+    #[test]
+    fn test_fan_out_is_bounded() {
+        // A server claiming 2^60 leaves whose hashes never match
+        let mut local = tree(&[b"a"]);
+        let mut client = SyncClient::new(&mut local).with_max_batch(64);
+        client.start();
+        let mut response = SyncMessage::Summary {
+            size: 1 << 60,
+            root: Some([7u8; 32]),
+        }
+        .encode();
+        let mut rounds = 0;
+        let err = loop {
+            let request = match client.handle(&response) {
+                Ok(Some(next)) => next,
+                Ok(None) => panic!("sync finished against a forged size"),
+                Err(err) => break err,
+            };
+            let SyncMessage::GetHashes { level, indices } = SyncMessage::decode(&request).unwrap()
+            else {
+                panic!("expected a hash request");
+            };
+            assert!(indices.len() <= 64);
+            response = SyncMessage::Hashes {
+                level,
+                hashes: vec![[9u8; 32]; indices.len()],
+            }
+            .encode();
+            rounds += 1;
+        };
+        assert_eq!(err, SyncError::Malformed);
+        assert_eq!(rounds, 6);
+    }
+
+    #[test]
+    fn test_forged_empty_summary_keeps_the_replica() {
+        let mut local = tree(&[b"a", b"b"]);
+        let local_root = local.get_root_bytes();
+        let mut client = SyncClient::new(&mut local);
+        client.start();
+
+        let response = SyncMessage::Summary {
+            size: 0,
+            root: Some([7u8; 32]),
+        }
+        .encode();
+        assert!(client.handle(&response).is_err());
+        assert_eq!(local.get_size(), 2);
+        assert_eq!(local.get_root_bytes(), local_root);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::hasher::Hasher;
+use crate::merkle::SimpleMerkleTree;
+use crate::merkle::sync::{SyncClient, SyncError, SyncServer};
+
+/// Traffic counters for a completed sync run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Number of request/response exchanges.
+    pub round_trips: usize,
+    /// Total size of the encoded requests.
+    pub bytes_sent: usize,
+    /// Total size of the encoded responses.
+    pub bytes_received: usize,
+}
+
+/// Sync `local` with `remote` in-process, passing encoded messages directly
+/// between a `SyncClient` and a `SyncServer`.
+///
+/// Intended for tests and for replicas living in the same process; over a
+/// network, run the two state machines on either end of the channel.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::merkle::sync::sync_loopback;
+/// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+///
+/// let mut remote = SimpleMerkleTree::new(Sha256Hasher::new());
+/// let mut local = SimpleMerkleTree::new(Sha256Hasher::new());
+/// remote.add_leaf(b"a").unwrap();
+/// remote.add_leaf(b"b").unwrap();
+/// local.add_leaf(b"a").unwrap();
+///
+/// sync_loopback(&mut local, &remote).unwrap();
+/// assert_eq!(local.get_root(), remote.get_root());
+/// ```
+pub fn sync_loopback<H: Hasher>(
+    local: &mut SimpleMerkleTree<H>,
+    remote: &SimpleMerkleTree<H>,
+) -> Result<SyncStats, SyncError> {
+    let server = SyncServer::new(remote);
+    let mut client = SyncClient::new(local);
+    let mut stats = SyncStats::default();
+
+    let mut request = Some(client.start());
+    while let Some(bytes) = request {
+        let response = server.handle(&bytes)?;
+        stats.round_trips += 1;
+        stats.bytes_sent += bytes.len();
+        stats.bytes_received += response.len();
+        request = client.handle(&response)?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+    use crate::merkle::MerkleTree;
+
+    fn numbered(count: u32, changed: &[u32]) -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for i in 0..count {
+            let data = if changed.contains(&i) {
+                format!("changed{}", i)
+            } else {
+                format!("leaf{}", i)
+            };
+            tree.add_leaf(data.as_bytes()).unwrap();
+        }
+        tree
+    }
+
+    fn assert_synced(
+        local: &SimpleMerkleTree<Sha256Hasher>,
+        remote: &SimpleMerkleTree<Sha256Hasher>,
+    ) {
+        assert_eq!(local.get_size(), remote.get_size());
+        assert_eq!(local.get_root(), remote.get_root());
+        for i in 0..remote.get_size() {
+            assert_eq!(local.get_data(i), remote.get_data(i));
+        }
+    }
+
+    // =========================================================================
+    // Convergence Tests
+    // =========================================================================
+
+    #[test]
+    fn test_sync_changed_leaves() {
+        let remote = numbered(37, &[3, 20, 36]);
+        let mut local = numbered(37, &[]);
+        let stats = sync_loopback(&mut local, &remote).unwrap();
+        assert_synced(&local, &remote);
+        // Summary, one round per level below the root, then the leaves
+        assert_eq!(stats.round_trips, 1 + 6 + 1);
+    }
+
+    #[test]
+    fn test_sync_grows_and_shrinks() {
+        for (local_size, remote_size) in [(0, 5), (5, 0), (3, 17), (17, 3), (1, 2), (8, 9)] {
+            let remote = numbered(remote_size, &[]);
+            let mut local = numbered(local_size, &[0]);
+            sync_loopback(&mut local, &remote).unwrap();
+            assert_synced(&local, &remote);
+        }
+    }
+
+    #[test]
+    fn test_sync_duplicated_last_leaf() {
+        // Same root, different sizes
+        let remote = numbered(3, &[]);
+        let mut local = numbered(3, &[]);
+        local.add_leaf(b"leaf2").unwrap();
+        assert_eq!(local.get_root(), remote.get_root());
+
+        sync_loopback(&mut local, &remote).unwrap();
+        assert_synced(&local, &remote);
+    }
+
+    #[test]
+    fn test_sync_is_idempotent() {
+        let remote = numbered(10, &[4]);
+        let mut local = numbered(10, &[]);
+        sync_loopback(&mut local, &remote).unwrap();
+        let stats = sync_loopback(&mut local, &remote).unwrap();
+        assert_eq!(stats.round_trips, 1);
+    }
+
+    // =========================================================================
+    // Efficiency Tests
+    // =========================================================================
+
+    #[test]
+    fn test_single_change_transfers_little() {
+        let remote = numbered(256, &[100]);
+        let mut local = numbered(256, &[]);
+        let stats = sync_loopback(&mut local, &remote).unwrap();
+        assert_synced(&local, &remote);
+
+        // Two hashes per level, far less than 256 leaves
+        assert!(stats.bytes_received < 8 * 2 * 33 + 100);
+    }
+}
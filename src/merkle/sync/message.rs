@@ -0,0 +1,233 @@
+use crate::merkle::codec::{Reader, put_bytes, put_varint};
+use crate::merkle::sync::SyncError;
+
+const TAG_GET_SUMMARY: u8 = 0;
+const TAG_SUMMARY: u8 = 1;
+const TAG_GET_HASHES: u8 = 2;
+const TAG_HASHES: u8 = 3;
+const TAG_GET_LEAVES: u8 = 4;
+const TAG_LEAVES: u8 = 5;
+
+/// A message of the sync protocol.
+///
+/// Messages are encoded as a one-byte tag followed by their fields, using
+/// varints for integers and length prefixes for lists and leaf data.
+///
+/// # Example
+///
+/// ```
+/// use merkle_trees::merkle::sync::SyncMessage;
+///
+/// let message = SyncMessage::GetHashes { level: 2, indices: vec![0, 1] };
+/// let bytes = message.encode();
+/// assert_eq!(SyncMessage::decode(&bytes).unwrap(), message);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncMessage {
+    /// Ask for the size and root of the server's tree.
+    GetSummary,
+    /// The size and root of the server's tree.
+    Summary { size: u64, root: Option<[u8; 32]> },
+    /// Ask for the hashes of the nodes at the given level and indices.
+    GetHashes { level: u32, indices: Vec<u64> },
+    /// The requested node hashes, in request order.
+    Hashes { level: u32, hashes: Vec<[u8; 32]> },
+    /// Ask for the data of the leaves at the given indices.
+    GetLeaves { indices: Vec<u64> },
+    /// The requested leaf data, in request order. Leaves are never empty.
+    Leaves { leaves: Vec<Vec<u8>> },
+}
+
+impl SyncMessage {
+    /// Encode the message to bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            SyncMessage::GetSummary => out.push(TAG_GET_SUMMARY),
+            SyncMessage::Summary { size, root } => {
+                out.push(TAG_SUMMARY);
+                put_varint(&mut out, *size);
+                match root {
+                    Some(root) => {
+                        out.push(1);
+                        out.extend_from_slice(root);
+                    }
+                    None => out.push(0),
+                }
+            }
+            SyncMessage::GetHashes { level, indices } => {
+                out.push(TAG_GET_HASHES);
+                put_varint(&mut out, *level as u64);
+                put_varint(&mut out, indices.len() as u64);
+                for index in indices {
+                    put_varint(&mut out, *index);
+                }
+            }
+            SyncMessage::Hashes { level, hashes } => {
+                out.push(TAG_HASHES);
+                put_varint(&mut out, *level as u64);
+                put_varint(&mut out, hashes.len() as u64);
+                for hash in hashes {
+                    out.extend_from_slice(hash);
+                }
+            }
+            SyncMessage::GetLeaves { indices } => {
+                out.push(TAG_GET_LEAVES);
+                put_varint(&mut out, indices.len() as u64);
+                for index in indices {
+                    put_varint(&mut out, *index);
+                }
+            }
+            SyncMessage::Leaves { leaves } => {
+                out.push(TAG_LEAVES);
+                put_varint(&mut out, leaves.len() as u64);
+                for leaf in leaves {
+                    put_bytes(&mut out, leaf);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a message from bytes.
+    ///
+    /// Returns `SyncError::Malformed` for unknown tags, truncated input,
+    /// trailing bytes, or an empty leaf.
+    pub fn decode(bytes: &[u8]) -> Result<Self, SyncError> {
+        let mut reader = Reader::new(bytes);
+        let message = Self::read(&mut reader).ok_or(SyncError::Malformed)?;
+        if !reader.is_empty() {
+            return Err(SyncError::Malformed);
+        }
+        Ok(message)
+    }
+
+    fn read(reader: &mut Reader) -> Option<Self> {
+        let message = match reader.u8()? {
+            TAG_GET_SUMMARY => SyncMessage::GetSummary,
+            TAG_SUMMARY => {
+                let size = reader.varint()?;
+                let root = match reader.u8()? {
+                    0 => None,
+                    1 => Some(reader.hash()?),
+                    _ => return None,
+                };
+                SyncMessage::Summary { size, root }
+            }
+            TAG_GET_HASHES => {
+                let level = u32::try_from(reader.varint()?).ok()?;
+                let indices = read_indices(reader)?;
+                SyncMessage::GetHashes { level, indices }
+            }
+            TAG_HASHES => {
+                let level = u32::try_from(reader.varint()?).ok()?;
+                let count = reader.count(32)?;
+                let hashes = (0..count).map(|_| reader.hash()).collect::<Option<_>>()?;
+                SyncMessage::Hashes { level, hashes }
+            }
+            TAG_GET_LEAVES => SyncMessage::GetLeaves {
+                indices: read_indices(reader)?,
+            },
+            TAG_LEAVES => {
+                // A leaf takes a length byte and at least one data byte
+                let count = reader.count(2)?;
+                let leaves = (0..count)
+                    .map(|_| {
+                        let data = reader.bytes()?;
+                        (!data.is_empty()).then(|| data.to_vec())
+                    })
+                    .collect::<Option<_>>()?;
+                SyncMessage::Leaves { leaves }
+            }
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+fn read_indices(reader: &mut Reader) -> Option<Vec<u64>> {
+    let count = reader.count(1)?;
+    (0..count).map(|_| reader.varint()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: SyncMessage) {
+        let bytes = message.encode();
+        assert_eq!(SyncMessage::decode(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn test_round_trip_all_messages() {
+        round_trip(SyncMessage::GetSummary);
+        round_trip(SyncMessage::Summary {
+            size: 0,
+            root: None,
+        });
+        round_trip(SyncMessage::Summary {
+            size: 1 << 40,
+            root: Some([7u8; 32]),
+        });
+        round_trip(SyncMessage::GetHashes {
+            level: 3,
+            indices: vec![0, 1, 300],
+        });
+        round_trip(SyncMessage::Hashes {
+            level: 0,
+            hashes: vec![[1u8; 32], [2u8; 32]],
+        });
+        round_trip(SyncMessage::GetLeaves {
+            indices: vec![u64::MAX],
+        });
+        round_trip(SyncMessage::Leaves {
+            leaves: vec![b"a".to_vec(), vec![0u8; 200]],
+        });
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_tag() {
+        assert_eq!(SyncMessage::decode(&[42]), Err(SyncError::Malformed));
+        assert_eq!(SyncMessage::decode(&[]), Err(SyncError::Malformed));
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_input() {
+        let bytes = SyncMessage::Hashes {
+            level: 1,
+            hashes: vec![[1u8; 32]],
+        }
+        .encode();
+        for len in 0..bytes.len() {
+            assert_eq!(
+                SyncMessage::decode(&bytes[..len]),
+                Err(SyncError::Malformed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_trailing_bytes() {
+        let mut bytes = SyncMessage::GetSummary.encode();
+        bytes.push(0);
+        assert_eq!(SyncMessage::decode(&bytes), Err(SyncError::Malformed));
+    }
+
+    #[test]
+    fn test_decode_rejects_empty_leaf() {
+        let bytes = SyncMessage::Leaves {
+            leaves: vec![b"a".to_vec(), Vec::new()],
+        }
+        .encode();
+        assert_eq!(SyncMessage::decode(&bytes), Err(SyncError::Malformed));
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_root_flag() {
+        assert_eq!(
+            SyncMessage::decode(&[TAG_SUMMARY, 0, 2]),
+            Err(SyncError::Malformed)
+        );
+    }
+}
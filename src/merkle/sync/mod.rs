@@ -0,0 +1,75 @@
+//! Anti-entropy sync between two replicas of a `SimpleMerkleTree`.
+//!
+//! A `SyncClient` brings its local tree in line with the tree served by a
+//! `SyncServer`. The client compares root summaries, then descends the
+//! tree one level per round trip, asking only for the hashes under
+//! subtrees that differ. It compares nodes by the same rule as
+//! `SimpleMerkleTree::diff`, one level of the walk per round trip. Once it reaches the leaves it fetches the
+//! differing leaves and resizes its tree to match.
+//!
+//! Both sides are transport-agnostic state machines that consume and
+//! produce encoded messages, so they can run over any byte channel. For
+//! in-process use and testing, `sync_loopback` connects the two directly.
+//!
+//! # Protocol
+//!
+//! ```text
+//! client                          server
+//!   GetSummary               ->
+//!                            <-   Summary { size, root }
+//!   GetHashes { level, [i] } ->
+//!                            <-   Hashes { level, [hash] }
+//!   ...one round per level...
+//!   GetLeaves { [i] }        ->
+//!                            <-   Leaves { [data] }
+//! ```
+
+pub mod client;
+pub mod loopback;
+pub mod message;
+pub mod server;
+
+pub use client::SyncClient;
+pub use loopback::{SyncStats, sync_loopback};
+pub use message::SyncMessage;
+pub use server::SyncServer;
+
+/// Errors that can occur while running the sync protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncError {
+    /// A message could not be decoded.
+    Malformed,
+    /// A message was valid but not expected in the current state.
+    UnexpectedMessage,
+    /// A request referred to nodes or leaves the tree does not have.
+    InvalidRequest,
+    /// The synced tree does not match the root announced by the server.
+    RootMismatch,
+}
+
+impl std::fmt::Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::Malformed => write!(f, "malformed sync message"),
+            SyncError::UnexpectedMessage => write!(f, "unexpected sync message"),
+            SyncError::InvalidRequest => write!(f, "request refers to missing nodes"),
+            SyncError::RootMismatch => write!(f, "synced root does not match the remote root"),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_error_display() {
+        assert_eq!(SyncError::Malformed.to_string(), "malformed sync message");
+        assert_eq!(
+            SyncError::RootMismatch.to_string(),
+            "synced root does not match the remote root"
+        );
+    }
+}
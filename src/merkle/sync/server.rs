@@ -0,0 +1,157 @@
+use crate::hasher::Hasher;
+use crate::merkle::sync::{SyncError, SyncMessage};
+use crate::merkle::{MerkleTree, SimpleMerkleTree};
+
+/// The serving side of the sync protocol.
+///
+/// Answers requests about a tree it borrows. Node hashes for every level
+/// are computed once when the server is created.
+pub struct SyncServer<'a, H: Hasher> {
+    tree: &'a SimpleMerkleTree<H>,
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl<'a, H: Hasher> SyncServer<'a, H> {
+    pub fn new(tree: &'a SimpleMerkleTree<H>) -> Self {
+        Self {
+            tree,
+            levels: tree.level_hashes(),
+        }
+    }
+
+    /// Handle an encoded request and return the encoded response.
+    ///
+    /// Returns `SyncError::InvalidRequest` if the request refers to nodes or
+    /// leaves the tree does not have, and `SyncError::UnexpectedMessage` if
+    /// it is a response rather than a request.
+    pub fn handle(&self, request: &[u8]) -> Result<Vec<u8>, SyncError> {
+        let response = match SyncMessage::decode(request)? {
+            SyncMessage::GetSummary => SyncMessage::Summary {
+                size: self.tree.get_size(),
+                root: self.tree.get_root_bytes(),
+            },
+            SyncMessage::GetHashes { level, indices } => {
+                let nodes = self
+                    .levels
+                    .get(level as usize)
+                    .ok_or(SyncError::InvalidRequest)?;
+                let hashes = indices
+                    .iter()
+                    .map(|&index| usize::try_from(index).ok().and_then(|i| nodes.get(i)))
+                    .map(|hash| hash.copied().ok_or(SyncError::InvalidRequest))
+                    .collect::<Result<_, _>>()?;
+                SyncMessage::Hashes { level, hashes }
+            }
+            SyncMessage::GetLeaves { indices } => {
+                let leaves = indices
+                    .iter()
+                    .map(|&index| self.tree.get_data(index).map(<[u8]>::to_vec))
+                    .collect::<Option<_>>()
+                    .ok_or(SyncError::InvalidRequest)?;
+                SyncMessage::Leaves { leaves }
+            }
+            _ => return Err(SyncError::UnexpectedMessage),
+        };
+        Ok(response.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::Sha256Hasher;
+
+    fn server_tree() -> SimpleMerkleTree<Sha256Hasher> {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        for data in [b"a", b"b", b"c"] {
+            tree.add_leaf(data).unwrap();
+        }
+        tree
+    }
+
+    fn ask(server: &SyncServer<Sha256Hasher>, request: SyncMessage) -> SyncMessage {
+        SyncMessage::decode(&server.handle(&request.encode()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_summary() {
+        let tree = server_tree();
+        let server = SyncServer::new(&tree);
+        assert_eq!(
+            ask(&server, SyncMessage::GetSummary),
+            SyncMessage::Summary {
+                size: 3,
+                root: tree.get_root_bytes(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_hashes_at_root_level() {
+        let tree = server_tree();
+        let server = SyncServer::new(&tree);
+        let response = ask(
+            &server,
+            SyncMessage::GetHashes {
+                level: 2,
+                indices: vec![0],
+            },
+        );
+        assert_eq!(
+            response,
+            SyncMessage::Hashes {
+                level: 2,
+                hashes: vec![tree.get_root_bytes().unwrap()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_leaves() {
+        let tree = server_tree();
+        let server = SyncServer::new(&tree);
+        let response = ask(
+            &server,
+            SyncMessage::GetLeaves {
+                indices: vec![2, 0],
+            },
+        );
+        assert_eq!(
+            response,
+            SyncMessage::Leaves {
+                leaves: vec![b"c".to_vec(), b"a".to_vec()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_rejects_missing_nodes() {
+        let tree = server_tree();
+        let server = SyncServer::new(&tree);
+        let requests = [
+            SyncMessage::GetHashes {
+                level: 3,
+                indices: vec![0],
+            },
+            SyncMessage::GetHashes {
+                level: 1,
+                indices: vec![2],
+            },
+            SyncMessage::GetLeaves { indices: vec![3] },
+        ];
+        for request in requests {
+            assert_eq!(
+                server.handle(&request.encode()),
+                Err(SyncError::InvalidRequest)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_responses() {
+        let tree = server_tree();
+        let server = SyncServer::new(&tree);
+        let response = SyncMessage::Leaves { leaves: vec![] }.encode();
+        assert_eq!(server.handle(&response), Err(SyncError::UnexpectedMessage));
+    }
+}
@@ -0,0 +1,381 @@
+//! JSON dumps of a `SimpleMerkleTree` for external tools.
+//!
+//! The schema follows the spirit of OpenZeppelin's `StandardMerkleTree`
+//! dump: every hash is a `0x`-prefixed lowercase hex string and the leaf
+//! values are listed next to the tree.
+//!
+//! ```text
+//! {
+//!   "format": "merkle-trees-v1",
+//!   "hasher": "sha2-256",        // display name, informational
+//!   "hashCode": 18,              // multicodec code, null if the hasher has none
+//!   "root": "0x...",             // null for an empty tree
+//!   "levels": [["0x...", ...]],  // level 0 holds the leaf hashes, the last
+//!                                // level is [root]; [] for an empty tree
+//!   "values": [{ "value": "0x...", "leafIndex": 0 }, ...]
+//! }
+//! ```
+//!
+//! A level with an odd number of nodes pairs its last node with itself, as
+//! in the rest of the crate. Loading rebuilds the tree from the values and
+//! checks every level and the root against the dump.
+
+use serde::{Deserialize, Serialize};
+
+use crate::bytes_to_hex;
+use crate::hasher::{HashId, Hasher};
+use crate::merkle::simple_tree::SimpleMerkleTree;
+use crate::merkle::{MerkleTree, MerkleTreeError};
+use crate::serde_hex::hex_to_bytes;
+
+/// Format identifier written to and required in every dump.
+const FORMAT: &str = "merkle-trees-v1";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Dump {
+    format: String,
+    hasher: Option<String>,
+    hash_code: Option<u64>,
+    root: Option<String>,
+    levels: Vec<Vec<String>>,
+    values: Vec<DumpValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DumpValue {
+    value: String,
+    leaf_index: u64,
+}
+
+fn invalid(message: impl Into<String>) -> MerkleTreeError {
+    MerkleTreeError::InvalidDump {
+        message: message.into(),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", bytes_to_hex(bytes))
+}
+
+/// Parse a `0x`-prefixed hex string.
+fn from_hex(hex: &str) -> Result<Vec<u8>, MerkleTreeError> {
+    hex.strip_prefix("0x")
+        .and_then(hex_to_bytes)
+        .ok_or_else(|| invalid(format!("{:?} is not 0x-prefixed hex", hex)))
+}
+
+fn hash_from_hex(hex: &str) -> Result<[u8; 32], MerkleTreeError> {
+    from_hex(hex)?
+        .try_into()
+        .map_err(|_| invalid(format!("{:?} is not a 32-byte hash", hex)))
+}
+
+impl<H: Hasher> SimpleMerkleTree<H> {
+    /// Export the tree as a pretty-printed JSON dump.
+    ///
+    /// The dump lists the leaf values, the hashes of every level and the
+    /// root, with the hasher's name and multicodec code; the schema is
+    /// described in the README.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+    ///
+    /// let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+    /// tree.add_leaf(b"a").unwrap();
+    /// tree.add_leaf(b"b").unwrap();
+    ///
+    /// let json = tree.dump();
+    /// assert!(json.contains("\"format\": \"merkle-trees-v1\""));
+    /// let loaded = SimpleMerkleTree::load_dump(&json, Sha256Hasher::new()).unwrap();
+    /// assert_eq!(loaded.get_root(), tree.get_root());
+    /// ```
+    pub fn dump(&self) -> String {
+        let hash_id = self.hasher().hash_id();
+        let dump = Dump {
+            format: FORMAT.to_string(),
+            hasher: hash_id.map(|id| id.to_string()),
+            hash_code: hash_id.map(|id| id.code()),
+            root: self.get_root_bytes().map(|root| to_hex(&root)),
+            levels: self
+                .level_hashes()
+                .iter()
+                .map(|level| level.iter().map(|hash| to_hex(hash)).collect())
+                .collect(),
+            values: self
+                .leaves()
+                .iter()
+                .enumerate()
+                .map(|(index, leaf)| DumpValue {
+                    value: to_hex(leaf.data()),
+                    leaf_index: index as u64,
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&dump).expect("dump serializes to JSON")
+    }
+
+    /// Import a tree from a JSON dump written by `dump`.
+    ///
+    /// The tree is rebuilt from the values with `hasher`, and the dump must
+    /// agree with it everywhere. Returns:
+    /// - `MerkleTreeError::InvalidDump` for malformed JSON, an unknown
+    ///   format, bad hex, or values out of order
+    /// - `MerkleTreeError::HasherMismatch` if the dump and the hasher both
+    ///   have a `HashId` and they differ; if either is missing, a wrong
+    ///   hasher shows up as a `CorruptedNode` instead
+    /// - `MerkleTreeError::EmptyInput` if a value is empty
+    /// - `MerkleTreeError::CorruptedNode` for the first level hash that
+    ///   differs from the rebuilt tree
+    /// - `MerkleTreeError::RootMismatch` if the root differs
+    pub fn load_dump(json: &str, hasher: H) -> Result<Self, MerkleTreeError> {
+        let dump: Dump = serde_json::from_str(json).map_err(|err| invalid(err.to_string()))?;
+        if dump.format != FORMAT {
+            return Err(invalid(format!("unsupported format {:?}", dump.format)));
+        }
+        // As with tree files, an unidentified hasher is caught by the hashes
+        if let (Some(dumped), Some(loading)) = (dump.hash_code.map(HashId::new), hasher.hash_id())
+            && dumped != loading
+        {
+            return Err(MerkleTreeError::HasherMismatch {
+                proof: dumped,
+                verifier: loading,
+            });
+        }
+
+        let mut leaves = Vec::with_capacity(dump.values.len());
+        for (position, value) in dump.values.iter().enumerate() {
+            if value.leaf_index != position as u64 {
+                return Err(invalid(format!(
+                    "value {} has leafIndex {}",
+                    position, value.leaf_index
+                )));
+            }
+            let data = from_hex(&value.value)?;
+            if data.is_empty() {
+                return Err(MerkleTreeError::EmptyInput);
+            }
+            leaves.push((position, data));
+        }
+        let root = dump.root.as_deref().map(hash_from_hex).transpose()?;
+
+        let mut tree = Self::new(hasher);
+        tree.apply_leaves(leaves, 0)?;
+
+        let levels = tree.level_hashes();
+        if dump.levels.len() != levels.len() {
+            return Err(invalid(format!(
+                "expected {} levels, found {}",
+                levels.len(),
+                dump.levels.len()
+            )));
+        }
+        for (level, (dumped, rebuilt)) in dump.levels.iter().zip(&levels).enumerate() {
+            if dumped.len() != rebuilt.len() {
+                return Err(invalid(format!(
+                    "level {} should have {} hashes, found {}",
+                    level,
+                    rebuilt.len(),
+                    dumped.len()
+                )));
+            }
+            for (index, (hex, hash)) in dumped.iter().zip(rebuilt).enumerate() {
+                if hash_from_hex(hex)? != *hash {
+                    return Err(MerkleTreeError::CorruptedNode {
+                        level: level as u32,
+                        index: index as u64,
+                    });
+                }
+            }
+        }
+        if tree.get_root_bytes() != root {
+            return Err(MerkleTreeError::RootMismatch);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Sha256Hasher, SimpleHasher};
+    use crate::merkle::tests::tree_of;
+    use serde_json::{Value, json};
+
+    fn edit(json: &str, change: impl FnOnce(&mut Value)) -> String {
+        let mut value: Value = serde_json::from_str(json).unwrap();
+        change(&mut value);
+        value.to_string()
+    }
+
+    fn load(json: &str) -> Result<SimpleMerkleTree<Sha256Hasher>, MerkleTreeError> {
+        SimpleMerkleTree::load_dump(json, Sha256Hasher::new())
+    }
+
+    // =========================================================================
+    // Schema Tests
+    // =========================================================================
+
+    #[test]
+    fn test_dump_schema() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"\x01\x02").unwrap();
+        let hasher = Sha256Hasher::new();
+        let leaf_hash = to_hex(&hasher.hash_leaf(b"\x01\x02"));
+
+        let value: Value = serde_json::from_str(&tree.dump()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "format": "merkle-trees-v1",
+                "hasher": "sha2-256",
+                "hashCode": 18,
+                "root": leaf_hash,
+                "levels": [[leaf_hash]],
+                "values": [{ "value": "0x0102", "leafIndex": 0 }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_dump_levels() {
+        let tree = tree_of(3);
+        let value: Value = serde_json::from_str(&tree.dump()).unwrap();
+        let widths: Vec<usize> = value["levels"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|level| level.as_array().unwrap().len())
+            .collect();
+        assert_eq!(widths, [3, 2, 1]);
+        assert_eq!(value["levels"][2][0], value["root"]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for count in [0, 1, 2, 5, 8, 17] {
+            let tree = tree_of(count);
+            let loaded = load(&tree.dump()).unwrap();
+            assert_eq!(loaded.get_root_bytes(), tree.get_root_bytes());
+            assert_eq!(loaded.get_size(), count);
+            for i in 0..count {
+                assert_eq!(loaded.get_data(i), tree.get_data(i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let value: Value = serde_json::from_str(&tree_of(0).dump()).unwrap();
+        assert_eq!(value["root"], Value::Null);
+        assert_eq!(value["levels"], json!([]));
+        assert_eq!(value["values"], json!([]));
+    }
+
+    // =========================================================================
+    // Import Validation Tests
+    // =========================================================================
+
+    #[test]
+    fn test_rejects_malformed_dump() {
+        assert!(matches!(
+            load("{"),
+            Err(MerkleTreeError::InvalidDump { .. })
+        ));
+        let json = tree_of(2).dump();
+        let extra = edit(&json, |v| v["extra"] = json!(1));
+        assert!(matches!(
+            load(&extra),
+            Err(MerkleTreeError::InvalidDump { .. })
+        ));
+        let format = edit(&json, |v| v["format"] = json!("standard-v1"));
+        assert_eq!(
+            load(&format).err(),
+            Some(MerkleTreeError::InvalidDump {
+                message: "unsupported format \"standard-v1\"".to_string()
+            })
+        );
+        let no_prefix = edit(&json, |v| v["values"][0]["value"] = json!("6c65"));
+        assert_eq!(
+            load(&no_prefix).err(),
+            Some(MerkleTreeError::InvalidDump {
+                message: "\"6c65\" is not 0x-prefixed hex".to_string()
+            })
+        );
+        let empty = edit(&json, |v| v["values"][1]["value"] = json!("0x"));
+        assert_eq!(load(&empty).err(), Some(MerkleTreeError::EmptyInput));
+    }
+
+    #[test]
+    fn test_rejects_other_hasher() {
+        let json = tree_of(2).dump();
+        assert_eq!(
+            SimpleMerkleTree::load_dump(&json, SimpleHasher::new()).err(),
+            Some(MerkleTreeError::HasherMismatch {
+                proof: HashId::SHA2_256,
+                verifier: HashId::SIMPLE,
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_hash_code_falls_back_to_hash_checks() {
+        let json = edit(&tree_of(3).dump(), |v| v["hashCode"] = json!(null));
+        let tree = load(&json).unwrap();
+        assert_eq!(tree.get_root(), tree_of(3).get_root());
+
+        assert_eq!(
+            SimpleMerkleTree::load_dump(&json, SimpleHasher::new()).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 0, index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_values_out_of_order() {
+        let json = edit(&tree_of(2).dump(), |v| {
+            v["values"][1]["leafIndex"] = json!(5);
+        });
+        assert_eq!(
+            load(&json).err(),
+            Some(MerkleTreeError::InvalidDump {
+                message: "value 1 has leafIndex 5".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_detects_inconsistent_tree() {
+        let json = tree_of(5).dump();
+
+        let changed_value = edit(&json, |v| v["values"][3]["value"] = json!("0xff"));
+        assert_eq!(
+            load(&changed_value).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 0, index: 3 })
+        );
+
+        let changed_hash = edit(&json, |v| v["levels"][1][2] = json!(to_hex(&[0u8; 32])));
+        assert_eq!(
+            load(&changed_hash).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 1, index: 2 })
+        );
+
+        let changed_root = edit(&json, |v| v["root"] = json!(to_hex(&[0u8; 32])));
+        assert_eq!(
+            load(&changed_root).err(),
+            Some(MerkleTreeError::RootMismatch)
+        );
+
+        let missing_level = edit(&json, |v| {
+            v["levels"].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            load(&missing_level).err(),
+            Some(MerkleTreeError::InvalidDump {
+                message: "expected 4 levels, found 3".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Saving and loading a `SimpleMerkleTree`.
+//!
+//! The format is compact and versioned (integers are LEB128 varints):
+//!
+//! ```text
+//! version: u8 (= 1)
+//! flags: u8, bit 0 set if level hashes follow the root
+//! hash id: varint multicodec code of the hasher, 0 if it has none
+//! leaf count: varint
+//! leaves: count * (varint length, data)
+//! root: 32 bytes, absent when the tree is empty
+//! levels: for each level below the root, from the leaves up,
+//!         ceil(count / 2^level) hashes of 32 bytes
+//! ```
+//!
+//! Loading always rebuilds the tree from the leaves and checks the result
+//! against the stored root. Saved level hashes make the file larger but let
+//! `load` name the first node that does not match, instead of only
+//! reporting that the root differs. The hash id lets `load` tell a file
+//! saved with another hasher apart from a damaged one.
+
+use std::io::{Read, Write};
+
+use crate::hasher::{HashId, Hasher};
+use crate::merkle::codec::{Reader, put_bytes, put_varint, read_varint};
+use crate::merkle::simple_tree::SimpleMerkleTree;
+use crate::merkle::{MerkleTree, MerkleTreeError};
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Flag for files that store the hashes of every level below the root.
+const FLAG_LEVELS: u8 = 0x01;
+
+impl<H: Hasher> SimpleMerkleTree<H> {
+    /// Write the leaves and root to `writer`.
+    ///
+    /// Data is written in many small pieces, so wrap files in a
+    /// `BufWriter`. I/O failures are returned as `MerkleTreeError::Io`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use merkle_trees::{MerkleTree, Sha256Hasher, SimpleMerkleTree};
+    ///
+    /// let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+    /// tree.add_leaf(b"a").unwrap();
+    ///
+    /// let mut file = Vec::new();
+    /// tree.save(&mut file).unwrap();
+    /// let loaded = SimpleMerkleTree::load(file.as_slice(), Sha256Hasher::new()).unwrap();
+    /// assert_eq!(loaded.get_root(), tree.get_root());
+    /// ```
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), MerkleTreeError> {
+        self.write_file(writer, false)
+    }
+
+    /// Write the leaves, root and the hashes of every level below the root.
+    pub fn save_with_levels<W: Write>(&self, writer: W) -> Result<(), MerkleTreeError> {
+        self.write_file(writer, true)
+    }
+
+    /// Read a tree written by `save` or `save_with_levels`.
+    ///
+    /// The tree is rebuilt with `hasher`, which must be the hasher the tree
+    /// was saved with. Returns `MerkleTreeError::HasherMismatch` if the file
+    /// and `hasher` both have a `HashId` and they differ,
+    /// `MerkleTreeError::CorruptedNode` if a saved level hash differs from
+    /// the rebuilt one, `RootMismatch` if the root does, and the same
+    /// decoding errors as `Proof::from_bytes` for a malformed file.
+    pub fn load<R: Read>(mut reader: R, hasher: H) -> Result<Self, MerkleTreeError> {
+        let mut file = Vec::new();
+        reader.read_to_end(&mut file)?;
+        let mut reader = Reader::new(&file);
+
+        let version = reader.u8().ok_or(MerkleTreeError::TruncatedInput)?;
+        if version != FORMAT_VERSION {
+            return Err(MerkleTreeError::UnsupportedVersion { version });
+        }
+        let flags = reader.u8().ok_or(MerkleTreeError::TruncatedInput)?;
+        if flags & !FLAG_LEVELS != 0 {
+            return Err(MerkleTreeError::InvalidEncoding);
+        }
+        let saved_id = match read_varint(&mut reader)? {
+            0 => None,
+            code => Some(HashId::new(code)),
+        };
+        if let (Some(saved), Some(loading)) = (saved_id, hasher.hash_id())
+            && saved != loading
+        {
+            return Err(MerkleTreeError::HasherMismatch {
+                proof: saved,
+                verifier: loading,
+            });
+        }
+
+        // Every leaf takes at least two bytes: its length and one byte of data
+        let count = read_varint(&mut reader)?;
+        if count.saturating_mul(2) > reader.rest().len() as u64 {
+            return Err(MerkleTreeError::TruncatedInput);
+        }
+        let mut leaves = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = read_varint(&mut reader)?;
+            let data = usize::try_from(len)
+                .ok()
+                .and_then(|len| reader.take(len))
+                .ok_or(MerkleTreeError::TruncatedInput)?;
+            if data.is_empty() {
+                return Err(MerkleTreeError::EmptyInput);
+            }
+            leaves.push(data.to_vec());
+        }
+
+        let root = if count > 0 {
+            Some(reader.hash().ok_or(MerkleTreeError::TruncatedInput)?)
+        } else {
+            None
+        };
+
+        let mut stored_levels = Vec::new();
+        if flags & FLAG_LEVELS != 0 {
+            let mut width = count;
+            while width > 1 {
+                let level = (0..width)
+                    .map(|_| reader.hash().ok_or(MerkleTreeError::TruncatedInput))
+                    .collect::<Result<Vec<_>, _>>()?;
+                stored_levels.push(level);
+                width = width.div_ceil(2);
+            }
+        }
+
+        if !reader.is_empty() {
+            return Err(MerkleTreeError::TrailingBytes {
+                count: reader.rest().len(),
+            });
+        }
+
+        let mut tree = SimpleMerkleTree::new(hasher);
+        tree.apply_leaves(leaves.into_iter().enumerate().collect(), 0)?;
+
+        if !stored_levels.is_empty() {
+            let levels = tree.level_hashes();
+            for (level, (stored, rebuilt)) in stored_levels.iter().zip(&levels).enumerate() {
+                if let Some(index) = stored.iter().zip(rebuilt).position(|(s, r)| s != r) {
+                    return Err(MerkleTreeError::CorruptedNode {
+                        level: level as u32,
+                        index: index as u64,
+                    });
+                }
+            }
+        }
+        if tree.get_root_bytes() != root {
+            return Err(MerkleTreeError::RootMismatch);
+        }
+        Ok(tree)
+    }
+
+    fn write_file<W: Write>(
+        &self,
+        mut writer: W,
+        with_levels: bool,
+    ) -> Result<(), MerkleTreeError> {
+        let mut header = vec![FORMAT_VERSION, if with_levels { FLAG_LEVELS } else { 0 }];
+        put_varint(
+            &mut header,
+            self.hasher().hash_id().map_or(0, |id| id.code()),
+        );
+        put_varint(&mut header, self.get_size());
+        writer.write_all(&header)?;
+
+        let mut scratch = Vec::new();
+        for leaf in self.leaves() {
+            scratch.clear();
+            put_bytes(&mut scratch, leaf.data());
+            writer.write_all(&scratch)?;
+        }
+        if let Some(root) = self.get_root_bytes() {
+            writer.write_all(&root)?;
+        }
+
+        if with_levels {
+            let levels = self.level_hashes();
+            // The last level is the root, which is already written
+            for level in levels.iter().take(levels.len().saturating_sub(1)) {
+                for hash in level {
+                    writer.write_all(hash)?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{PersonalizedHasher, Sha256Hasher, SimpleHasher};
+    use crate::merkle::hash::Hash;
+    use crate::merkle::tests::tree_of;
+
+    fn saved(tree: &SimpleMerkleTree<Sha256Hasher>, with_levels: bool) -> Vec<u8> {
+        let mut file = Vec::new();
+        if with_levels {
+            tree.save_with_levels(&mut file).unwrap();
+        } else {
+            tree.save(&mut file).unwrap();
+        }
+        file
+    }
+
+    fn load(file: &[u8]) -> Result<SimpleMerkleTree<Sha256Hasher>, MerkleTreeError> {
+        SimpleMerkleTree::load(file, Sha256Hasher::new())
+    }
+
+    /// A writer that fails after accepting `remaining` bytes.
+    struct FailingWriter {
+        remaining: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(std::io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // =========================================================================
+    // Round Trip Tests
+    // =========================================================================
+
+    #[test]
+    fn test_round_trip() {
+        for count in [0, 1, 2, 3, 7, 8, 33] {
+            let tree = tree_of(count);
+            for with_levels in [false, true] {
+                let loaded = load(&saved(&tree, with_levels)).unwrap();
+                assert_eq!(loaded.get_size(), count);
+                assert_eq!(loaded.get_root(), tree.get_root());
+                if count > 0 {
+                    assert_eq!(loaded.prove(count - 1), tree.prove(count - 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_encoding() {
+        let mut tree = SimpleMerkleTree::new(Sha256Hasher::new());
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"bc").unwrap();
+        let root = tree.get_root_bytes().unwrap();
+
+        let mut expected = vec![0x01, 0x00, 0x12, 0x02, 0x01, b'a', 0x02, b'b', b'c'];
+        expected.extend_from_slice(&root);
+        assert_eq!(saved(&tree, false), expected);
+
+        // With levels, the two leaf hashes follow the root
+        let with_levels = saved(&tree, true);
+        assert_eq!(with_levels[1], FLAG_LEVELS);
+        assert_eq!(with_levels.len(), expected.len() + 64);
+        assert_eq!(
+            &with_levels[expected.len()..][..32],
+            tree.leaves()[0].hash()
+        );
+    }
+
+    #[test]
+    fn test_empty_tree_encoding() {
+        assert_eq!(
+            saved(&tree_of(0), true),
+            vec![0x01, FLAG_LEVELS, 0x12, 0x00]
+        );
+    }
+
+    // =========================================================================
+    // Integrity Tests
+    // =========================================================================
+
+    #[test]
+    fn test_corrupted_leaf_is_detected() {
+        let tree = tree_of(4);
+        let mut file = saved(&tree, false);
+        // The first leaf's data starts after the header and its length
+        file[5] ^= 0x01;
+        assert_eq!(load(&file).err(), Some(MerkleTreeError::RootMismatch));
+
+        let mut file = saved(&tree, true);
+        file[5] ^= 0x01;
+        assert_eq!(
+            load(&file).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 0, index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_corrupted_level_hash_is_detected() {
+        let tree = tree_of(5);
+        let mut file = saved(&tree, true);
+        // Levels hold 5, 3 and 2 hashes; flip a byte in level 1, node 2
+        let offset = file.len() - (2 + 1) * 32;
+        file[offset] ^= 0x80;
+        assert_eq!(
+            load(&file).err(),
+            Some(MerkleTreeError::CorruptedNode { level: 1, index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_corrupted_root_is_detected() {
+        let tree = tree_of(3);
+        let mut file = saved(&tree, false);
+        let last = file.len() - 1;
+        file[last] ^= 0x01;
+        assert_eq!(load(&file).err(), Some(MerkleTreeError::RootMismatch));
+    }
+
+    #[test]
+    fn test_wrong_hasher_is_detected() {
+        let file = saved(&tree_of(2), false);
+        assert_eq!(
+            SimpleMerkleTree::load(file.as_slice(), SimpleHasher::new()).err(),
+            Some(MerkleTreeError::HasherMismatch {
+                proof: HashId::SHA2_256,
+                verifier: HashId::SIMPLE
+            })
+        );
+    }
+
+    #[test]
+    fn test_unidentified_hasher_falls_back_to_root_check() {
+        // Neither side can be identified, so only the root catches it
+        let tagged = |tag: &[u8]| PersonalizedHasher::new(Sha256Hasher::new(), tag);
+        let mut tree = SimpleMerkleTree::new(tagged(b"a"));
+        tree.add_leaf(b"a").unwrap();
+        tree.add_leaf(b"b").unwrap();
+        let mut file = Vec::new();
+        tree.save(&mut file).unwrap();
+        assert_eq!(file[2], 0x00);
+
+        let loaded = SimpleMerkleTree::load(file.as_slice(), tagged(b"a")).unwrap();
+        assert_eq!(loaded.get_root(), tree.get_root());
+        assert_eq!(
+            SimpleMerkleTree::load(file.as_slice(), tagged(b"b")).err(),
+            Some(MerkleTreeError::RootMismatch)
+        );
+    }
+
+    // =========================================================================
+    // Malformed File Tests
+    // =========================================================================
+
+    #[test]
+    fn test_rejects_bad_header() {
+        assert_eq!(load(&[]).err(), Some(MerkleTreeError::TruncatedInput));
+        assert_eq!(
+            load(&[2, 0, 0]).err(),
+            Some(MerkleTreeError::UnsupportedVersion { version: 2 })
+        );
+        assert_eq!(
+            load(&[1, 0x02, 0]).err(),
+            Some(MerkleTreeError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_rejects_truncated_file() {
+        let file = saved(&tree_of(3), true);
+        for len in 0..file.len() {
+            assert_eq!(
+                load(&file[..len]).err(),
+                Some(MerkleTreeError::TruncatedInput),
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_trailing_bytes() {
+        let mut file = saved(&tree_of(2), false);
+        file.push(0);
+        assert_eq!(
+            load(&file).err(),
+            Some(MerkleTreeError::TrailingBytes { count: 1 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_huge_leaf_count() {
+        let mut file = vec![1, 0, 0x12];
+        put_varint(&mut file, u64::MAX);
+        assert_eq!(load(&file).err(), Some(MerkleTreeError::TruncatedInput));
+    }
+
+    #[test]
+    fn test_rejects_empty_leaf() {
+        let file = [1, 0, 0x12, 1, 0, 0];
+        assert_eq!(load(&file).err(), Some(MerkleTreeError::EmptyInput));
+    }
+
+    #[test]
+    fn test_write_failure() {
+        let tree = tree_of(3);
+        let err = tree.save(FailingWriter { remaining: 10 }).unwrap_err();
+        assert_eq!(
+            err,
+            MerkleTreeError::Io {
+                message: "disk full".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Serde helpers for hashes and byte strings.
+//!
+//! Human-readable formats such as JSON get lowercase hex strings; binary
+//! formats such as bincode or CBOR get raw bytes. Use the submodules with
+//! `#[serde(with = "...")]` to store roots the same way in your own types:
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Checkpoint {
+//!     #[serde(with = "merkle_trees::serde_hex::hash")]
+//!     root: [u8; 32],
+//! }
+//!
+//! let json = serde_json::to_string(&Checkpoint { root: [0xab; 32] }).unwrap();
+//! assert_eq!(json, format!("{{\"root\":\"{}\"}}", "ab".repeat(32)));
+//! ```
+
+use crate::bytes_to_hex;
+use crate::merkle::digest32::decode_hex;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
+/// Borrowed bytes that serialize as hex or raw bytes.
+struct HexBytes<'a>(&'a [u8]);
+
+impl Serialize for HexBytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&bytes_to_hex(self.0))
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+/// Owned bytes read back from hex or raw bytes.
+struct HexBuf(Vec<u8>);
+
+impl<'de> de::Deserialize<'de> for HexBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(HexBufVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(HexBufVisitor)
+        }
+    }
+}
+
+struct HexBufVisitor;
+
+impl<'de> Visitor<'de> for HexBufVisitor {
+    type Value = HexBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hex string or a byte array")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<HexBuf, E> {
+        hex_to_bytes(value)
+            .map(HexBuf)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<HexBuf, E> {
+        Ok(HexBuf(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<HexBuf, E> {
+        Ok(HexBuf(value))
+    }
+
+    // Some binary formats hand byte arrays over as sequences
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<HexBuf, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(HexBuf(bytes))
+    }
+}
+
+/// Parse a hex string with an even number of digits, in either case.
+pub(crate) fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut bytes = vec![0u8; hex.len() / 2];
+    decode_hex(hex, 0, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn to_hash<E: de::Error>(buf: HexBuf) -> Result<[u8; 32], E> {
+    let len = buf.0.len();
+    buf.0
+        .try_into()
+        .map_err(|_| E::invalid_length(len, &"32 bytes"))
+}
+
+/// A 32-byte hash.
+pub mod hash {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        HexBytes(hash).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        to_hash(de::Deserialize::deserialize(deserializer)?)
+    }
+}
+
+/// An optional 32-byte hash.
+pub mod hash_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        hash: &Option<[u8; 32]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        hash.as_ref()
+            .map(|hash| HexBytes(hash))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<[u8; 32]>, D::Error> {
+        let buf: Option<HexBuf> = de::Deserialize::deserialize(deserializer)?;
+        buf.map(to_hash).transpose()
+    }
+}
+
+/// A list of 32-byte hashes.
+pub mod hash_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hashes: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(hashes.iter().map(|hash| HexBytes(hash)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; 32]>, D::Error> {
+        let bufs: Vec<HexBuf> = de::Deserialize::deserialize(deserializer)?;
+        bufs.into_iter().map(to_hash).collect()
+    }
+}
+
+/// A list of byte strings of any length.
+pub mod bytes_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(items: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter().map(|item| HexBytes(item)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        let bufs: Vec<HexBuf> = de::Deserialize::deserialize(deserializer)?;
+        Ok(bufs.into_iter().map(|buf| buf.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use serde_test::{Configure, Token, assert_tokens};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    struct Root(#[serde(with = "hash")] [u8; 32]);
+
+    #[test]
+    fn test_hex_to_bytes() {
+        assert_eq!(hex_to_bytes("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(hex_to_bytes(""), Some(vec![]));
+        assert_eq!(hex_to_bytes("abc"), None);
+        assert_eq!(hex_to_bytes("zz"), None);
+        assert_eq!(hex_to_bytes("+1"), None);
+        assert_eq!(hex_to_bytes("é"), None);
+    }
+
+    #[test]
+    fn test_hash_tokens() {
+        let root = Root([0x5a; 32]);
+        let hex: &'static str = "5a".repeat(32).leak();
+        assert_tokens(
+            &root.readable(),
+            &[Token::NewtypeStruct { name: "Root" }, Token::Str(hex)],
+        );
+        assert_tokens(
+            &root.compact(),
+            &[
+                Token::NewtypeStruct { name: "Root" },
+                Token::Bytes(&[0x5a; 32]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_hash_rejects_wrong_length() {
+        let err = serde_json::from_str::<Root>("\"abcd\"").unwrap_err();
+        assert!(err.to_string().contains("invalid length 2"));
+        assert!(serde_json::from_str::<Root>("\"not hex\"").is_err());
+    }
+
+    #[test]
+    fn test_uppercase_hex_is_accepted() {
+        let json = format!("\"{}\"", "AB".repeat(32));
+        assert_eq!(
+            serde_json::from_str::<Root>(&json).unwrap(),
+            Root([0xab; 32])
+        );
+    }
+}